@@ -1,6 +1,32 @@
-mod position;
+pub mod rasterizer;
 
-use position::*;
+/* the shader checks of backend/build.rs, which uses all of them */
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "../../backend/build/shader_validation.rs"]
+mod shader_validation;
+
+/* absolute path of a file of the repository, e.g. "public/data/packs/default/pack.txt" */
+#[cfg(test)]
+fn repo_path(path: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join(path);
+    path.to_str().unwrap().to_string()
+}
+
+/* blocks_atlas.png holds the first textures of BlockSideTexture::ALL, the default pack adds
+ * the others from their own image */
+#[cfg(test)]
+const ATLAS_LAYERS: usize = 16;
+
+#[cfg(test)]
+fn block_image_path(texture: engine_core::world::BlockSideTexture) -> String {
+    repo_path(&format!(
+        "public/data/textures/blocks/{}.png",
+        texture.get_name()
+    ))
+}
 
 #[cfg(test)]
 mod tests {
@@ -8,9 +34,10 @@ mod tests {
 
     use glam::{ivec2, ivec3};
 
-    use crate::{
-        BlockPos, ChunkPos, PageChunkOffset, PagePos, CHUNK_PAGE_SIZE, CHUNK_SIZE, MAX_BLOCK_Y,
-        MAX_CHUNK_Y, MIN_BLOCK_Y, MIN_CHUNK_Y, NUM_CHUNKS_PER_PAGE,
+    use engine_core::world::{
+        BlockPos, BlockTextureLayers, BlockType, Chunk, ChunkPos, LightLevel, PageChunkOffset,
+        PagePos, CHUNK_PAGE_SIZE, CHUNK_SIZE, MAX_BLOCK_Y, MAX_CHUNK_Y, MIN_BLOCK_Y, MIN_CHUNK_Y,
+        NUM_CHUNKS_PER_PAGE, VERTICES_PER_SIDE,
     };
 
     #[test]
@@ -170,6 +197,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_plain_chunk_meshes_its_outside_only() {
+        let chunk = Chunk::plain(BlockType::Stone);
+        let data = chunk.to_vertex_data(&BlockTextureLayers::default(), |_| LightLevel::default());
+        let faces_per_side = CHUNK_SIZE * CHUNK_SIZE;
+        assert_eq!(data.opaque.len(), 6 * faces_per_side * VERTICES_PER_SIDE);
+        assert!(data.cutout.is_empty() && data.translucent.is_empty());
+    }
+
     // #[test]
     // fn test_proper_modulo() {
     //     assert_eq!(4, proper_modulo_i32(-6, 5));
@@ -187,3 +223,1158 @@ mod tests {
     //     assert_eq!(1, proper_modulo_i32(6, 5));
     // }
 }
+
+#[cfg(test)]
+mod light_tests {
+    use engine_core::world::{
+        BlockType, Chunk, ChunkPos, ChunkStreamer, LightStorage, WorldGenerator, CHUNK_SIZE,
+        MAX_BLOCK_Y, MAX_LIGHT,
+    };
+    use glam::{ivec3, IVec3, U16Vec3};
+
+    struct TestWorld(fn(ChunkPos) -> Chunk);
+
+    impl WorldGenerator for TestWorld {
+        fn generate(&mut self, chunk_pos: ChunkPos) -> Chunk {
+            self.0(chunk_pos)
+        }
+    }
+
+    /* a stone layer filling blocks 0 to 15 in y, everything below is a closed cave */
+    fn stone_layer(chunk_pos: ChunkPos) -> Chunk {
+        match chunk_pos.as_vec().y {
+            0 => Chunk::plain(BlockType::Stone),
+            _ => Chunk::empty(),
+        }
+    }
+
+    /* loads the pages around the origin */
+    fn load_world(generate: fn(ChunkPos) -> Chunk) -> ChunkStreamer<TestWorld> {
+        let mut streamer = ChunkStreamer::new(TestWorld(generate));
+        while streamer.tick_streaming(ChunkPos::default()) > 0 {}
+        streamer.take_relit_chunks();
+        streamer
+    }
+
+    fn sky(streamer: &ChunkStreamer<TestWorld>, pos: IVec3) -> u8 {
+        streamer.get_light(pos).sky()
+    }
+
+    fn block(streamer: &ChunkStreamer<TestWorld>, pos: IVec3) -> u8 {
+        streamer.get_light(pos).block()
+    }
+
+    #[test]
+    fn test_skylight_fills_columns_down_to_opaque_blocks() {
+        let streamer = load_world(|chunk_pos| match chunk_pos.as_vec() {
+            // a shaft through the stone layer
+            IVec3 { x: 0, y: 0, z: 0 } => {
+                let mut chunk = Chunk::plain(BlockType::Stone);
+                for y in 0..CHUNK_SIZE {
+                    chunk.set(U16Vec3::new(5, y as _, 5), BlockType::Empty);
+                }
+                chunk
+            }
+            _ => stone_layer(chunk_pos),
+        });
+        assert_eq!(sky(&streamer, ivec3(0, MAX_BLOCK_Y - 1, 0)), MAX_LIGHT);
+        assert_eq!(sky(&streamer, ivec3(0, 16, 0)), MAX_LIGHT);
+        assert_eq!(sky(&streamer, ivec3(30, -1, 0)), 0);
+        // full skylight goes down the shaft, then spreads dimmer around it
+        assert_eq!(sky(&streamer, ivec3(5, 0, 5)), MAX_LIGHT);
+        assert_eq!(sky(&streamer, ivec3(5, -20, 5)), MAX_LIGHT);
+        assert_eq!(sky(&streamer, ivec3(8, -20, 5)), MAX_LIGHT - 3);
+        assert_eq!(sky(&streamer, ivec3(0, -1, 0)), MAX_LIGHT - 10);
+        assert_eq!(sky(&streamer, ivec3(5, -1, 20)), 0);
+        assert!((0..MAX_BLOCK_Y).all(|y| block(&streamer, ivec3(5, y, 5)) == 0));
+    }
+
+    #[test]
+    fn test_lava_light_falls_off_with_distance() {
+        let streamer = load_world(|chunk_pos| match chunk_pos.as_vec() {
+            IVec3 { x: 0, y: 1, z: 0 } => {
+                let mut chunk = Chunk::empty();
+                chunk.set(U16Vec3::new(0, 4, 0), BlockType::Lava);
+                chunk
+            }
+            _ => Chunk::empty(),
+        });
+        let lava = ivec3(0, 20, 0);
+        assert_eq!(block(&streamer, lava), MAX_LIGHT);
+        for offset in [
+            ivec3(3, 0, 0),
+            ivec3(-2, 5, 1),
+            ivec3(0, -14, 0),
+            ivec3(7, 7, 1),
+        ] {
+            let distance = offset.abs().element_sum() as u8;
+            let expected = MAX_LIGHT.saturating_sub(distance);
+            assert_eq!(block(&streamer, lava + offset), expected, "at {offset}");
+        }
+        // skylight is a separate channel
+        assert_eq!(sky(&streamer, lava + IVec3::X), MAX_LIGHT);
+    }
+
+    #[test]
+    fn test_light_crosses_page_borders() {
+        // pages are 6 chunks wide around the origin, a border is between blocks 47 and 48
+        let streamer = load_world(|chunk_pos| match chunk_pos.as_vec() {
+            IVec3 { x: 2, y: -1, z: 0 } | IVec3 { x: -4, y: -1, z: 0 } => {
+                let mut chunk = stone_layer(chunk_pos);
+                chunk.set(U16Vec3::new(13, 4, 0), BlockType::Lava);
+                chunk
+            }
+            _ => stone_layer(chunk_pos),
+        });
+        // the page of x = 45 is loaded first, the one of x = -51 after its neighbour
+        assert_eq!(block(&streamer, ivec3(45, -12, 0)), MAX_LIGHT);
+        assert_eq!(block(&streamer, ivec3(50, -12, 0)), MAX_LIGHT - 5);
+        assert_eq!(block(&streamer, ivec3(-51, -12, 0)), MAX_LIGHT);
+        assert_eq!(block(&streamer, ivec3(-46, -12, 0)), MAX_LIGHT - 5);
+    }
+
+    #[test]
+    fn test_relight_after_placing_and_removing_blocks() {
+        let mut streamer = load_world(stone_layer);
+        let lava = ivec3(0, -5, 0);
+        assert_eq!(block(&streamer, lava + IVec3::X), 0);
+
+        assert!(streamer.set_block(lava.into(), BlockType::Lava));
+        assert_eq!(block(&streamer, lava), MAX_LIGHT);
+        assert_eq!(block(&streamer, lava + ivec3(3, 0, 0)), MAX_LIGHT - 3);
+        assert_eq!(block(&streamer, lava + ivec3(-14, 0, 0)), 1);
+        let relit = streamer.take_relit_chunks();
+        for chunk in [ivec3(0, -1, 0), ivec3(-1, -1, 0), ivec3(0, 0, 0)] {
+            assert!(relit.contains(&chunk.into()), "{chunk} not relit");
+        }
+        assert!(!relit.contains(&ivec3(1, -1, 0).into()));
+
+        assert!(streamer.set_block(lava.into(), BlockType::Empty));
+        assert_eq!(block(&streamer, lava), 0);
+        assert_eq!(block(&streamer, lava + ivec3(3, 0, 0)), 0);
+
+        // digging a shaft lets the sky in, closing it gets the cave dark again
+        let shaft = ivec3(5, 0, 5);
+        for y in 0..CHUNK_SIZE as i32 {
+            streamer.set_block((shaft + IVec3::Y * y).into(), BlockType::Empty);
+        }
+        assert_eq!(sky(&streamer, shaft - IVec3::Y), MAX_LIGHT);
+        assert_eq!(sky(&streamer, shaft + ivec3(2, -1, 0)), MAX_LIGHT - 2);
+        streamer.set_block((shaft + IVec3::Y * 15).into(), BlockType::Stone);
+        assert_eq!(sky(&streamer, shaft), 0);
+        assert_eq!(sky(&streamer, shaft + ivec3(2, -1, 0)), 0);
+    }
+}
+
+#[cfg(test)]
+mod render_tests {
+    use std::{collections::HashSet, rc::Rc};
+
+    use backend::{
+        graphics::{
+            enable_vertex_layout, InstancedRenderer, Material, Mesh, MeshRenderer, RenderTarget,
+            ShaderDef, ShadowMap, UniformValue, VertexAttrType, VertexAttribute, VertexDataType,
+            INSTANCE_TRANSFORM,
+        },
+        utils::{make_quad, GlContext, RecordedObjectType, RecordingDevice},
+    };
+
+    const VERTEX: &str = "#version 300 es
+in vec3 position;
+in vec2 uv;
+uniform mat4 model;
+out vec2 v_uv;
+void main() {
+    v_uv = uv;
+    gl_Position = model * vec4(position, 1.0);
+}
+";
+
+    const FRAGMENT: &str = "#version 300 es
+precision highp float;
+in vec2 v_uv;
+uniform float opacity;
+#ifdef TINTED
+uniform vec3 tint;
+#endif
+out vec4 color;
+void main() {
+    color = vec4(v_uv, 0.0, opacity);
+}
+";
+
+    fn shader_def() -> ShaderDef {
+        ShaderDef::new("test.vert", "test.frag", VERTEX, FRAGMENT, vec![])
+    }
+
+    fn make_renderer(gl: &GlContext) -> MeshRenderer {
+        let program = shader_def().compile_shared(gl).unwrap();
+        let mut renderer = MeshRenderer::new(Material::new(&program));
+        renderer.set_mesh(gl, Rc::new(make_quad())).unwrap();
+        renderer
+    }
+
+    #[test]
+    fn test_mesh_renderer_draws_once() {
+        let device = RecordingDevice::new();
+        let gl = GlContext::new(device.clone());
+        let renderer = make_renderer(&gl);
+        device.clear();
+
+        renderer.render(&gl);
+        let draws = device.get_draws();
+        assert_eq!(draws.len(), 1);
+        assert_eq!(draws[0].mode, glow::TRIANGLE_STRIP);
+        assert_eq!(draws[0].count, 4);
+        assert_eq!(draws[0].program, device.get_bound_program());
+        assert!(draws[0].program.is_some());
+        assert!(draws[0].vertex_array.is_some());
+        assert!(device.is_enabled(glow::DEPTH_TEST));
+        assert_eq!(device.get_errors(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_redundant_state_changes_are_skipped() {
+        let device = RecordingDevice::new();
+        let gl = GlContext::new(device.clone());
+        let renderer = make_renderer(&gl);
+        renderer.render(&gl);
+        device.clear();
+
+        // everything is still bound from the first render
+        renderer.render(&gl);
+        assert_eq!(device.get_draws().len(), 1);
+        assert_eq!(device.get_commands(), vec!["draw_arrays(0x5, 0, 4)"]);
+    }
+
+    #[test]
+    fn test_per_object_values_go_through_their_own_material() {
+        let device = RecordingDevice::new();
+        let gl = GlContext::new(device.clone());
+        let renderer = make_renderer(&gl);
+        let faded = renderer
+            .get_material()
+            .clone()
+            .with_uniform("opacity", UniformValue::Float(0.25))
+            .unwrap();
+        device.clear();
+
+        renderer.render_with(&gl, &faded);
+        let commands = device.get_commands();
+        assert!(commands
+            .iter()
+            .any(|command| command.starts_with("uniform_1_f32(") && command.ends_with(", 0.25)")));
+        assert!(renderer
+            .get_material()
+            .clone()
+            .with_uniform("missing", UniformValue::Float(1.0))
+            .is_err());
+        assert_eq!(device.get_errors(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_released_objects_are_deleted_at_end_of_frame() {
+        let device = RecordingDevice::new();
+        let gl = GlContext::new(device.clone());
+        let renderer = make_renderer(&gl);
+        // shaders are deleted once linked
+        assert_eq!(device.count_live(RecordedObjectType::Shader), 0);
+        assert_eq!(device.count_live(RecordedObjectType::Program), 1);
+        assert_eq!(device.count_live(RecordedObjectType::VertexArray), 1);
+
+        renderer.render(&gl);
+        drop(renderer);
+        // draws queued this frame may still use them
+        assert_eq!(device.count_live(RecordedObjectType::Program), 1);
+        gl.end_frame();
+        assert_eq!(device.get_live_objects(), vec![]);
+        assert_eq!(gl.get_resource_counts().buffers, 0);
+        assert_eq!(device.get_errors(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_framebuffers_are_released_with_their_owner() {
+        let device = RecordingDevice::new();
+        let gl = GlContext::new(device.clone());
+        let target = RenderTarget::new(&gl, 64, 32, glow::RGBA8, true).unwrap();
+        let shadow_map = ShadowMap::new(&gl, 128).unwrap();
+        assert_eq!(gl.get_resource_counts().framebuffers, 2);
+        assert_eq!(device.count_live(RecordedObjectType::Framebuffer), 2);
+
+        drop(target);
+        drop(shadow_map);
+        gl.end_frame();
+        assert_eq!(gl.get_resource_counts().framebuffers, 0);
+        assert_eq!(device.get_live_objects(), vec![]);
+        assert_eq!(device.get_errors(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_defines_select_uniforms() {
+        let gl = GlContext::new(RecordingDevice::new());
+        let has_tint = |def: ShaderDef| {
+            let program = unsafe { def.compile(&gl) }.unwrap();
+            let uniforms = program.get_active_uniforms();
+            uniforms.iter().any(|input| input.name == "tint")
+        };
+        assert!(!has_tint(shader_def()));
+        let tinted = vec![("TINTED", "1")];
+        assert!(has_tint(shader_def().with_defines(tinted)));
+    }
+
+    #[test]
+    fn test_instanced_renderer_draws_every_instance_at_once() {
+        const INSTANCED_VERTEX: &str = "#version 300 es
+in vec3 position;
+in vec4 instance_color;
+in mat4 instance_transform;
+out vec4 v_color;
+void main() {
+    v_color = instance_color;
+    gl_Position = instance_transform * vec4(position, 1.0);
+}
+";
+        const INSTANCED_FRAGMENT: &str = "#version 300 es
+precision highp float;
+in vec4 v_color;
+out vec4 color;
+void main() {
+    color = v_color;
+}
+";
+        let device = RecordingDevice::new();
+        let gl = GlContext::new(device.clone());
+        let def = ShaderDef::new(
+            "instanced.vert",
+            "instanced.frag",
+            INSTANCED_VERTEX,
+            INSTANCED_FRAGMENT,
+            vec![],
+        );
+        let program = def.compile_shared(&gl).unwrap();
+        let mesh = Rc::new(Mesh::from_f32(
+            &[0.0; 6],
+            &[(VertexAttrType::Position, 3)],
+            glow::LINES,
+        ));
+        let make_renderer = |layout: Vec<VertexAttribute>| {
+            let renderer = MeshRenderer::new(Material::new(&program));
+            InstancedRenderer::new(&gl, renderer, mesh.clone(), layout)
+        };
+        assert!(make_renderer(vec![]).is_err());
+
+        device.clear();
+        let layout = vec![
+            VertexAttribute::f32(VertexAttrType::InstanceColor, 4),
+            INSTANCE_TRANSFORM,
+        ];
+        let mut renderer = make_renderer(layout).unwrap();
+        let divisors: HashSet<String> = device
+            .get_commands()
+            .into_iter()
+            .filter(|c| c.starts_with("vertex_attrib_divisor(") && c.ends_with(", 1)"))
+            .collect();
+        // the color and the 4 columns of the transform
+        assert_eq!(divisors.len(), 5);
+
+        let instances = [[1.0f32; 20]; 3];
+        assert!(renderer.set_instances(&gl, &[[1.0f32; 7]]).is_err());
+        renderer.set_instances(&gl, &instances).unwrap();
+        device.clear();
+        renderer.render(&gl);
+        let draws = device.get_draws();
+        assert_eq!(draws.len(), 1);
+        assert_eq!(draws[0].instance_count, 3);
+        assert_eq!(draws[0].mode, glow::LINES);
+        let instanced_calls = device
+            .get_commands()
+            .iter()
+            .filter(|c| c.starts_with("draw_arrays_instanced("))
+            .count();
+        assert_eq!(instanced_calls, 1);
+        assert_eq!(device.get_errors(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_vertex_layout_pointers() {
+        let layout = [
+            VertexAttribute::f32(VertexAttrType::Position, 3),
+            VertexAttribute::f32(VertexAttrType::Normal, 3),
+            VertexAttribute::normalized(VertexAttrType::Color, 4, VertexDataType::U8),
+            VertexAttribute::integer(
+                VertexAttrType::Custom(VertexDataType::I32),
+                1,
+                VertexDataType::I32,
+            ),
+            VertexAttribute::f32_matrix(VertexAttrType::InstanceTransform, 4),
+        ];
+        let device = RecordingDevice::new();
+        let gl = GlContext::new(device.clone());
+        let vao = gl.new_vertex_array().unwrap();
+        gl.set_vertex_array(Some(vao.key()));
+        let vbo = gl.new_buffer().unwrap();
+        unsafe { gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo.key())) };
+        device.clear();
+
+        // normals have no location, they are skipped but still take room
+        enable_vertex_layout(&gl, &layout, 1, |attr_type| match attr_type {
+            VertexAttrType::Position => Some(0),
+            VertexAttrType::Color => Some(1),
+            VertexAttrType::Custom(_) => Some(2),
+            VertexAttrType::InstanceTransform => Some(3),
+            _ => None,
+        });
+        let pointers: Vec<String> = device
+            .get_commands()
+            .into_iter()
+            .filter(|c| c.starts_with("vertex_attrib_pointer"))
+            .collect();
+        let columns = (0..4).map(|i| {
+            let offset = 32 + i * 16;
+            format!(
+                "vertex_attrib_pointer_f32({}, 4, 0x1406, false, 96, {offset})",
+                3 + i
+            )
+        });
+        let expected: Vec<String> = [
+            "vertex_attrib_pointer_f32(0, 3, 0x1406, false, 96, 0)".to_string(),
+            "vertex_attrib_pointer_f32(1, 4, 0x1401, true, 96, 24)".to_string(),
+            "vertex_attrib_pointer_i32(2, 1, 0x1404, 96, 28)".to_string(),
+        ]
+        .into_iter()
+        .chain(columns)
+        .collect();
+        assert_eq!(pointers, expected);
+        let divisors = device
+            .get_commands()
+            .iter()
+            .filter(|c| c.starts_with("vertex_attrib_divisor(") && c.ends_with(", 1)"))
+            .count();
+        assert_eq!(divisors, 7);
+        assert!(device.get_errors().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod world_tests {
+    use backend::{
+        utils::{GlContext, RecordingDevice},
+        world::{TestGenerator, World},
+    };
+    use engine_core::{time::Time, world::CHUNK_SIZE};
+    use fastrand::Rng;
+    use glam::Vec3;
+
+    #[test]
+    fn test_chunk_meshes_stay_within_budget() {
+        let budget = 512 * 1024;
+        let gl = GlContext::new(RecordingDevice::new());
+        let mut world = World::new(TestGenerator {
+            rng: Rng::with_seed(0),
+        });
+        world.set_chunk_memory_budget(budget);
+        for frame in 0..200 {
+            world.update(&gl, &Time::default(), Vec3::ZERO).unwrap();
+            let loaded_bytes = world.get_loaded_bytes();
+            assert!(
+                loaded_bytes <= budget,
+                "frame {frame}: {loaded_bytes} bytes"
+            );
+        }
+        assert!(world.iter_meshed_chunks().count() > 0);
+        assert!(world.get_info().contains("full"), "{}", world.get_info());
+
+        // far enough for every mesh to be out of the load distance, making room for new ones
+        let near_origin: Vec<_> = world.iter_meshed_chunks().collect();
+        let far_away = Vec3::new((40 * CHUNK_SIZE) as f32, 0.0, 0.0);
+        for frame in 0..50 {
+            world.update(&gl, &Time::default(), far_away).unwrap();
+            let loaded_bytes = world.get_loaded_bytes();
+            assert!(
+                loaded_bytes <= budget,
+                "frame {frame} after moving: {loaded_bytes} bytes"
+            );
+        }
+        let meshed: Vec<_> = world.iter_meshed_chunks().collect();
+        assert!(near_origin.iter().any(|pos| !meshed.contains(pos)));
+        assert!(meshed.iter().any(|pos| !near_origin.contains(pos)));
+    }
+}
+
+#[cfg(test)]
+mod golden_tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use backend::graphics::{Lighting, RgbaImage};
+    use engine_core::world::{
+        generate_mesh, sort_faces_back_to_front, BlockSideTexture, BlockTextureLayers, BlockType,
+        Chunk, ChunkSideData, LightLevel, Side, CHUNK_SIZE,
+    };
+    use glam::{vec3, Mat4, U16Vec3, Vec3};
+
+    use crate::{
+        block_image_path,
+        rasterizer::{ChunkPass, ChunkVertex, Image, ReferenceRenderer, TextureArray},
+        repo_path, ATLAS_LAYERS,
+    };
+
+    const SKY_COLOR: [u8; 4] = [140, 180, 230, 255];
+    const SIDES: [Side; 6] = [
+        Side::Top,
+        Side::Bottom,
+        Side::Front,
+        Side::Back,
+        Side::Right,
+        Side::Left,
+    ];
+    /* the atlas layers and the images added on top of it, like the default pack */
+    fn load_block_textures() -> TextureArray {
+        let decode = |path: String| RgbaImage::decode_png(&fs::read(path).unwrap()).unwrap();
+        let atlas = decode(repo_path("public/data/textures/blocks/blocks_atlas.png"));
+        let mut layers = atlas.split_layers(ATLAS_LAYERS as u32).unwrap();
+        let added = &BlockSideTexture::ALL[ATLAS_LAYERS..];
+        layers.extend(
+            added
+                .iter()
+                .map(|texture| decode(block_image_path(*texture))),
+        );
+        TextureArray::new(layers).unwrap()
+    }
+
+    fn perspective(eye: Vec3, target: Vec3) -> Mat4 {
+        let projection = Mat4::perspective_rh_gl(60f32.to_radians(), 1.0, 0.1, 100.0);
+        projection * Mat4::look_at_rh(eye, target, Vec3::Y)
+    }
+
+    fn renderer(size: u32, view_projection: Mat4) -> ReferenceRenderer {
+        let lighting = Lighting::default();
+        ReferenceRenderer::new(
+            size,
+            size,
+            SKY_COLOR,
+            view_projection,
+            lighting,
+            load_block_textures(),
+        )
+    }
+
+    /* compares against golden/<name>.png, rewritten instead when UPDATE_GOLDEN is set. On a
+     * mismatch the render is saved as golden/<name>.actual.png */
+    fn assert_matches_golden(name: &str, image: &Image) {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("golden");
+        let golden_path = directory.join(format!("{name}.png"));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            image.save_png(&golden_path).unwrap();
+            return;
+        }
+        let actual_path: PathBuf = directory.join(format!("{name}.actual.png"));
+        let golden = match Image::load_png(&golden_path) {
+            Ok(golden) => golden,
+            Err(e) => {
+                image.save_png(&actual_path).unwrap();
+                panic!("{e}, run with UPDATE_GOLDEN=1 to create it");
+            }
+        };
+        // a few pixels may round differently with another compiler or CPU
+        let diff = image.diff(&golden, 2);
+        if diff.mismatched > image.pixels.len() / 1000 {
+            image.save_png(&actual_path).unwrap();
+            panic!("{name} differs from its golden image: {diff:?}, see {actual_path:?}");
+        }
+    }
+
+    fn full_light() -> LightLevel {
+        LightLevel::new(15, 0)
+    }
+
+    #[test]
+    fn test_decode_matches_generate_mesh() {
+        let offset = U16Vec3::new(3, 7, 30);
+        let light = LightLevel::new(12, 5);
+        for side in SIDES {
+            let sides = [(side, BlockSideTexture::Gold, offset, light)];
+            let data = generate_mesh(sides, &BlockTextureLayers::default());
+            assert_eq!(data.len(), 6);
+            for vertex in data.iter().map(|data| ChunkVertex::decode(*data)) {
+                let corner = vertex.position - offset.as_vec3();
+                assert!(corner.cmpge(Vec3::ZERO).all() && corner.cmple(Vec3::ONE).all());
+                assert_eq!(vertex.layer, BlockSideTexture::Gold as i32);
+                assert_eq!(vertex.light, glam::vec2(0.8f32.powi(3), 0.8f32.powi(10)));
+            }
+            // all the corners of a face lie on the plane it faces
+            let normal = ChunkVertex::decode(data[0]).normal;
+            let center = offset.as_vec3() + Vec3::splat(0.5);
+            for vertex in data.iter().map(|data| ChunkVertex::decode(*data)) {
+                assert_eq!((vertex.position - center).dot(normal), 0.5, "{side:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_golden_grass_block() {
+        let sides = SIDES.map(|side| {
+            let texture = match side {
+                Side::Top => BlockSideTexture::GrassTop,
+                Side::Bottom => BlockSideTexture::Dirt,
+                _ => BlockSideTexture::GrassSide,
+            };
+            (side, texture, U16Vec3::ZERO, full_light())
+        });
+        let mut renderer = renderer(96, perspective(vec3(2.2, 2.0, 2.6), Vec3::splat(0.5)));
+        let data = generate_mesh(sides, &BlockTextureLayers::default());
+        renderer.draw_chunk_mesh(&data, Vec3::ZERO, ChunkPass::Opaque);
+        assert_matches_golden("grass_block", &renderer.color);
+    }
+
+    #[test]
+    fn test_golden_block_textures() {
+        // a 5x5 grid of top faces seen from above, one per layer, with the first layer in the
+        // -x -z corner, on the top left of the image
+        let sides: Vec<ChunkSideData> = BlockSideTexture::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, texture)| {
+                let offset = U16Vec3::new((i % 5) as u16, 0, (i / 5) as u16);
+                (Side::Top, *texture, offset, full_light())
+            })
+            .collect();
+        let projection = Mat4::orthographic_rh_gl(0.0, 5.0, -5.0, 0.0, 0.1, 10.0);
+        let view = Mat4::look_at_rh(vec3(0.0, 5.0, 0.0), Vec3::ZERO, Vec3::NEG_Z);
+        let mut renderer = renderer(160, projection * view);
+        let data = generate_mesh(sides, &BlockTextureLayers::default());
+        renderer.draw_chunk_mesh(&data, Vec3::ZERO, ChunkPass::Opaque);
+        assert_matches_golden("block_textures", &renderer.color);
+    }
+
+    #[test]
+    fn test_golden_terrain_chunk() {
+        let mut chunk = Chunk::empty();
+        let size = 8;
+        for x in 0..size {
+            for z in 0..size {
+                chunk.set(U16Vec3::new(x, 0, z), BlockType::Stone);
+                chunk.set(U16Vec3::new(x, 1, z), BlockType::Dirt);
+                let top = match (x, z) {
+                    (1..=3, 1..=2) => BlockType::Water,
+                    (5, 5) => BlockType::Lava,
+                    (6, 2) => BlockType::Sand,
+                    _ => BlockType::Grass,
+                };
+                chunk.set(U16Vec3::new(x, 2, z), top);
+            }
+        }
+        chunk.set(U16Vec3::new(5, 3, 2), BlockType::Glass);
+        chunk.set(U16Vec3::new(2, 3, 6), BlockType::Gold);
+        chunk.set(U16Vec3::new(2, 4, 6), BlockType::Diamond);
+        // daylight everywhere, plus block light around the lava
+        let chunk_size = CHUNK_SIZE as u16;
+        for x in 0..chunk_size {
+            for y in 0..chunk_size {
+                for z in 0..chunk_size {
+                    let distance = x.abs_diff(5) + y.abs_diff(3) + z.abs_diff(5);
+                    let block = 15u16.saturating_sub(distance * 3) as u8;
+                    let sky = if (x, z) == (5, 5) { 4 } else { 15 };
+                    chunk.set_light(U16Vec3::new(x, y, z), LightLevel::new(sky, block));
+                }
+            }
+        }
+        let data = chunk.to_vertex_data(&BlockTextureLayers::default(), |_| full_light());
+
+        let eye = vec3(10.5, 8.0, 11.0);
+        let mut renderer = renderer(160, perspective(eye, vec3(4.0, 2.0, 4.0)));
+        renderer.draw_chunk_mesh(&data.opaque, Vec3::ZERO, ChunkPass::Opaque);
+        renderer.draw_chunk_mesh(&data.cutout, Vec3::ZERO, ChunkPass::Cutout(0.5));
+        let mut translucent = data.translucent.clone();
+        sort_faces_back_to_front(&mut translucent, eye);
+        renderer.draw_chunk_mesh(&translucent, Vec3::ZERO, ChunkPass::Translucent(0.6));
+        assert_matches_golden("terrain_chunk", &renderer.color);
+    }
+}
+
+#[cfg(test)]
+mod texture_array_tests {
+    use std::collections::HashMap;
+
+    use backend::{
+        graphics::{RgbaImage, TextureArrayBuilder, TextureArrayLoader, TextureArraySource},
+        utils::{GlContext, RecordedObjectType, RecordingDevice},
+    };
+    use engine_core::world::{
+        generate_mesh, BlockSideTexture, BlockTextureLayers, LightLevel, Side,
+    };
+    use glam::U16Vec3;
+
+    use crate::{block_image_path, rasterizer::ChunkVertex, repo_path, ATLAS_LAYERS};
+
+    fn plain_image(width: u32, height: u32, color: [u8; 4]) -> RgbaImage {
+        let pixels = color.repeat((width * height) as usize);
+        RgbaImage::new(width, height, pixels).unwrap()
+    }
+
+    #[test]
+    fn test_builder_stacks_layers_in_order() {
+        let strip = plain_image(4, 8, [255, 0, 0, 255]);
+        let names = ["a".to_string(), "b".to_string()];
+        let mut builder = TextureArrayBuilder::new();
+        builder.add_strip(&names, &strip).unwrap();
+        // a known name keeps its layer, a new one goes on top
+        assert_eq!(builder.add_image("a", plain_image(4, 4, [0; 4])), Ok(0));
+        assert_eq!(builder.add_image("c", plain_image(4, 4, [0; 4])), Ok(2));
+
+        let device = RecordingDevice::new();
+        let gl = GlContext::new(device.clone());
+        let array = builder.build(&gl).unwrap();
+        let expected: HashMap<String, u32> = [("a", 0), ("b", 1), ("c", 2)]
+            .map(|(n, l)| (n.to_string(), l))
+            .into();
+        assert_eq!(array.layers, expected);
+        assert!(device
+            .get_commands()
+            .contains(&"tex_image_3d(0x8c1a, 0, 4x4x3)".to_string()));
+        assert_eq!(device.count_live(RecordedObjectType::Texture), 1);
+    }
+
+    #[test]
+    fn test_builder_rejects_mismatched_sizes() {
+        let mut builder = TextureArrayBuilder::new()
+            .with_image("a", plain_image(16, 16, [0; 4]))
+            .unwrap();
+        assert!(builder.add_image("b", plain_image(32, 32, [0; 4])).is_err());
+        assert!(builder
+            .add_strip(&["c".to_string()], &plain_image(16, 32, [0; 4]))
+            .is_err());
+        assert!(RgbaImage::new(2, 2, vec![0; 4]).is_err());
+        assert!(plain_image(16, 48, [0; 4]).split_layers(5).is_err());
+
+        let gl = GlContext::new(RecordingDevice::new());
+        assert!(TextureArrayBuilder::new().build(&gl).is_err());
+    }
+
+    #[test]
+    fn test_meshes_use_the_layers_of_the_names() {
+        let layers: HashMap<String, u32> = [("unknown", 3), ("gold_ore", 7)]
+            .map(|(n, l)| (n.to_string(), l))
+            .into();
+        let layers = BlockTextureLayers::from_names(&layers).unwrap();
+        assert_eq!(layers.get_layer(BlockSideTexture::Gold), 7);
+        assert_eq!(layers.get_layer(BlockSideTexture::Dirt), 3);
+
+        let side = (
+            Side::Top,
+            BlockSideTexture::Gold,
+            U16Vec3::ZERO,
+            LightLevel::default(),
+        );
+        let data = generate_mesh([side], &layers);
+        assert!(data.iter().all(|v| ChunkVertex::decode(*v).layer == 7));
+
+        let too_far = HashMap::from([("dirt".to_string(), 64)]);
+        assert!(BlockTextureLayers::from_names(&too_far).is_err());
+    }
+
+    #[test]
+    fn test_loader_assembles_block_images() {
+        let (atlas, added) = BlockSideTexture::ALL.split_at(ATLAS_LAYERS);
+        let names = atlas.iter().map(|t| t.get_name().to_string()).collect();
+        let mut sources = vec![
+            TextureArraySource::Strip {
+                names,
+                path: repo_path("public/data/textures/blocks/blocks_atlas.png"),
+            },
+            TextureArraySource::Image {
+                name: "dirt".to_string(),
+                path: repo_path("public/data/textures/blocks/dirt.png"),
+            },
+        ];
+        sources.extend(added.iter().map(|texture| TextureArraySource::Image {
+            name: texture.get_name().to_string(),
+            path: block_image_path(*texture),
+        }));
+        let loader = TextureArrayLoader::new(sources);
+        // files are read right away natively
+        assert!(loader.is_ready());
+        let gl = GlContext::new(RecordingDevice::new());
+        let array = loader.build(&gl).unwrap().unwrap();
+        assert_eq!(array.layers.len(), BlockSideTexture::ALL.len());
+        let layers = BlockTextureLayers::from_names(&array.layers).unwrap();
+        assert_eq!(layers, BlockTextureLayers::default());
+
+        let missing = TextureArrayLoader::new(vec![TextureArraySource::Image {
+            name: "missing".to_string(),
+            path: repo_path("public/data/textures/blocks/missing.png"),
+        }]);
+        assert!(missing.build(&gl).unwrap().is_err());
+    }
+}
+
+#[cfg(test)]
+mod resource_pack_tests {
+    use std::fs;
+
+    use backend::{
+        graphics::{ResourcePack, ResourcePackLoader, TextureArraySource},
+        utils::{GlContext, RecordingDevice},
+    };
+    use engine_core::world::{BlockSideTexture, BlockTextureLayers};
+
+    use crate::repo_path;
+
+    /* a pack in its own directory, with the files next to its manifest */
+    fn write_pack(name: &str, files: &[(&str, &str)]) -> String {
+        let dir = std::env::temp_dir().join(format!("resource_pack_tests_{name}"));
+        fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), content).unwrap();
+        }
+        dir.join("pack.txt").to_str().unwrap().to_string()
+    }
+
+    fn load_pack(manifest_url: &str) -> Result<backend::graphics::LoadedResourcePack, String> {
+        let gl = GlContext::new(RecordingDevice::new());
+        let mut loader = ResourcePackLoader::new(manifest_url);
+        // files are read right away natively
+        loader.poll(&gl).unwrap()
+    }
+
+    #[test]
+    fn test_parses_pack_manifest() {
+        let manifest = "
+            # comment
+            name = Test pack
+            strip atlas.png = a b
+            texture c = /textures/c.png
+            shader chunk.frag = shaders/chunk.frag
+            color b = #ff8000
+        ";
+        let pack = ResourcePack::parse(manifest, "data/packs/test/pack.txt").unwrap();
+        assert_eq!(pack.name, "Test pack");
+        assert_eq!(
+            pack.textures,
+            vec![
+                TextureArraySource::Strip {
+                    names: vec!["a".to_string(), "b".to_string()],
+                    path: "data/packs/test/atlas.png".to_string(),
+                },
+                TextureArraySource::Image {
+                    name: "c".to_string(),
+                    path: "/textures/c.png".to_string(),
+                },
+            ]
+        );
+        let frag = Some("data/packs/test/shaders/chunk.frag".to_string());
+        assert_eq!(pack.shaders, [None, frag]);
+        assert_eq!(pack.colors, vec![("b".to_string(), [255, 128, 0])]);
+    }
+
+    #[test]
+    fn test_rejects_bad_manifests() {
+        let error = |manifest: &str| ResourcePack::parse(manifest, "pack.txt").unwrap_err();
+        assert!(error("texture a = a.png\nfoo = bar").starts_with("pack.txt:2:"));
+        assert!(error("texture a = a.png\ncolor a = red").starts_with("pack.txt:2:"));
+        assert!(error("shader sky.frag = sky.frag").starts_with("pack.txt:1:"));
+        assert!(error("texture a b = a.png").starts_with("pack.txt:1:"));
+        assert!(error("strip a.png =").starts_with("pack.txt:1:"));
+        // nothing to draw blocks with
+        assert!(error("name = Empty").starts_with("pack.txt:"));
+    }
+
+    #[test]
+    fn test_loads_shipped_packs() {
+        let pack = load_pack(&repo_path("public/data/packs/default/pack.txt")).unwrap();
+        assert_eq!(pack.name, "Default");
+        assert_eq!(pack.shaders, [None, None]);
+        let layers = BlockTextureLayers::from_names(&pack.texture_array.layers).unwrap();
+        assert_eq!(layers, BlockTextureLayers::default());
+
+        let pack = load_pack(&repo_path("public/data/packs/autumn/pack.txt")).unwrap();
+        assert_eq!(pack.name, "Autumn");
+        assert_eq!(pack.texture_array.layers.len(), BlockSideTexture::ALL.len());
+
+        assert!(load_pack(&repo_path("public/data/packs/missing/pack.txt")).is_err());
+    }
+
+    #[test]
+    fn test_pack_colors_and_shaders() {
+        let dirt = repo_path("public/data/textures/blocks/dirt.png");
+        let shader = "#version 300 es\nvoid main() {}\n";
+        let manifest = format!("texture dirt = {dirt}\nshader chunk.vert = chunk.vert\n");
+        let url = write_pack(
+            "shaders",
+            &[("pack.txt", &manifest), ("chunk.vert", shader)],
+        );
+        let pack = load_pack(&url).unwrap();
+        assert_eq!(pack.shaders, [Some(shader.to_string()), None]);
+
+        let manifest = format!("texture dirt = {dirt}\ncolor grass_block_top = #ffffff\n");
+        let url = write_pack("colors", &[("pack.txt", &manifest)]);
+        assert!(load_pack(&url).unwrap_err().contains("grass_block_top"));
+
+        // includes are only resolved for the built-in shaders
+        let frag = repo_path("backend/shaders/chunk.frag");
+        let manifest = format!("texture dirt = {dirt}\nshader chunk.frag = {frag}\n");
+        let url = write_pack("includes", &[("pack.txt", &manifest)]);
+        assert!(load_pack(&url).unwrap_err().contains("#include"));
+    }
+}
+
+#[cfg(test)]
+mod texture_loader_tests {
+    use std::{
+        cell::RefCell,
+        fs,
+        future::Future,
+        pin::pin,
+        rc::Rc,
+        task::{Context, Poll, Waker},
+    };
+
+    use backend::{
+        graphics::{TextureLoader, TextureState, TextureType},
+        utils::{GlContext, RecordingDevice},
+    };
+
+    use crate::repo_path;
+
+    #[test]
+    fn test_loader_queues_past_max_loading() {
+        let device = RecordingDevice::new();
+        let gl = GlContext::new(device.clone());
+        let mut loader = TextureLoader::new().with_max_loading(2);
+        let requests: Vec<_> = ["dirt.png", "sand.png", "grass_block_side.png"]
+            .iter()
+            .map(|file| {
+                loader.load(
+                    &gl,
+                    &repo_path(&format!("public/data/textures/blocks/{file}")),
+                    TextureType::Texture2D,
+                )
+            })
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(requests
+            .iter()
+            .all(|r| r.get_state() == TextureState::Pending));
+
+        loader.tick(&gl).unwrap();
+        let states: Vec<_> = requests.iter().map(|r| r.get_state()).collect();
+        let ready = TextureState::Ready;
+        assert_eq!(
+            states,
+            [ready.clone(), ready.clone(), TextureState::Pending]
+        );
+        assert_eq!(loader.get_num_pending(), 1);
+
+        loader.tick(&gl).unwrap();
+        assert_eq!(requests[2].get_state(), ready);
+        let uploads = device
+            .get_commands()
+            .iter()
+            .filter(|c| c.starts_with("tex_image_2d(0xde1, 0, 16x16)"))
+            .count();
+        assert_eq!(uploads, 3);
+    }
+
+    #[test]
+    fn test_loader_retries_then_fails() {
+        let gl = GlContext::new(RecordingDevice::new());
+        let mut loader = TextureLoader::new().with_retries(1);
+        let request = loader
+            .load(
+                &gl,
+                &repo_path("public/data/textures/blocks/missing.png"),
+                TextureType::Texture2D,
+            )
+            .unwrap();
+        let errors = Rc::new(RefCell::new(Vec::new()));
+        let on_error = errors.clone();
+        request.on_error(move |e| on_error.borrow_mut().push(e.to_string()));
+
+        loader.tick(&gl).unwrap();
+        assert_eq!(request.get_state(), TextureState::Pending);
+        loader.tick(&gl).unwrap();
+        assert!(matches!(request.get_state(), TextureState::Failed(_)));
+        assert_eq!(errors.borrow().len(), 1);
+        assert!(errors.borrow()[0].contains("missing.png"));
+
+        // a callback added late still hears about it
+        let late = Rc::new(RefCell::new(false));
+        let on_error = late.clone();
+        request.on_error(move |_| *on_error.borrow_mut() = true);
+        assert!(*late.borrow());
+    }
+
+    #[test]
+    fn test_loader_rejects_broken_images() {
+        let path = std::env::temp_dir().join("texture_loader_tests_broken.png");
+        fs::write(&path, b"not a png").unwrap();
+        let gl = GlContext::new(RecordingDevice::new());
+        let mut loader = TextureLoader::new();
+        let broken = loader
+            .load(&gl, path.to_str().unwrap(), TextureType::Texture2D)
+            .unwrap();
+        // 16x256 doesn't split into 3 layers
+        let atlas = repo_path("public/data/textures/blocks/blocks_atlas.png");
+        let layers = loader
+            .load(&gl, &atlas, TextureType::Texture2DArray(3))
+            .unwrap();
+        assert!(loader
+            .load(&gl, &atlas, TextureType::Texture2DArray(0))
+            .is_err());
+        loader.tick(&gl).unwrap();
+        assert!(matches!(broken.get_state(), TextureState::Failed(_)));
+        assert!(matches!(layers.get_state(), TextureState::Failed(_)));
+        assert_eq!(loader.get_num_pending(), 0);
+    }
+
+    #[test]
+    fn test_request_resolves_as_future() {
+        let gl = GlContext::new(RecordingDevice::new());
+        let mut loader = TextureLoader::new();
+        let request = loader
+            .load(
+                &gl,
+                &repo_path("public/data/textures/blocks/dirt.png"),
+                TextureType::Texture2D,
+            )
+            .unwrap();
+        let mut future = pin!(request.clone());
+        let mut context = Context::from_waker(Waker::noop());
+        assert!(future.as_mut().poll(&mut context).is_pending());
+        loader.tick(&gl).unwrap();
+        let Poll::Ready(texture) = future.as_mut().poll(&mut context) else {
+            panic!("request should be settled");
+        };
+        assert_eq!(texture.unwrap().key(), request.get_texture().key());
+    }
+}
+
+#[cfg(test)]
+mod input_tests {
+    use backend::core::{InputEventType, InputSystem, KeyEvent, Modifiers, MouseEvent};
+    use glam::vec2;
+
+    fn key(code: &str, repeat: bool) -> KeyEvent {
+        KeyEvent {
+            key: code.to_string(),
+            code: code.to_string(),
+            repeat,
+            modifiers: Modifiers::default(),
+        }
+    }
+
+    fn mouse_move(x: f32, y: f32) -> InputEventType {
+        InputEventType::MouseMove(MouseEvent {
+            position: vec2(x, y),
+            button: 0,
+            buttons: 1,
+            modifiers: Modifiers::default(),
+        })
+    }
+
+    #[test]
+    fn test_pushed_events_update_input_state() {
+        let mut system = InputSystem::new().unwrap();
+        system.push_event(InputEventType::KeyDown(key("KeyW", false)));
+        system.push_event(InputEventType::KeyDown(key("KeyW", true)));
+        system.push_event(mouse_move(10.0, 10.0));
+        system.push_event(mouse_move(15.0, 8.0));
+
+        let inputs = system.get_inputs();
+        assert!(inputs.is_key_down("KeyW"));
+        assert_eq!(inputs.get_mouse_delta(), vec2(5.0, -2.0));
+        assert_eq!(inputs.get_events().len(), 4);
+
+        system.clear_events();
+        system.push_event(InputEventType::KeyUp(key("KeyW", false)));
+        let inputs = system.get_inputs();
+        assert!(!inputs.is_key_down("KeyW"));
+        assert_eq!(inputs.get_events().len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod shader_validation_tests {
+    use std::path::PathBuf;
+
+    use crate::shader_validation::{Declarations, Shader, SourceLine};
+
+    const FRAGMENT_SHADER: &str = "#version 300 es
+precision mediump float;
+out vec4 out_color;
+void main() {
+    int k = 2;
+    vec3 color = vec3(k, 1, k >> 1) * float(k);
+    float alpha = 1.0f;
+    out_color = vec4(color, alpha);
+}";
+
+    fn validate(name: &str, source: &str) -> Vec<String> {
+        let lines = source
+            .lines()
+            .enumerate()
+            .map(|(index, text)| SourceLine {
+                text: text.to_string(),
+                file: PathBuf::from(format!("shaders/{name}")),
+                line: index + 1,
+            })
+            .collect();
+        let mut shader = Shader {
+            name: name.to_string(),
+            lines,
+            declarations: Declarations::default(),
+        };
+        shader.validate(&[])
+    }
+
+    fn assert_fails(source: &str, expected: &str) {
+        let errors = validate("test.frag", source);
+        assert!(
+            errors.iter().any(|error| error.starts_with(expected)),
+            "expected {expected}, got {errors:?}"
+        );
+    }
+
+    #[test]
+    fn test_valid_shader_passes() {
+        assert_eq!(validate("test.frag", FRAGMENT_SHADER), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_version_must_be_es() {
+        let source = FRAGMENT_SHADER.replace("#version 300 es", "#version 450");
+        assert_fails(
+            &source,
+            "shaders/test.frag:1: shaders must start with #version 300 es",
+        );
+    }
+
+    #[test]
+    fn test_fragment_shader_needs_float_precision() {
+        let source = FRAGMENT_SHADER.replace("precision mediump float;", "");
+        assert_fails(
+            &source,
+            "shaders/test.frag:1: fragment shaders need a default",
+        );
+    }
+
+    #[test]
+    fn test_implicit_int_to_float_conversion_fails() {
+        let source = FRAGMENT_SHADER.replace("float(k)", "k");
+        assert_fails(
+            &source,
+            "shaders/test.frag:6: GLSL ES has no implicit conversions",
+        );
+    }
+
+    #[test]
+    fn test_int_literal_for_float_fails() {
+        let source = FRAGMENT_SHADER.replace("1.0f;", "1;");
+        assert_fails(
+            &source,
+            "shaders/test.frag:7: GLSL ES has no implicit conversions",
+        );
+    }
+}
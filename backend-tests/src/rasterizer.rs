@@ -0,0 +1,392 @@
+/* CPU reference of the chunk pipeline: decodes generate_mesh output like chunk.vert, then
+ * rasterizes and shades it like chunk.frag, without shadows and fog. Slow but deterministic,
+ * it renders the golden images that catch regressions in the vertex format and UV logic */
+
+use std::{fs, fs::File, io::BufWriter, path::Path};
+
+use backend::graphics::{Lighting, RgbaImage};
+use glam::{vec2, vec3, Mat4, Vec2, Vec3, Vec4, Vec4Swizzles};
+
+/* chunk.vert's NORMALS, indexed by face */
+const NORMALS: [Vec3; 6] = [
+    Vec3::Y,
+    Vec3::NEG_Y,
+    Vec3::Z,
+    Vec3::NEG_Z,
+    Vec3::X,
+    Vec3::NEG_X,
+];
+
+const BLOCK_LIGHT_COLOR: Vec3 = vec3(1.0, 0.85, 0.6);
+
+/* a vertex of generate_mesh, with the outputs chunk.vert computes from it */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkVertex {
+    /* in chunk space */
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub uv: Vec2,
+    /* texture array layer */
+    pub layer: i32,
+    /* x: skylight, y: block light, as brightness */
+    pub light: Vec2,
+}
+
+impl ChunkVertex {
+    pub fn decode(data: i32) -> Self {
+        let position = vec3(
+            (data & 31) as f32,
+            ((data >> 5) & 31) as f32,
+            ((data >> 10) & 31) as f32,
+        );
+        let face = (data >> 15) & 7;
+        let layer = (data >> 18) & 63;
+        let sky_light = (data >> 24) & 15;
+        let block_light = (data >> 28) & 15;
+
+        let normal = NORMALS[face as usize];
+        let uv = if normal.x.abs() > 0.5 {
+            vec2(1.0 - position.z, 1.0 - position.y)
+        } else if normal.y.abs() > 0.5 {
+            vec2(position.z, position.x)
+        } else {
+            vec2(1.0 - position.x, 1.0 - position.y)
+        };
+        let light = vec2(0.8f32.powi(15 - sky_light), 0.8f32.powi(15 - block_light));
+        Self {
+            position,
+            normal,
+            uv,
+            layer,
+            light,
+        }
+    }
+}
+
+/* RGBA8 pixels, the first row being the top one */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+/* how much two images differ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageDiff {
+    /* pixels with a channel differing by more than the tolerance */
+    pub mismatched: usize,
+    pub max_difference: u8,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, color: [u8; 4]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![color; (width * height) as usize],
+        }
+    }
+
+    pub fn load_png(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("Error opening {path:?}: {e}"))?;
+        let image =
+            RgbaImage::decode_png(&bytes).map_err(|e| format!("Error decoding {path:?}: {e}"))?;
+        Ok(Self {
+            width: image.width,
+            height: image.height,
+            pixels: image
+                .pixels
+                .chunks_exact(4)
+                .map(|p| [p[0], p[1], p[2], p[3]])
+                .collect(),
+        })
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Error creating {path:?}: {e}"))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| format!("Error writing {path:?}: {e}"))?;
+        writer
+            .write_image_data(self.pixels.concat().as_slice())
+            .map_err(|e| format!("Error writing {path:?}: {e}"))
+    }
+
+    pub fn diff(&self, other: &Image, tolerance: u8) -> ImageDiff {
+        if (self.width, self.height) != (other.width, other.height) {
+            return ImageDiff {
+                mismatched: self.pixels.len().max(other.pixels.len()),
+                max_difference: u8::MAX,
+            };
+        }
+        let mut diff = ImageDiff {
+            mismatched: 0,
+            max_difference: 0,
+        };
+        for (a, b) in self.pixels.iter().zip(other.pixels.iter()) {
+            let difference = (0..4).map(|i| a[i].abs_diff(b[i])).max().unwrap();
+            diff.max_difference = diff.max_difference.max(difference);
+            if difference > tolerance {
+                diff.mismatched += 1;
+            }
+        }
+        diff
+    }
+}
+
+/* images of the same size, like the block textures are uploaded as a TEXTURE_2D_ARRAY. Sampled
+ * like the chunk texture: nearest filtering, repeat wrapping */
+#[derive(Debug, Clone)]
+pub struct TextureArray {
+    layers: Vec<RgbaImage>,
+}
+
+impl TextureArray {
+    pub fn new(layers: Vec<RgbaImage>) -> Result<Self, String> {
+        let Some(first) = layers.first() else {
+            return Err("A texture array needs at least one layer".to_string());
+        };
+        if let Some(other) = layers
+            .iter()
+            .find(|layer| (layer.width, layer.height) != (first.width, first.height))
+        {
+            return Err(format!(
+                "A {}x{} layer in a {}x{} texture array",
+                other.width, other.height, first.width, first.height
+            ));
+        }
+        Ok(Self { layers })
+    }
+
+    /* layers stacked vertically in one image, like blocks_atlas.png */
+    pub fn from_image(image: &RgbaImage, layers: u32) -> Result<Self, String> {
+        Self::new(image.split_layers(layers)?)
+    }
+
+    pub fn sample(&self, uv: Vec2, layer: i32) -> Vec4 {
+        let image = &self.layers[layer.clamp(0, self.layers.len() as i32 - 1) as usize];
+        let texel = |coordinate: f32, size: u32| {
+            let wrapped = coordinate - coordinate.floor();
+            ((wrapped * size as f32) as u32).min(size - 1)
+        };
+        let x = texel(uv.x, image.width);
+        let y = texel(uv.y, image.height);
+        let index = (y * image.width + x) as usize * 4;
+        let [r, g, b, a] = [0, 1, 2, 3].map(|channel| image.pixels[index + channel]);
+        Vec4::new(r as f32, g as f32, b as f32, a as f32) / 255.0
+    }
+}
+
+/* the render state of one of the chunk passes in WorldRenderData::render */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChunkPass {
+    Opaque,
+    /* fragments under the threshold are discarded */
+    Cutout(f32),
+    /* blended with this opacity, without writing depth */
+    Translucent(f32),
+}
+
+/* post-projection vertex, with the varyings of chunk.vert */
+#[derive(Debug, Clone, Copy)]
+struct ClipVertex {
+    position: Vec4,
+    uv: Vec2,
+    light: Vec2,
+}
+
+impl ClipVertex {
+    fn lerp(&self, other: &ClipVertex, t: f32) -> ClipVertex {
+        ClipVertex {
+            position: self.position.lerp(other.position, t),
+            uv: self.uv.lerp(other.uv, t),
+            light: self.light.lerp(other.light, t),
+        }
+    }
+}
+
+/* a colour and depth target to render chunk meshes into, with GL conventions: counter
+ * clockwise front faces with back faces culled, LESS depth test, pixel centers at .5 */
+pub struct ReferenceRenderer {
+    pub color: Image,
+    depth: Vec<f32>,
+    view_projection: Mat4,
+    lighting: Lighting,
+    texture: TextureArray,
+}
+
+impl ReferenceRenderer {
+    pub fn new(
+        width: u32,
+        height: u32,
+        clear_color: [u8; 4],
+        view_projection: Mat4,
+        lighting: Lighting,
+        texture: TextureArray,
+    ) -> Self {
+        Self {
+            color: Image::new(width, height, clear_color),
+            depth: vec![1.0; (width * height) as usize],
+            view_projection,
+            lighting,
+            texture,
+        }
+    }
+
+    /* draws the triangles of a generate_mesh output, world_pos being the chunk.vert uniform */
+    pub fn draw_chunk_mesh(&mut self, data: &[i32], world_pos: Vec3, pass: ChunkPass) {
+        for triangle in data.chunks_exact(3) {
+            let vertices = triangle.iter().map(|data| ChunkVertex::decode(*data));
+            let vertices: Vec<_> = vertices.collect();
+            // normal and layer are flat, the same for all the vertices of a face
+            let (normal, layer) = (vertices[2].normal, vertices[2].layer);
+            let clip: Vec<_> = vertices
+                .iter()
+                .map(|v| ClipVertex {
+                    position: self.view_projection * (v.position + world_pos).extend(1.0),
+                    uv: v.uv,
+                    light: v.light,
+                })
+                .collect();
+            let polygon = clip_near(&clip);
+            for i in 1..polygon.len().saturating_sub(1) {
+                let triangle = [polygon[0], polygon[i], polygon[i + 1]];
+                self.rasterize(&triangle, normal, layer, pass);
+            }
+        }
+    }
+
+    fn rasterize(&mut self, triangle: &[ClipVertex; 3], normal: Vec3, layer: i32, pass: ChunkPass) {
+        let (width, height) = (self.color.width as f32, self.color.height as f32);
+        // window coordinates, y going up, z in [0, 1]
+        let window = triangle.map(|v| {
+            let ndc = v.position.xyz() / v.position.w;
+            vec3(
+                (ndc.x + 1.0) * 0.5 * width,
+                (ndc.y + 1.0) * 0.5 * height,
+                ndc.z * 0.5 + 0.5,
+            )
+        });
+        let edge =
+            |a: Vec3, b: Vec3, x: f32, y: f32| (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x);
+        let area = edge(window[0], window[1], window[2].x, window[2].y);
+        if area <= 0.0 {
+            return;
+        }
+        // top-left rule, so that pixels on an edge shared by two triangles are drawn once
+        let is_top_left = |a: Vec3, b: Vec3| (a.y == b.y && b.x < a.x) || b.y < a.y;
+        let edges = [(1, 2), (2, 0), (0, 1)];
+        let min = window.iter().fold(Vec2::MAX, |m, v| m.min(v.truncate()));
+        let max = window.iter().fold(Vec2::MIN, |m, v| m.max(v.truncate()));
+        let x_range = (min.x.floor().max(0.0) as u32)..(max.x.ceil().min(width) as u32);
+        let y_range = (min.y.floor().max(0.0) as u32)..(max.y.ceil().min(height) as u32);
+        let inverse_w = triangle.map(|v| 1.0 / v.position.w);
+
+        for y in y_range {
+            for x in x_range.clone() {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let mut weights = [0.0; 3];
+                let mut inside = true;
+                for (i, (a, b)) in edges.iter().enumerate() {
+                    let w = edge(window[*a], window[*b], px, py);
+                    inside &= w > 0.0 || (w == 0.0 && is_top_left(window[*a], window[*b]));
+                    weights[i] = w / area;
+                }
+                if !inside {
+                    continue;
+                }
+                let depth = (0..3).map(|i| weights[i] * window[i].z).sum::<f32>();
+                let index = ((self.color.height - 1 - y) * self.color.width + x) as usize;
+                if !(0.0..=1.0).contains(&depth) || depth >= self.depth[index] {
+                    continue;
+                }
+                // perspective correct interpolation
+                let perspective: Vec<f32> = (0..3).map(|i| weights[i] * inverse_w[i]).collect();
+                let total: f32 = perspective.iter().sum();
+                let uv = (0..3)
+                    .map(|i| triangle[i].uv * perspective[i])
+                    .sum::<Vec2>()
+                    / total;
+                let light = (0..3)
+                    .map(|i| triangle[i].light * perspective[i])
+                    .sum::<Vec2>()
+                    / total;
+
+                let Some(color) = self.shade(uv, light, normal, layer, pass) else {
+                    continue;
+                };
+                let pixel = &mut self.color.pixels[index];
+                let color = match pass {
+                    ChunkPass::Translucent(_) => {
+                        let destination = Vec4::from_array(pixel.map(|c| c as f32 / 255.0));
+                        color * color.w + destination * (1.0 - color.w)
+                    }
+                    _ => {
+                        self.depth[index] = depth;
+                        color
+                    }
+                };
+                *pixel = color
+                    .to_array()
+                    .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+        }
+    }
+
+    /* chunk.frag without shadows and fog, None for discarded fragments */
+    fn shade(
+        &self,
+        uv: Vec2,
+        light: Vec2,
+        normal: Vec3,
+        layer: i32,
+        pass: ChunkPass,
+    ) -> Option<Vec4> {
+        let color = self.texture.sample(uv, layer);
+        if let ChunkPass::Cutout(threshold) = pass {
+            if color.w < threshold {
+                return None;
+            }
+        }
+        let rgb = color.xyz();
+        let sun_lit = self.apply_lighting(rgb, normal) * light.x;
+        let lit = sun_lit.max(rgb * BLOCK_LIGHT_COLOR * light.y);
+        let opacity = match pass {
+            ChunkPass::Translucent(opacity) => opacity,
+            _ => 1.0,
+        };
+        Some(lit.extend(color.w * opacity))
+    }
+
+    /* lighting.glsl, fully out of shadow */
+    fn apply_lighting(&self, color: Vec3, normal: Vec3) -> Vec3 {
+        let n = normal.normalize();
+        let diffuse = n.dot(-self.lighting.sun_direction).max(0.0);
+        let sky = 0.5 + 0.5 * n.y;
+        let hemisphere = 0.6 + (1.0 - 0.6) * sky;
+        let ambient = self.lighting.ambient_color * hemisphere * (1.0 - 0.15 * n.x.abs());
+        color * (ambient + self.lighting.sun_color * diffuse)
+    }
+}
+
+/* clips a triangle against the near plane (z > -w), the result being a convex polygon of up
+ * to 4 vertices */
+fn clip_near(triangle: &[ClipVertex]) -> Vec<ClipVertex> {
+    let distance = |v: &ClipVertex| v.position.z + v.position.w;
+    let mut polygon = Vec::with_capacity(4);
+    for i in 0..triangle.len() {
+        let (current, next) = (&triangle[i], &triangle[(i + 1) % triangle.len()]);
+        let (d_current, d_next) = (distance(current), distance(next));
+        if d_current > 0.0 {
+            polygon.push(*current);
+        }
+        if (d_current > 0.0) != (d_next > 0.0) {
+            polygon.push(current.lerp(next, d_current / (d_current - d_next)));
+        }
+    }
+    polygon
+}
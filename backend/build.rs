@@ -1,5 +1,16 @@
 // build.rs
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+
+#[path = "build/shader_validation.rs"]
+mod shader_validation;
+
+use shader_validation::{Declarations, RequestedDefine, Shader, SourceLine, SHADERS_DIR};
+
+const SOURCES_DIR: &str = "src";
+
 fn main() {
     // note: add error checking yourself.
     let output = Command::new("git")
@@ -12,4 +23,228 @@ fn main() {
         .unwrap();
     let git_hash = String::from_utf8(output.stdout).unwrap();
     println!("cargo:rustc-env=GIT_HASH={}", git_hash);
+
+    let mut shaders = preprocess_shaders();
+    let (defines, mut errors) = requested_defines();
+    for shader in shaders.iter_mut() {
+        errors.extend(shader.validate(&defines));
+    }
+    errors.extend(check_shader_defs(&shaders));
+    errors.extend(check_defines_are_tested(&shaders, &defines));
+    // an error in an include shows up once for every shader including it
+    let mut seen = HashSet::new();
+    errors.retain(|error| seen.insert(error.clone()));
+    if !errors.is_empty() {
+        eprintln!("{} shader error(s):\n{}", errors.len(), errors.join("\n"));
+        std::process::exit(1);
+    }
+}
+
+/* resolves the #include directives of every shader into OUT_DIR/shaders, where shader_def!
+ * picks them up */
+fn preprocess_shaders() -> Vec<Shader> {
+    println!("cargo:rerun-if-changed={}", SHADERS_DIR);
+    println!("cargo:rerun-if-changed={}", SOURCES_DIR);
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join(SHADERS_DIR);
+    fs::create_dir_all(&out_dir).unwrap();
+    let shaders_dir = Path::new(SHADERS_DIR);
+    let mut shaders = Vec::new();
+    for entry in fs::read_dir(shaders_dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            println!("cargo:rerun-if-changed={}", path.display());
+            continue;
+        }
+        let mut lines = Vec::new();
+        resolve_includes(shaders_dir, &path, &mut HashSet::new(), &mut lines)
+            .unwrap_or_else(|error| panic!("{error}"));
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let source: String = lines
+            .iter()
+            .map(|line| format!("{}\n", line.text))
+            .collect();
+        fs::write(out_dir.join(&name), source).unwrap();
+        shaders.push(Shader {
+            name,
+            lines,
+            declarations: Declarations::default(),
+        });
+    }
+    shaders
+}
+
+/* inlines `#include "file"` lines, paths being relative to the shaders directory.
+ * Each file is only included once per shader, like with #pragma once */
+fn resolve_includes(
+    shaders_dir: &Path,
+    path: &Path,
+    included: &mut HashSet<PathBuf>,
+    result: &mut Vec<SourceLine>,
+) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    for (index, line) in source.lines().enumerate() {
+        let Some(directive) = line.trim().strip_prefix("#include") else {
+            result.push(SourceLine {
+                text: line.to_string(),
+                file: path.to_path_buf(),
+                line: index + 1,
+            });
+            continue;
+        };
+        let name = directive
+            .trim()
+            .strip_prefix('"')
+            .and_then(|name| name.strip_suffix('"'))
+            .ok_or(format!(
+                "{}:{}: expected #include \"file\"",
+                path.display(),
+                index + 1
+            ))?;
+        let include_path = shaders_dir.join(name);
+        if !include_path.is_file() {
+            return Err(format!(
+                "{}:{}: no shader include {}",
+                path.display(),
+                index + 1,
+                include_path.display()
+            ));
+        }
+        if included.insert(include_path.clone()) {
+            resolve_includes(shaders_dir, &include_path, included, result)?;
+        }
+    }
+    Ok(())
+}
+
+/* checks that the names given to shader_def! and with_attributes are declared by the shaders,
+ * in any of the variants validated */
+fn check_shader_defs(shaders: &[Shader]) -> Vec<String> {
+    let mut errors = Vec::new();
+    for path in rust_sources(Path::new(SOURCES_DIR)) {
+        let source = fs::read_to_string(&path).unwrap();
+        for (offset, _) in source.match_indices("shader_def!(") {
+            let line = source[..offset].matches('\n').count() + 1;
+            let location = format!("{}:{}", path.display(), line);
+            let (arguments, rest) = split_parenthesized(&source[offset + "shader_def!".len()..]);
+            let literals = string_literals(arguments);
+            let [vertex_name, fragment_name, uniforms @ ..] = literals.as_slice() else {
+                continue;
+            };
+            let find = |name: &String| shaders.iter().find(|shader| shader.name == *name);
+            let (Some(vertex), Some(fragment)) = (find(vertex_name), find(fragment_name)) else {
+                errors.push(format!(
+                    "{location}: no shader {vertex_name} or {fragment_name} in {SHADERS_DIR}"
+                ));
+                continue;
+            };
+            for uniform in uniforms {
+                let declared = |shader: &Shader| shader.declarations.uniforms.contains(uniform);
+                if !declared(vertex) && !declared(fragment) {
+                    errors.push(format!(
+                        "{location}: uniform '{uniform}' isn't declared in {vertex_name} or \
+                         {fragment_name}"
+                    ));
+                }
+            }
+            for attribute in chained_call_literals(rest, "with_attributes") {
+                if !vertex.declarations.inputs.contains(&attribute) {
+                    errors.push(format!(
+                        "{location}: attribute '{attribute}' isn't declared in {vertex_name}"
+                    ));
+                }
+            }
+        }
+    }
+    errors
+}
+
+/* the ShaderDefine constants of the sources, like `const CUTOUT: ShaderDefine = ("A", "1");` */
+fn requested_defines() -> (Vec<RequestedDefine>, Vec<String>) {
+    let mut defines = Vec::new();
+    let mut errors = Vec::new();
+    for path in rust_sources(Path::new(SOURCES_DIR)) {
+        let source = fs::read_to_string(&path).unwrap();
+        for (offset, declaration) in source.match_indices(": ShaderDefine = ") {
+            let line = source[..offset].matches('\n').count() + 1;
+            let location = format!("{}:{}", path.display(), line);
+            let (arguments, _) = split_parenthesized(&source[offset + declaration.len()..]);
+            match string_literals(arguments).as_slice() {
+                [name, value] => defines.push(RequestedDefine {
+                    name: name.clone(),
+                    value: value.clone(),
+                    location,
+                }),
+                _ => errors.push(format!(
+                    "{location}: a ShaderDefine has to be a (\"NAME\", \"value\") literal"
+                )),
+            }
+        }
+    }
+    (defines, errors)
+}
+
+/* a define no shader tests is most likely misspelled */
+fn check_defines_are_tested(shaders: &[Shader], defines: &[RequestedDefine]) -> Vec<String> {
+    defines
+        .iter()
+        .filter(|define| {
+            !shaders
+                .iter()
+                .any(|shader| shader.tests_define(&define.name))
+        })
+        .map(|define| {
+            format!(
+                "{}: no shader in {SHADERS_DIR} tests the define {}",
+                define.location, define.name
+            )
+        })
+        .collect()
+}
+
+fn rust_sources(dir: &Path) -> Vec<PathBuf> {
+    let mut sources = Vec::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            sources.extend(rust_sources(&path));
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            sources.push(path);
+        }
+    }
+    sources
+}
+
+/* splits "(a, (b)) rest" into "a, (b)" and " rest" */
+fn split_parenthesized(text: &str) -> (&str, &str) {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 1 => return (&text[1..index], &text[index + 1..]),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    ("", "")
+}
+
+fn string_literals(text: &str) -> Vec<String> {
+    text.split('"')
+        .skip(1)
+        .step_by(2)
+        .map(|literal| literal.to_string())
+        .collect()
+}
+
+/* literals passed to a method called on the macro result, like .with_attributes(...) */
+fn chained_call_literals(mut rest: &str, method: &str) -> Vec<String> {
+    while let Some(call) = rest.trim_start().strip_prefix('.') {
+        let name_end = call.find('(').unwrap_or(call.len());
+        let (arguments, after) = split_parenthesized(&call[name_end..]);
+        if call[..name_end].trim() == method {
+            return string_literals(arguments);
+        }
+        rest = after;
+    }
+    Vec::new()
 }
@@ -0,0 +1,474 @@
+// shader_validation.rs, shared by build.rs and the tests of backend-tests
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use naga::front::glsl::{Frontend, Options};
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::{
+    AddressSpace, Arena, BinaryOperator, Binding, Expression, Literal, Module, ShaderStage,
+    TypeInner,
+};
+
+pub const SHADERS_DIR: &str = "shaders";
+
+/* combined samplers of GLSL ES and the separate texture and sampler naga wants instead */
+const SAMPLER_TYPES: [(&str, &str, &str); 4] = [
+    ("sampler2D", "texture2D", "sampler"),
+    ("sampler2DArray", "texture2DArray", "sampler"),
+    ("sampler2DShadow", "texture2D", "samplerShadow"),
+    ("sampler2DArrayShadow", "texture2DArray", "samplerShadow"),
+];
+const PRECISION_QUALIFIERS: [&str; 3] = ["lowp ", "mediump ", "highp "];
+const MATRIX_TYPES: [(&str, usize); 3] = [("mat2", 2), ("mat3", 3), ("mat4", 4)];
+/* names given by to_vulkan_glsl to the block wrapping each plain uniform and to the columns of
+ * matrix inputs, to find the original names in the parsed module */
+const PLAIN_UNIFORM_BLOCK: &str = "PlainUniform";
+const MATRIX_COLUMN: &str = "_column";
+
+/* a line of a shader after includes are resolved, with where it comes from */
+pub struct SourceLine {
+    pub text: String,
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+pub struct Shader {
+    pub name: String,
+    pub lines: Vec<SourceLine>,
+    /* filled by validate, from every variant that parsed */
+    pub declarations: Declarations,
+}
+
+/* uniforms outside of blocks and vertex inputs, as found in the parsed module */
+#[derive(Default)]
+pub struct Declarations {
+    pub uniforms: HashSet<String>,
+    pub inputs: HashSet<String>,
+}
+
+impl Declarations {
+    fn extend(&mut self, module: &Module) {
+        for (_, global) in module.global_variables.iter() {
+            let ty = &module.types[global.ty];
+            match (global.space, &ty.inner) {
+                (AddressSpace::Uniform, TypeInner::Struct { members, .. })
+                    if ty
+                        .name
+                        .as_ref()
+                        .is_some_and(|name| name.starts_with(PLAIN_UNIFORM_BLOCK)) =>
+                {
+                    let names = members.iter().filter_map(|member| member.name.clone());
+                    self.uniforms.extend(names);
+                }
+                // the texture half of a split sampler keeps the name of the original one
+                (AddressSpace::Handle, TypeInner::Image { .. }) => {
+                    self.uniforms.extend(global.name.clone());
+                }
+                _ => {}
+            }
+        }
+        for entry_point in module.entry_points.iter() {
+            if entry_point.stage != ShaderStage::Vertex {
+                continue;
+            }
+            for argument in entry_point.function.arguments.iter() {
+                let (Some(name), Some(Binding::Location { .. })) =
+                    (&argument.name, &argument.binding)
+                else {
+                    continue;
+                };
+                match name.split_once(MATRIX_COLUMN) {
+                    None => self.inputs.insert(name.clone()),
+                    Some((matrix, "0")) => self.inputs.insert(matrix.to_string()),
+                    Some(_) => false,
+                };
+            }
+        }
+    }
+}
+
+/* a ShaderDefine constant of the sources, see shaders.rs */
+pub struct RequestedDefine {
+    pub name: String,
+    pub value: String,
+    pub location: String,
+}
+
+impl Shader {
+    fn get_stage(&self) -> Option<ShaderStage> {
+        match Path::new(&self.name).extension()?.to_str()? {
+            "vert" => Some(ShaderStage::Vertex),
+            "frag" => Some(ShaderStage::Fragment),
+            _ => None,
+        }
+    }
+
+    /* whether an #if, #ifdef or #ifndef of the shader tests the define */
+    pub fn tests_define(&self, name: &str) -> bool {
+        self.lines.iter().any(|line| {
+            line.text.trim_start().starts_with("#if")
+                && line
+                    .text
+                    .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .any(|word| word == name)
+        })
+    }
+
+    /* validates the shader with each combination of the requested defines it tests, returns
+     * the diagnostics */
+    pub fn validate(&mut self, defines: &[RequestedDefine]) -> Vec<String> {
+        let Some(stage) = self.get_stage() else {
+            return Vec::new();
+        };
+        let mut errors = self.check_es_rules(stage);
+        let tested: Vec<_> = defines
+            .iter()
+            .filter(|define| self.tests_define(&define.name))
+            .collect();
+        for variant in 0..1 << tested.len() {
+            let variant_defines: Vec<_> = tested
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| variant & (1 << i) != 0)
+                .map(|(_, define)| *define)
+                .collect();
+            errors.extend(self.validate_variant(stage, &variant_defines));
+        }
+        errors
+    }
+
+    /* naga only reads GLSL 440 and up, for which the source is rewritten. This checks the rules
+     * of GLSL ES 3.00 the rewrite would hide */
+    fn check_es_rules(&self, stage: ShaderStage) -> Vec<String> {
+        let mut errors = Vec::new();
+        let start = format!("{}/{}:1", SHADERS_DIR, self.name);
+        if self.lines.first().map(|line| line.text.trim()) != Some("#version 300 es") {
+            errors.push(format!("{start}: shaders must start with #version 300 es"));
+        }
+        let has_float_precision = self.lines.iter().any(|line| {
+            let words: Vec<_> = line.text.split_whitespace().collect();
+            matches!(words.as_slice(), ["precision", _, "float;"])
+        });
+        if stage == ShaderStage::Fragment && !has_float_precision {
+            errors.push(format!(
+                "{start}: fragment shaders need a default float precision in GLSL ES"
+            ));
+        }
+        errors
+    }
+
+    /* parses and validates one variant, keeping what it declares */
+    fn validate_variant(
+        &mut self,
+        stage: ShaderStage,
+        defines: &[&RequestedDefine],
+    ) -> Vec<String> {
+        // defines go right after #version, pointing at it in diagnostics
+        let mut lines: Vec<_> = self.lines.iter().collect();
+        let define_lines: Vec<_> = defines
+            .iter()
+            .map(|define| SourceLine {
+                text: format!("#define {} {}", define.name, define.value),
+                file: self.lines[0].file.clone(),
+                line: 1,
+            })
+            .collect();
+        lines.splice(1..1, define_lines.iter());
+        let variant = match defines.is_empty() {
+            true => String::new(),
+            false => {
+                let names: Vec<_> = defines
+                    .iter()
+                    .map(|define| format!("{} {}", define.name, define.value))
+                    .collect();
+                format!(" (with {})", names.join(", "))
+            }
+        };
+        let locate = |source: &str, offset: usize| {
+            let index = source[..offset.min(source.len())].matches('\n').count();
+            match lines.get(index) {
+                Some(line) => format!("{}:{}{variant}", line.file.display(), line.line),
+                None => format!("{}/{}{variant}", SHADERS_DIR, self.name),
+            }
+        };
+
+        let source = to_vulkan_glsl(&lines);
+        let module = match Frontend::default().parse(&Options::from(stage), &source) {
+            Ok(module) => module,
+            Err(errors) => {
+                return errors
+                    .iter()
+                    .map(|error| {
+                        let offset = error.meta.to_range().map_or(0, |range| range.start);
+                        format!("{}: {}", locate(&source, offset), error.kind)
+                    })
+                    .collect();
+            }
+        };
+        self.declarations.extend(&module);
+        let mut errors: Vec<_> = find_implicit_conversions(&module, &source)
+            .into_iter()
+            .map(|offset| {
+                format!(
+                    "{}: GLSL ES has no implicit conversions, convert explicitly",
+                    locate(&source, offset)
+                )
+            })
+            .collect();
+        let mut validator = Validator::new(ValidationFlags::all(), Capabilities::all());
+        if let Err(error) = validator.validate(&module) {
+            // the last span is the innermost expression
+            let offset = error
+                .spans()
+                .last()
+                .and_then(|(span, _)| span.to_range())
+                .map_or(0, |range| range.start);
+            // the top level error only names the function, the details are in the sources
+            let mut message = error.as_inner().to_string();
+            let mut cause = std::error::Error::source(error.as_inner());
+            while let Some(inner) = cause {
+                message.push_str(&format!(": {inner}"));
+                cause = inner.source();
+            }
+            errors.push(format!("{}: {message}", locate(&source, offset)));
+        }
+        errors
+    }
+}
+
+/* GLSL 450 converts ints to floats and uints wherever one is expected, GLSL ES 3.00 only in
+ * constructors. Returns the offsets of the conversions the rewritten source relies on */
+fn find_implicit_conversions(module: &Module, source: &str) -> Vec<usize> {
+    let functions = module.functions.iter().map(|(_, function)| function);
+    let entry_points = module
+        .entry_points
+        .iter()
+        .map(|entry_point| &entry_point.function);
+    let mut offsets = find_conversions_in(module, &module.const_expressions, source);
+    for function in functions.chain(entry_points) {
+        offsets.extend(find_conversions_in(module, &function.expressions, source));
+    }
+    offsets
+}
+
+/* naga spells conversions out, spanning only the operand when implicit, and folds int literals
+ * converted to floats into float literals, which the source writes as ints */
+fn find_conversions_in(
+    module: &Module,
+    expressions: &Arena<Expression>,
+    source: &str,
+) -> Vec<usize> {
+    /* vector and matrix constructors convert their arguments in GLSL ES too, and naga makes
+     * shift amounts unsigned where GLSL ES takes any int */
+    let mut allowed = HashSet::new();
+    for (_, expression) in expressions.iter() {
+        match expression {
+            Expression::Compose { ty, components }
+                if matches!(
+                    module.types[*ty].inner,
+                    TypeInner::Vector { .. } | TypeInner::Matrix { .. }
+                ) =>
+            {
+                allowed.extend(components.iter().copied());
+            }
+            Expression::Splat { value, .. } => {
+                allowed.insert(*value);
+            }
+            Expression::Binary {
+                op: BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight,
+                right,
+                ..
+            } => {
+                allowed.insert(*right);
+            }
+            _ => {}
+        }
+    }
+    expressions
+        .iter()
+        .filter(|(handle, _)| !allowed.contains(handle))
+        .filter_map(|(handle, expression)| {
+            let span = expressions.get_span(handle).to_range()?;
+            let implicit = match expression {
+                Expression::As {
+                    expr,
+                    convert: Some(_),
+                    ..
+                } => expressions.get_span(*expr).to_range() == Some(span.clone()),
+                Expression::Literal(literal) => {
+                    let text = &source[span.clone()];
+                    let written_as_int = text
+                        .chars()
+                        .all(|c| c.is_ascii_digit() || " +-*/%()".contains(c));
+                    written_as_int
+                        && text.chars().any(|c| c.is_ascii_digit())
+                        && !matches!(literal, Literal::I32(_) | Literal::I64(_))
+                }
+                _ => false,
+            };
+            implicit.then_some(span.start)
+        })
+        .collect()
+}
+
+/* naga reads the Vulkan flavour of GLSL: rewrites GLSL ES 3.00 into it without adding or
+ * removing lines, so diagnostics keep pointing at the right place */
+fn to_vulkan_glsl(lines: &[&SourceLine]) -> String {
+    let mut used_locations = (Vec::new(), Vec::new());
+    for line in lines.iter() {
+        if let Some((is_input, location)) = parse_location(&line.text) {
+            match is_input {
+                true => used_locations.0.push(location),
+                false => used_locations.1.push(location),
+            }
+        }
+    }
+
+    let mut next_locations = (0, 0);
+    let mut binding = 0;
+    let mut samplers: Vec<(String, &str)> = Vec::new();
+    // matrix inputs become one vec input per column, naga doesn't take matrices
+    let mut matrix_inputs: Vec<(String, String)> = Vec::new();
+    let mut depth = 0;
+    let mut result = String::new();
+    for line in lines.iter() {
+        let mut text = line.text.clone();
+        for qualifier in PRECISION_QUALIFIERS {
+            text = text.replace(qualifier, "");
+        }
+        let trimmed = text.trim_start().to_string();
+        let words: Vec<_> = trimmed.split_whitespace().collect();
+        if depth == 0 {
+            if trimmed.starts_with("#version") {
+                text = "#version 450".to_string();
+            } else if words.first() == Some(&"precision") {
+                text.clear();
+            } else if let Some(declaration) = trimmed.strip_prefix("uniform ") {
+                let sampler = declaration.trim_end_matches(';').split_once(' ').and_then(
+                    |(sampler_type, name)| {
+                        let types = SAMPLER_TYPES.iter().find(|(t, _, _)| *t == sampler_type)?;
+                        Some((types, name.trim().to_string()))
+                    },
+                );
+                text = match sampler {
+                    Some(((sampler_type, texture_type, sampler_kind), name)) => {
+                        samplers.push((name.clone(), sampler_type));
+                        format!(
+                            "layout(binding = {binding}) uniform {texture_type} {name}; \
+                             layout(binding = {}) uniform {sampler_kind} {name}_sampler;",
+                            binding + 1
+                        )
+                    }
+                    None => format!(
+                        "layout(binding = {binding}) uniform {PLAIN_UNIFORM_BLOCK}{binding} \
+                         {{ {declaration} }};"
+                    ),
+                };
+                binding += 2;
+            } else if words.first() == Some(&"layout")
+                && words.contains(&"uniform")
+                && !trimmed.contains("binding")
+            {
+                if let Some(index) = text.find('(') {
+                    text.insert_str(index + 1, &format!("binding = {binding}, "));
+                    binding += 2;
+                }
+            } else if matches!(
+                words.as_slice(),
+                ["in" | "out", ..] | ["flat", "in" | "out", ..]
+            ) {
+                let is_input = words.contains(&"in");
+                let (used, next) = match is_input {
+                    true => (&used_locations.0, &mut next_locations.0),
+                    false => (&used_locations.1, &mut next_locations.1),
+                };
+                let matrix_size = match words.as_slice() {
+                    ["in", matrix_type, _] => MATRIX_TYPES
+                        .iter()
+                        .find(|(t, _)| t == matrix_type)
+                        .map(|(_, size)| *size),
+                    _ => None,
+                };
+                let columns = matrix_size.unwrap_or(1);
+                let mut declarations = Vec::new();
+                for column in 0..columns {
+                    while used.contains(next) {
+                        *next += 1;
+                    }
+                    declarations.push(match matrix_size {
+                        None => format!("layout(location = {next}) {trimmed}"),
+                        Some(size) => {
+                            let name = words[2].trim_end_matches(';');
+                            let column = format!("{name}{MATRIX_COLUMN}{column}");
+                            format!("layout(location = {next}) in vec{size} {column};")
+                        }
+                    });
+                    *next += 1;
+                }
+                if let Some(size) = matrix_size {
+                    let name = words[2].trim_end_matches(';');
+                    let columns: Vec<_> = (0..size)
+                        .map(|c| format!("{name}{MATRIX_COLUMN}{c}"))
+                        .collect();
+                    let matrix = format!("mat{size}({})", columns.join(", "));
+                    matrix_inputs.push((name.to_string(), matrix));
+                }
+                text = declarations.join(" ");
+            }
+        }
+        for (name, matrix) in matrix_inputs.iter() {
+            text = replace_word(&text, name, matrix);
+        }
+        if !text.contains("_sampler;") {
+            for (name, sampler_type) in samplers.iter() {
+                let combined = format!("{sampler_type}({name}, {name}_sampler)");
+                text = replace_word(&text, name, &combined);
+            }
+        }
+        depth += line.text.matches('{').count() as i32 - line.text.matches('}').count() as i32;
+        result.push_str(&text);
+        result.push('\n');
+    }
+    result
+}
+
+/* (is an input, location) of a `layout (location = N) in/out` line */
+fn parse_location(line: &str) -> Option<(bool, u32)> {
+    let rest = line.trim_start().strip_prefix("layout")?;
+    let (qualifiers, declaration) = rest.split_once(')')?;
+    let location = qualifiers.split_once("location")?.1;
+    let location = location
+        .trim_start_matches([' ', '='])
+        .trim()
+        .parse()
+        .ok()?;
+    match declaration
+        .split_whitespace()
+        .find(|word| *word != "flat")?
+    {
+        "in" => Some((true, location)),
+        "out" => Some((false, location)),
+        _ => None,
+    }
+}
+
+fn replace_word(text: &str, word: &str, replacement: &str) -> String {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(word) {
+        let starts_word = !rest[..index].chars().last().is_some_and(is_identifier);
+        let ends_word = !rest[index + word.len()..]
+            .chars()
+            .next()
+            .is_some_and(is_identifier);
+        result.push_str(&rest[..index]);
+        result.push_str(match starts_word && ends_word {
+            true => replacement,
+            false => word,
+        });
+        rest = &rest[index + word.len()..];
+    }
+    result.push_str(rest);
+    result
+}
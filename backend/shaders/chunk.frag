@@ -3,13 +3,34 @@
 precision highp float;
 in vec2 v_texcoord;
 flat in int v_depth;
+in vec3 o_normal;
+in float v_view_distance;
+in vec4 v_shadow_position;
+in vec2 v_light;
 
 uniform mediump sampler2DArray u_texture;
+uniform float opacity;
+#include "include/lighting.glsl"
+#include "include/shadows.glsl"
+#include "include/fog.glsl"
 
 out vec4 outColor;
 
+const vec3 BLOCK_LIGHT_COLOR = vec3(1.0f, 0.85f, 0.6f);
+
 void main() {
     // outColor = vec4(0.8f, 0.7f, 1.0f, 1.0f);
     // outColor = vec4(v_texcoord.x, v_texcoord.y, 1.0f, 1.0f);
-    outColor = texture(u_texture, vec3(v_texcoord, v_depth));
+    vec4 color = texture(u_texture, vec3(v_texcoord, v_depth));
+#ifdef ALPHA_THRESHOLD
+    // only in the cutout variant, discard would cost early depth testing to the others
+    if (color.a < ALPHA_THRESHOLD) {
+        discard;
+    }
+#endif
+    // sun and sky only reach what the skylight reaches, torches and lava light the rest
+    float shadow = shadow_factor(v_shadow_position);
+    vec3 sun_lit = apply_lighting(color.rgb, o_normal, shadow) * v_light.x;
+    vec3 lit = max(sun_lit, color.rgb * BLOCK_LIGHT_COLOR * v_light.y);
+    outColor = vec4(mix(lit, fog_color, fog_factor(v_view_distance)), color.a * opacity);
 }
@@ -1,17 +1,23 @@
 #version 300 es
 
-layout (location = 0) in int data;
+#include "include/chunk_data.glsl"
 
 uniform vec3 world_pos;
-uniform mat4 view;
-uniform mat4 projection;
+#include "include/frame_data.glsl"
 
 out vec2 v_texcoord;
 flat out int v_depth;
 
 out vec3 o_normal;
+out float v_view_distance;
+// x: skylight, y: block light, both already turned into brightness
+out vec2 v_light;
+out vec4 v_shadow_position;
 
-vec3 NORMALS[] = vec3[]( //
+// pushes the shadow lookup out of the surface, against shadow acne
+const float SHADOW_NORMAL_OFFSET = 0.05f;
+
+const vec3 NORMALS[6] = vec3[6]( //
 vec3(0.0f, 1.0f, 0.0f),  // +Y
 vec3(0.0f, -1.0f, 0.0f),   // -Y
 vec3(0.0f, 0.0f, 1.0f),  // +Z
@@ -21,31 +27,21 @@ vec3(-1.0f, 0.0f, 0.0f)  // -X
 );
 
 void main() {
-    int x = data & 63;
-    int y = (data >> 6) & 63;
-    int z = (data >> 12) & 63;
-    int face = (data >> 18) & 7;
-    int depth = (data >> 21) & 63;
-    int uv = (data >> 27) & 3;
-
-    vec3 position = vec3(x, y, z);
-    vec3 normal = NORMALS[face];
+    vec3 position = chunk_data_position();
+    int face = (data >> 15) & 7;
+    int sky_light = (data >> 24) & 15;
+    int block_light = (data >> 28) & 15;
 
-    // vec2 uvs = UVS[uv];
-    vec2 uvs;
-    if (abs(normal.x) > 0.5f) {
-        // Side faces
-        uvs = vec2(1 - z, 1 - y);
-    } else if (abs(normal.y) > 0.5f) {
-        // Top and bottom faces
-        uvs = vec2(z, x);
-    } else if (abs(normal.z) > 0.5f) {
-        // Front and back faces
-        uvs = vec2(1 - x, 1 - y);
-    }
+    vec3 normal = NORMALS[face];
 
     o_normal = normal;
-    gl_Position = projection * view * vec4(position + world_pos, 1.0f);
-    v_texcoord = uvs;
-    v_depth = depth;
+    vec3 world_position = position + world_pos;
+    vec3 shadow_offset = normal * SHADOW_NORMAL_OFFSET;
+    v_shadow_position = shadow_matrix * vec4(world_position + shadow_offset, 1.0f);
+    vec4 view_position = view * vec4(world_position, 1.0f);
+    v_view_distance = length(view_position.xyz);
+    gl_Position = projection * view_position;
+    v_texcoord = chunk_data_texcoord();
+    v_depth = chunk_data_layer();
+    v_light = pow(vec2(0.8f), vec2(15 - sky_light, 15 - block_light));
 }
@@ -0,0 +1,19 @@
+#version 300 es
+
+precision highp float;
+
+#ifdef ALPHA_THRESHOLD
+in vec2 v_texcoord;
+flat in int v_depth;
+
+uniform mediump sampler2DArray u_texture;
+#endif
+
+// depth only, cutout faces let the light through their transparent texels
+void main() {
+#ifdef ALPHA_THRESHOLD
+    if (texture(u_texture, vec3(v_texcoord, v_depth)).a < ALPHA_THRESHOLD) {
+        discard;
+    }
+#endif
+}
@@ -0,0 +1,19 @@
+#version 300 es
+
+#include "include/chunk_data.glsl"
+
+uniform vec3 world_pos;
+#include "include/frame_data.glsl"
+
+#ifdef ALPHA_THRESHOLD
+out vec2 v_texcoord;
+flat out int v_depth;
+#endif
+
+void main() {
+    gl_Position = shadow_matrix * vec4(chunk_data_position() + world_pos, 1.0f);
+#ifdef ALPHA_THRESHOLD
+    v_texcoord = chunk_data_texcoord();
+    v_depth = chunk_data_layer();
+#endif
+}
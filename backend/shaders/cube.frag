@@ -3,14 +3,23 @@
 precision highp float;
 in vec2 v_texcoord;
 in float v_depth;
+in vec3 o_normal;
+in float v_view_distance;
+in vec4 v_shadow_position;
 
 uniform mediump sampler2DArray u_texture;
+#include "include/lighting.glsl"
+#include "include/shadows.glsl"
+#include "include/fog.glsl"
 
 out vec4 outColor;
 
 void main() {
     outColor = vec4(0.8f, 0.7f, 1.0f, 1.0f);
     outColor = vec4(v_texcoord.x, v_texcoord.y, 1.0f, 1.0f);
-    outColor = texture(u_texture, vec3(v_texcoord, v_depth));
+    vec4 color = texture(u_texture, vec3(v_texcoord, v_depth));
+    float shadow = shadow_factor(v_shadow_position);
+    vec3 lit = apply_lighting(color.rgb, o_normal, shadow);
+    outColor = vec4(mix(lit, fog_color, fog_factor(v_view_distance)), color.a);
 
 }
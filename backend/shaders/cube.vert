@@ -1,22 +1,31 @@
 #version 300 es
 
-in vec3 position;
+layout (location = 0) in vec3 position;
 in vec2 uv;
 in vec3 normal;
 in float depth;
 
 uniform mat4 model;
-uniform mat4 view;
-uniform mat4 projection;
+#include "include/frame_data.glsl"
 
 out vec2 v_texcoord;
 out float v_depth;
 
 out vec3 o_normal;
+out float v_view_distance;
+out vec4 v_shadow_position;
+
+// pushes the shadow lookup out of the surface, against shadow acne
+const float SHADOW_NORMAL_OFFSET = 0.05f;
 
 void main() {
-    o_normal = normal;
-    gl_Position = projection * view * model * vec4(position, 1.0f);
+    o_normal = mat3(model) * normal;
+    vec4 world_position = model * vec4(position, 1.0f);
+    vec3 shadow_offset = normalize(o_normal) * SHADOW_NORMAL_OFFSET;
+    v_shadow_position = shadow_matrix * (world_position + vec4(shadow_offset, 0.0f));
+    vec4 view_position = view * world_position;
+    v_view_distance = length(view_position.xyz);
+    gl_Position = projection * view_position;
     v_texcoord = uv;
     v_depth = depth;
 }
@@ -0,0 +1,10 @@
+#version 300 es
+
+precision highp float;
+in vec4 fragColor;
+
+out vec4 outColor;
+
+void main() {
+    outColor = fragColor;
+}
@@ -0,0 +1,14 @@
+#version 300 es
+
+in vec3 position;
+in vec4 instance_color;
+in mat4 instance_transform;
+
+#include "include/frame_data.glsl"
+
+out vec4 fragColor;
+
+void main() {
+    gl_Position = projection * view * instance_transform * vec4(position, 1.0f);
+    fragColor = instance_color;
+}
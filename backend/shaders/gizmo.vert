@@ -4,8 +4,7 @@ in vec3 position;
 in vec3 color;
 
 uniform mat4 model;
-uniform mat4 view;
-uniform mat4 projection;
+#include "include/frame_data.glsl"
 
 out vec3 fragColor;
 
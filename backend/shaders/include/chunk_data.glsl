@@ -0,0 +1,26 @@
+// packed chunk vertex, see Chunk::generate_mesh for the layout
+layout (location = 0) in int data;
+
+vec3 chunk_data_position() {
+    return vec3(data & 31, (data >> 5) & 31, (data >> 10) & 31);
+}
+
+// texture coordinates, laid out on the side of the block the vertex belongs to
+vec2 chunk_data_texcoord() {
+    vec3 position = chunk_data_position();
+    int face = (data >> 15) & 7;
+    if (face < 2) {
+        // top and bottom faces
+        return position.zx;
+    } else if (face < 4) {
+        // front and back faces
+        return vec2(1.0f - position.x, 1.0f - position.y);
+    }
+    // side faces
+    return vec2(1.0f - position.z, 1.0f - position.y);
+}
+
+// layer of the block texture array
+int chunk_data_layer() {
+    return (data >> 18) & 63;
+}
@@ -0,0 +1,12 @@
+#include "include/frame_data.glsl"
+
+float fog_factor(float distance) {
+    if (fog_mode == 1) {
+        return clamp((distance - fog_start) / (fog_end - fog_start), 0.0f, 1.0f);
+    }
+    if (fog_mode == 2) {
+        float d = fog_density * distance / fog_end;
+        return 1.0f - exp(-d * d);
+    }
+    return 0.0f;
+}
@@ -0,0 +1,17 @@
+// per-frame data shared by all programs, see FrameData
+layout (std140) uniform FrameData {
+    mat4 view;
+    mat4 projection;
+    mat4 shadow_matrix;
+    vec3 camera_position;
+    float time;
+    vec3 sun_direction;
+    highp int shadows_enabled;
+    vec3 sun_color;
+    highp int fog_mode;
+    vec3 ambient_color;
+    float fog_start;
+    vec3 fog_color;
+    float fog_end;
+    float fog_density;
+};
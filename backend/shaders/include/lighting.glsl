@@ -0,0 +1,10 @@
+#include "include/frame_data.glsl"
+
+vec3 apply_lighting(vec3 color, vec3 normal, float shadow) {
+    vec3 n = normalize(normal);
+    float diffuse = max(dot(n, -sun_direction), 0.0f) * shadow;
+    // cheap hemisphere term so that faces facing away from the sun still differ
+    float sky = 0.5f + 0.5f * n.y;
+    vec3 ambient = ambient_color * mix(0.6f, 1.0f, sky) * (1.0f - 0.15f * abs(n.x));
+    return color * (ambient + sun_color * diffuse);
+}
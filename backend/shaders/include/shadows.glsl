@@ -0,0 +1,22 @@
+#include "include/frame_data.glsl"
+
+uniform mediump sampler2DShadow shadow_map;
+
+// 3x3 PCF over the hardware filtered comparison, 1.0 when fully lit
+float shadow_factor(vec4 shadow_position) {
+    if (shadows_enabled == 0) {
+        return 1.0f;
+    }
+    vec3 coords = shadow_position.xyz / shadow_position.w * 0.5f + 0.5f;
+    if (any(lessThan(coords, vec3(0.0f))) || any(greaterThan(coords, vec3(1.0f)))) {
+        return 1.0f;
+    }
+    vec2 texel = 1.0f / vec2(textureSize(shadow_map, 0));
+    float lit = 0.0f;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            lit += texture(shadow_map, vec3(coords.xy + vec2(x, y) * texel, coords.z));
+        }
+    }
+    return lit / 9.0f;
+}
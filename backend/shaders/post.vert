@@ -0,0 +1,11 @@
+#version 300 es
+
+// shared by every post effect, same location for all so that one quad serves them all
+layout (location = 0) in vec3 position;
+
+out vec2 v_uv;
+
+void main() {
+    v_uv = position.xy * 0.5f + 0.5f;
+    gl_Position = vec4(position.xy, 0.0f, 1.0f);
+}
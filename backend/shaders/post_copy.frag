@@ -0,0 +1,12 @@
+#version 300 es
+
+precision highp float;
+in vec2 v_uv;
+
+uniform sampler2D color_texture;
+
+out vec4 outColor;
+
+void main() {
+    outColor = vec4(texture(color_texture, v_uv).rgb, 1.0f);
+}
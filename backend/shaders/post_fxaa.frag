@@ -0,0 +1,44 @@
+#version 300 es
+
+precision highp float;
+in vec2 v_uv;
+
+uniform sampler2D color_texture;
+uniform vec2 texel_size;
+
+out vec4 outColor;
+
+const float FXAA_SPAN_MAX = 8.0f;
+const float FXAA_REDUCE_MUL = 1.0f / 8.0f;
+const float FXAA_REDUCE_MIN = 1.0f / 128.0f;
+const vec3 LUMA = vec3(0.299f, 0.587f, 0.114f);
+
+vec3 sample_at(vec2 offset) {
+    return texture(color_texture, v_uv + offset * texel_size).rgb;
+}
+
+// blurs along the edge direction found from the luma of the 4 diagonal neighbours
+void main() {
+    float luma_nw = dot(sample_at(vec2(-1.0f, -1.0f)), LUMA);
+    float luma_ne = dot(sample_at(vec2(1.0f, -1.0f)), LUMA);
+    float luma_sw = dot(sample_at(vec2(-1.0f, 1.0f)), LUMA);
+    float luma_se = dot(sample_at(vec2(1.0f, 1.0f)), LUMA);
+    vec3 color = sample_at(vec2(0.0f));
+    float luma_m = dot(color, LUMA);
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    vec2 direction = vec2(-((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se));
+    float reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25f * FXAA_REDUCE_MUL,
+        FXAA_REDUCE_MIN);
+    float scale = 1.0f / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, -FXAA_SPAN_MAX, FXAA_SPAN_MAX);
+
+    vec3 near = 0.5f * (sample_at(direction * (1.0f / 3.0f - 0.5f)) +
+        sample_at(direction * (2.0f / 3.0f - 0.5f)));
+    vec3 far = near * 0.5f + 0.25f * (sample_at(direction * -0.5f) +
+        sample_at(direction * 0.5f));
+    float luma_far = dot(far, LUMA);
+    outColor = vec4(luma_far < luma_min || luma_far > luma_max ? near : far, 1.0f);
+}
@@ -0,0 +1,62 @@
+#version 300 es
+
+precision highp float;
+in vec2 v_uv;
+
+uniform sampler2D color_texture;
+uniform highp sampler2D depth_texture;
+uniform float strength;
+uniform mat4 projection;
+uniform mat4 inverse_projection;
+
+out vec4 outColor;
+
+const int SAMPLE_COUNT = 12;
+// in blocks
+const float RADIUS = 0.6f;
+const float BIAS = 0.02f;
+const float GOLDEN_ANGLE = 2.39996f;
+
+vec3 view_position_at(vec2 uv) {
+    float depth = texture(depth_texture, uv).r;
+    vec4 position = inverse_projection * vec4(vec3(uv, depth) * 2.0f - 1.0f, 1.0f);
+    return position.xyz / position.w;
+}
+
+// interleaved gradient noise, rotates the kernel per pixel
+float noise() {
+    return fract(52.9829189f * fract(dot(gl_FragCoord.xy, vec2(0.06711056f, 0.00583715f))));
+}
+
+void main() {
+    vec3 color = texture(color_texture, v_uv).rgb;
+    if (texture(depth_texture, v_uv).r >= 1.0f) {
+        // sky
+        outColor = vec4(color, 1.0f);
+        return;
+    }
+    vec3 position = view_position_at(v_uv);
+    vec3 normal = normalize(cross(dFdx(position), dFdy(position)));
+    vec3 tangent = normalize(abs(normal.y) < 0.99f ? cross(normal, vec3(0.0f, 1.0f, 0.0f))
+        : cross(normal, vec3(1.0f, 0.0f, 0.0f)));
+    vec3 bitangent = cross(normal, tangent);
+
+    float rotation = noise() * 6.2831853f;
+    float occlusion = 0.0f;
+    for (int i = 0; i < SAMPLE_COUNT; i++) {
+        // spiral over the hemisphere around the normal, denser close to the point
+        float ratio = (float(i) + 0.5f) / float(SAMPLE_COUNT);
+        float angle = float(i) * GOLDEN_ANGLE + rotation;
+        vec3 direction = vec3(cos(angle) * ratio, sin(angle) * ratio, sqrt(1.0f - ratio * ratio));
+        vec3 sample_position = position + (tangent * direction.x + bitangent * direction.y
+            + normal * direction.z) * RADIUS * ratio;
+
+        vec4 projected = projection * vec4(sample_position, 1.0f);
+        vec2 sample_uv = projected.xy / projected.w * 0.5f + 0.5f;
+        float scene_z = view_position_at(sample_uv).z;
+        float in_range = smoothstep(0.0f, 1.0f, RADIUS / abs(position.z - scene_z));
+        occlusion += (scene_z >= sample_position.z + BIAS ? 1.0f : 0.0f) * in_range;
+    }
+    float ambient_occlusion = clamp(1.0f - occlusion / float(SAMPLE_COUNT) * strength, 0.0f, 1.0f);
+    outColor = vec4(color * ambient_occlusion, 1.0f);
+}
@@ -0,0 +1,20 @@
+#version 300 es
+
+precision highp float;
+in vec2 v_uv;
+
+uniform sampler2D color_texture;
+// exposure
+uniform float strength;
+
+out vec4 outColor;
+
+// filmic curve fitted to ACES by Krzysztof Narkowicz
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51f * x + 0.03f)) / (x * (2.43f * x + 0.59f) + 0.14f), 0.0f, 1.0f);
+}
+
+void main() {
+    vec3 color = texture(color_texture, v_uv).rgb * strength;
+    outColor = vec4(aces(color), 1.0f);
+}
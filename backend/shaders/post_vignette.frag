@@ -0,0 +1,17 @@
+#version 300 es
+
+precision highp float;
+in vec2 v_uv;
+
+uniform sampler2D color_texture;
+uniform float strength;
+
+out vec4 outColor;
+
+void main() {
+    vec3 color = texture(color_texture, v_uv).rgb;
+    // 0 at the center, 1 in the corners
+    float distance = length(v_uv - 0.5f) * sqrt(2.0f);
+    float darkening = strength * smoothstep(0.4f, 1.0f, distance);
+    outColor = vec4(color * (1.0f - darkening), 1.0f);
+}
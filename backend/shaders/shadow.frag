@@ -0,0 +1,7 @@
+#version 300 es
+
+precision highp float;
+
+// depth only, nothing to write
+void main() {
+}
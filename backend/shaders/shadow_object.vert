@@ -0,0 +1,11 @@
+#version 300 es
+
+// same location as in cube.vert so that the object vertex arrays can be reused
+layout (location = 0) in vec3 position;
+
+uniform mat4 model;
+#include "include/frame_data.glsl"
+
+void main() {
+    gl_Position = shadow_matrix * model * vec4(position, 1.0f);
+}
@@ -0,0 +1,31 @@
+#version 300 es
+
+precision highp float;
+in vec3 v_ray;
+
+uniform vec3 sun_position;
+uniform vec3 zenith_color;
+uniform vec3 horizon_color;
+
+out vec4 outColor;
+
+const float SUN_SIZE = 0.9995f;
+const float MOON_SIZE = 0.9997f;
+const vec3 SUN_COLOR = vec3(1.0f, 0.95f, 0.8f);
+const vec3 MOON_COLOR = vec3(0.85f, 0.87f, 0.95f);
+
+void main() {
+    vec3 ray = normalize(v_ray);
+    vec3 to_sun = sun_position;
+
+    float height = clamp(ray.y, 0.0f, 1.0f);
+    vec3 color = mix(horizon_color, zenith_color, sqrt(height));
+
+    float sun = dot(ray, to_sun);
+    color += SUN_COLOR * smoothstep(SUN_SIZE - 0.0005f, SUN_SIZE, sun);
+    // soft glow around the sun
+    color += SUN_COLOR * 0.25f * pow(max(sun, 0.0f), 64.0f);
+    color += MOON_COLOR * smoothstep(MOON_SIZE - 0.0003f, MOON_SIZE, dot(ray, -to_sun));
+
+    outColor = vec4(color, 1.0f);
+}
@@ -0,0 +1,14 @@
+#version 300 es
+
+in vec3 position;
+
+#include "include/frame_data.glsl"
+
+out vec3 v_ray;
+
+void main() {
+    // undo the projection then the camera rotation to get a world space view ray
+    vec3 view_ray = vec3(position.x / projection[0][0], position.y / projection[1][1], -1.0f);
+    v_ray = transpose(mat3(view)) * view_ray;
+    gl_Position = vec4(position.xy, 0.0f, 1.0f);
+}
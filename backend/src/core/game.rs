@@ -1,25 +1,34 @@
-use std::rc::Rc;
-
 use egui::{Label, RichText};
 use fastrand::Rng;
-use glam::{vec3, UVec3, Vec3};
-use glow::HasContext;
-use wasm_bindgen::JsValue;
+use glam::{vec3, vec4, UVec3, Vec3};
+use log::{info, warn};
 
 use crate::{
-    graphics::{Camera, TextureDef, TextureLoader, TextureType},
+    graphics::{
+        Camera, Fog, FogMode, FrameData, Lighting, PostProcessStack, ResourcePackLoader, ShadowMap,
+        Sky, SkyColors, DEFAULT_SHADOW_MAP_SIZE,
+    },
     gui::EguiBackend,
-    objects::{Gizmo, Player, Transform},
-    utils::performance_now,
-    world::{TestGenerator, World},
+    objects::{DebugBoxes, Gizmo, Player, Transform},
+    utils::{performance_now, GlContext, GlRenderFlags, GlStateStats},
+    world::{
+        BlockAppearance, BlockPos, BlockTextureLayers, BlockType, ChunkPos, TestGenerator, World,
+        CHUNK_SIZE,
+    },
 };
 
-use super::{HandleInputs, InputEventType, InputSystem, Time};
+#[cfg(feature = "shader_hot_reload")]
+use crate::graphics::ShaderReloader;
+
+use super::{HandleInputs, InputEventType, InputSystem, Time, TimeOfDay, HOURS_PER_DAY};
+
+/* (name, manifest) of the packs offered in the pause menu, the first one is loaded at start */
+const RESOURCE_PACKS: [(&str, &str); 2] = [
+    ("Default", "data/packs/default/pack.txt"),
+    ("Autumn", "data/packs/autumn/pack.txt"),
+];
 
-const GRASS_TEXTURE_PATH: &str = "data/textures/blocks/grass_block_side.png";
-const SAND_TEXTURE_PATH: &str = "data/textures/blocks/sand.png";
-const DIRT_TEXTURE_PATH: &str = "data/textures/blocks/dirt.png";
-const BLOCKS_ATLAS_PATH: &str = "data/textures/blocks/blocks_atlas.png";
+const MEGABYTE: usize = 1024 * 1024;
 
 type WorldGenerator = TestGenerator;
 
@@ -29,28 +38,44 @@ fn make_generator(rng: Rng) -> WorldGenerator {
 
 #[derive(Debug)]
 pub struct Game {
-    texture_loader: TextureLoader,
+    /* until the resource pack is loaded, the world is drawn once the first one is */
+    pack_loader: Option<ResourcePackLoader>,
+    /* name of the pack in use, or what went wrong with the last one */
+    pack_status: Result<String, String>,
     world: World<WorldGenerator>,
     camera: Camera,
+    lighting: Lighting,
+    time_of_day: TimeOfDay,
+    fog: Fog,
 
     input_system: InputSystem,
-    loaded_textures: Vec<Rc<TextureDef>>,
     time: Time,
     is_paused: bool,
     gui_state: GuiState,
     tick_times: [f64; 30],
     tick_time: f64,
     tick_index: usize,
+    /* GL state changes made during the previous frame */
+    gl_state_stats: GlStateStats,
     gizmo: Gizmo,
     player: Player,
+    debug_boxes: DebugBoxes,
 
+    sky: Option<Sky>,
+    frame_data: Option<FrameData>,
+    shadow_map: Option<ShadowMap>,
+    post_process: Option<PostProcessStack>,
     egui: Option<EguiBackend>,
+    #[cfg(feature = "shader_hot_reload")]
+    shader_reloader: ShaderReloader,
 }
 
 #[derive(Debug)]
 struct GuiState {
     show_pause_menu: bool,
     show_info: bool,
+    show_chunk_bounds: bool,
+    pack_url: String,
 }
 
 impl GuiState {
@@ -64,55 +89,76 @@ impl Default for GuiState {
         Self {
             show_pause_menu: false,
             show_info: true,
+            show_chunk_bounds: false,
+            pack_url: RESOURCE_PACKS[0].1.to_string(),
         }
     }
 }
 
 impl Game {
-    pub fn new() -> Result<Self, JsValue> {
+    pub fn new() -> Result<Self, String> {
         let rng = Rng::with_seed(0);
         let game = Game {
-            texture_loader: TextureLoader::new(10)?,
+            pack_loader: None,
+            pack_status: Ok(String::new()),
             world: World::new(make_generator(rng)),
-            loaded_textures: Vec::new(),
             camera: Camera::new(Vec3 {
                 x: -10.0,
                 y: 5.0,
                 z: 0.0,
             }),
+            lighting: Lighting::default(),
+            time_of_day: TimeOfDay::default(),
+            fog: Fog::default(),
             input_system: InputSystem::new()?,
             is_paused: false,
             time: Time::default(),
+            sky: None,
+            frame_data: None,
+            shadow_map: None,
+            post_process: None,
             egui: None,
             gui_state: GuiState::default(),
             tick_time: 0.0,
             tick_times: [0.0; 30],
             tick_index: 0,
+            gl_state_stats: GlStateStats::default(),
             gizmo: Gizmo::new(Vec3::ZERO, 10.0),
             player: Player::new(vec3(0.0, 0.5, 0.0)),
+            debug_boxes: DebugBoxes::default(),
+            #[cfg(feature = "shader_hot_reload")]
+            shader_reloader: ShaderReloader::new(),
         };
 
         Ok(game)
     }
 
-    pub unsafe fn load(&mut self, gl: &glow::Context) -> Result<(), String> {
-        for (path, t) in [(BLOCKS_ATLAS_PATH, TextureType::Texture2DArray(16))] {
-            let key = self.texture_loader.load(gl, path, t)?;
-            self.loaded_textures.push(Rc::new((t, key)));
-        }
+    /* feeds an event from the platform layer, handled on the next tick */
+    pub fn push_input(&self, event: InputEventType) {
+        self.input_system.push_event(event);
+    }
+
+    pub fn get_world_info(&self) -> String {
+        self.world.get_info()
+    }
+
+    pub unsafe fn load(&mut self, gl: &GlContext) -> Result<(), String> {
+        self.load_resource_pack(RESOURCE_PACKS[0].1);
 
-        gl.enable(glow::DEPTH_TEST);
-        gl.depth_func(glow::LESS);
-        gl.enable(glow::CULL_FACE);
+        gl.set_flag(GlRenderFlags::DepthTest, true);
+        gl.set_depth_func(glow::LESS);
+        gl.set_flag(GlRenderFlags::CullFace, true);
 
-        self.world
-            .setup_graphics(gl, self.loaded_textures[0].clone())?;
+        self.sky = Some(Sky::new(gl)?);
+        self.frame_data = Some(FrameData::new(gl)?);
+        self.shadow_map = Some(ShadowMap::new(gl, DEFAULT_SHADOW_MAP_SIZE)?);
+        self.post_process = Some(PostProcessStack::new(gl)?);
         self.egui = Some(EguiBackend::new(gl));
 
         Ok(())
     }
 
-    pub fn tick(&mut self, gl: &glow::Context, time: f64) -> Result<(), String> {
+    pub fn tick(&mut self, gl: &GlContext, time: f64) -> Result<(), String> {
         let start = performance_now();
 
         self.time.update(time);
@@ -146,8 +192,9 @@ impl Game {
         }
     }
 
-    fn update(&mut self, gl: &glow::Context) -> Result<(), String> {
+    fn update(&mut self, gl: &GlContext) -> Result<(), String> {
         if !self.is_paused {
+            self.time_of_day.update(&self.time);
             self.player.update(&self.time);
             if let Some(player) = self.player.get_gameobject() {
                 self.world.update(gl, &self.time, player.get_position())?;
@@ -158,6 +205,7 @@ impl Game {
             self.camera.target = player.get_position();
         }
         self.camera.update(&self.time);
+        self.lighting.set_time_of_day(&self.time_of_day);
 
         self.gizmo.update(&self.time);
 
@@ -169,13 +217,15 @@ impl Game {
 
         for event in inputs.get_events() {
             match event {
-                InputEventType::KeyDown(event) => {
+                InputEventType::KeyDown(_) => {
                     if inputs.is_key_down("Escape") {
                         self.is_paused = !self.is_paused;
                     }
                     if inputs.is_key_down("F2") {
                         self.gui_state.show_info = !self.gui_state.show_info;
-                        event.prevent_default();
+                    }
+                    if inputs.is_key_down("KeyL") && !self.gui_state.eats_input() {
+                        self.toggle_lava();
                     }
                 }
                 _ => {}
@@ -197,28 +247,196 @@ impl Game {
         self.input_system.clear_events();
     }
 
-    fn render(&mut self, gl: &glow::Context) -> Result<(), String> {
-        self.texture_loader.tick(&gl)?;
+    fn render(&mut self, gl: &GlContext) -> Result<(), String> {
+        self.gl_state_stats = gl.take_state_stats();
+        self.update_resource_pack(gl);
+        #[cfg(feature = "shader_hot_reload")]
+        self.shader_reloader.update(gl);
+        let sky_colors = SkyColors::at(&self.time_of_day);
+        // fog fades into the horizon so that chunks streaming in at the edge are hidden
+        self.fog.color = sky_colors.horizon;
+        self.fog.end = self.world.get_load_radius();
+        if let Some(shadow_map) = &mut self.shadow_map {
+            shadow_map.fit(&self.camera, self.lighting.sun_direction);
+        }
+        if let Some(frame_data) = &mut self.frame_data {
+            let (camera, time, lighting, fog) =
+                (&self.camera, &self.time, &self.lighting, &self.fog);
+            frame_data.update(gl, camera, time, lighting, fog, self.shadow_map.as_ref());
+        }
+        if let Some(shadow_map) = &self.shadow_map {
+            if shadow_map.enabled {
+                let viewport = shadow_map.begin(gl);
+                self.world.render_shadows(gl, shadow_map);
+                if let Some(player) = self.player.get_gameobject() {
+                    player.render_shadow(gl, shadow_map)?;
+                }
+                shadow_map.end(gl, viewport);
+            }
+        }
+        if let Some(post_process) = &mut self.post_process {
+            post_process.begin_scene(gl)?;
+        }
         unsafe {
-            gl.clear_color(0.0, 0.0, 0.0, 1.0);
+            let clear_color = sky_colors.horizon;
+            gl.clear_color(clear_color.x, clear_color.y, clear_color.z, 1.0);
             gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
         }
+        if let Some(sky) = &mut self.sky {
+            sky.render(gl, &self.time_of_day)?;
+        }
+        // sampled by the materials receiving shadows, after the shadow pass rendered into it
+        if let Some(shadow_map) = &self.shadow_map {
+            shadow_map.bind(gl);
+        }
         self.world.render(gl, &self.camera);
+        self.player.render_lazy(gl)?;
+        self.gizmo.render_lazy(gl)?;
+        if self.gui_state.show_chunk_bounds {
+            self.render_chunk_bounds(gl)?;
+        }
+        if let Some(post_process) = &mut self.post_process {
+            post_process.finish(gl, &self.camera)?;
+        }
 
-        self.player.render_lazy(gl, &self.camera);
-        self.gizmo.render_lazy(gl, &self.camera);
-
+        // drawn last, straight to the screen, on top of the post-processed scene
         self.draw_ui(gl);
 
         Ok(())
     }
 
-    fn draw_ui(&mut self, gl: &glow::Context) {
+    /* places a lava block where the player stands, or removes it. The light around is updated
+     * and the meshes rebuilt on the next world update */
+    fn toggle_lava(&mut self) {
+        let Some(player) = self.player.get_gameobject() else {
+            return;
+        };
+        let pos: BlockPos = player.get_position().floor().as_ivec3().into();
+        let block = match self.world.get_block(pos) {
+            Some(BlockType::Lava) => BlockType::Empty,
+            Some(_) => BlockType::Lava,
+            None => return,
+        };
+        self.world.set_block(pos, block);
+    }
+
+    /* the current pack stays in use until this one is loaded */
+    fn load_resource_pack(&mut self, manifest_url: &str) {
+        info!("Game: loading resource pack {manifest_url}");
+        self.pack_loader = Some(ResourcePackLoader::new(manifest_url));
+    }
+
+    /* a pack that fails to load is reported, the game goes on with the previous one */
+    fn update_resource_pack(&mut self, gl: &GlContext) {
+        let Some(loader) = &mut self.pack_loader else {
+            return;
+        };
+        let Some(pack) = loader.poll(gl) else {
+            return;
+        };
+        self.pack_loader = None;
+        self.pack_status = pack
+            .and_then(|pack| {
+                let appearance = BlockAppearance {
+                    texture: pack.texture_array.texture,
+                    layers: BlockTextureLayers::from_names(&pack.texture_array.layers)?,
+                    shaders: pack.shaders,
+                };
+                self.world.setup_graphics(gl, &appearance)?;
+                Ok(pack.name)
+            })
+            .inspect_err(|e| warn!("Game: can't use resource pack: {e}"));
+    }
+
+    fn render_chunk_bounds(&mut self, gl: &GlContext) -> Result<(), String> {
+        let player_chunk = self.player.get_gameobject().map(|player| -> ChunkPos {
+            let block_pos: BlockPos = player.get_position().as_ivec3().into();
+            block_pos.into()
+        });
+        let size = Vec3::splat(CHUNK_SIZE as f32);
+        for chunk_pos in self.world.iter_meshed_chunks() {
+            let color = match Some(chunk_pos) == player_chunk {
+                true => vec4(1.0, 1.0, 0.0, 1.0),
+                false => vec4(0.0, 1.0, 0.0, 1.0),
+            };
+            let min = chunk_pos.get_center_block_pos().as_vec3();
+            self.debug_boxes.add(min, size, color);
+        }
+        self.debug_boxes.render(gl)
+    }
+
+    fn draw_ui(&mut self, gl: &GlContext) {
+        let mut pack_to_load = None;
         if let Some(egui) = &mut self.egui {
             egui.render_ui(gl, |ctx| {
                 if self.is_paused {
                     egui::Window::new("PAUSE").show(ctx, |ui| {
                         ui.label("Game is paused");
+                        let mut hours = self.time_of_day.get_hours();
+                        let slider = egui::Slider::new(&mut hours, 0.0..=HOURS_PER_DAY)
+                            .text("Time of day");
+                        if ui.add(slider).changed() {
+                            self.time_of_day.set_hours(hours);
+                        }
+                        ui.checkbox(&mut self.time_of_day.frozen, "Freeze time of day");
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label("Fog:");
+                            ui.radio_value(&mut self.fog.mode, FogMode::Off, "Off");
+                            ui.radio_value(&mut self.fog.mode, FogMode::Linear, "Linear");
+                            let exponential = FogMode::Exponential;
+                            ui.radio_value(&mut self.fog.mode, exponential, "Exponential");
+                        });
+                        let fog_start = &mut self.fog.start_ratio;
+                        ui.add(egui::Slider::new(fog_start, 0.0..=1.0).text("Fog start"));
+                        let fog_density = &mut self.fog.density;
+                        ui.add(egui::Slider::new(fog_density, 0.5..=8.0).text("Fog density"));
+                        if let Some(shadow_map) = &mut self.shadow_map {
+                            ui.separator();
+                            ui.checkbox(&mut shadow_map.enabled, "Shadows");
+                        }
+                        let show_chunk_bounds = &mut self.gui_state.show_chunk_bounds;
+                        ui.checkbox(show_chunk_bounds, "Chunk bounds");
+                        let mut budget = self.world.get_chunk_memory_budget() / MEGABYTE;
+                        let slider = egui::Slider::new(&mut budget, 16..=1024)
+                            .text("Chunk memory budget (MB)");
+                        if ui.add(slider).changed() {
+                            self.world.set_chunk_memory_budget(budget * MEGABYTE);
+                        }
+                        ui.separator();
+                        let loading = self.pack_loader.as_ref().map(|l| l.get_url());
+                        let pack_url = &mut self.gui_state.pack_url;
+                        pack_to_load = resource_pack_ui(ui, pack_url, &self.pack_status, loading);
+                        if let Some(post_process) = &mut self.post_process {
+                            ui.separator();
+                            ui.label("Post processing, applied top to bottom:");
+                            let mut move_up = None;
+                            for (i, settings) in post_process.effects.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    let name = settings.effect.get_name();
+                                    ui.checkbox(&mut settings.enabled, name);
+                                    let range = settings.effect.get_strength_range();
+                                    if let Some((min, max)) = range {
+                                        let strength = &mut settings.strength;
+                                        ui.add(egui::Slider::new(strength, min..=max));
+                                    }
+                                    if i > 0 && ui.small_button("Up").clicked() {
+                                        move_up = Some(i);
+                                    }
+                                });
+                            }
+                            if let Some(i) = move_up {
+                                post_process.move_up(i);
+                            }
+                        }
+                    });
+                }
+                #[cfg(feature = "shader_hot_reload")]
+                if self.shader_reloader.get_errors().next().is_some() {
+                    egui::Window::new("Shader errors").show(ctx, |ui| {
+                        for error in self.shader_reloader.get_errors() {
+                            ui.label(RichText::new(error).monospace());
+                        }
                     });
                 }
                 if self.gui_state.show_info {
@@ -233,7 +451,7 @@ impl Game {
                                         let pos = player.get_position();
                                         let chunk_pos = player.get_chunk_position();
                                         ui.colored_label(egui::Color32::WHITE,RichText::new(format!(
-                                            "git rev: {}\nPlayer position: {:.1},{:.1},{:.1} (chunk {},{},{})\nFPS: {:.1}\nWorld: {}",
+                                            "git rev: {}\nPlayer position: {:.1},{:.1},{:.1} (chunk {},{},{})\nFPS: {:.1}\nGL state changes: {} ({} skipped)\nGL objects: {}\nGPU memory: {}\nWorld: {}",
                                             env!("GIT_HASH"),
                                             pos.x,
                                             pos.y,
@@ -242,6 +460,10 @@ impl Game {
                                             chunk_pos.y,
                                             chunk_pos.z,
                                             1000.0 / self.tick_time,
+                                            self.gl_state_stats.changes,
+                                            self.gl_state_stats.skipped,
+                                            gl.get_resource_counts(),
+                                            gl.get_memory_usage(),
                                             self.world.get_info()
                                         )).monospace());
                                     }
@@ -250,5 +472,40 @@ impl Game {
                 }
             });
         }
+        // the UI borrows the game, the pack is loaded once it is drawn
+        if let Some(url) = pack_to_load {
+            self.load_resource_pack(&url);
+        }
     }
 }
+
+/* pack picker of the pause menu, gives the manifest to load once one is picked */
+fn resource_pack_ui(
+    ui: &mut egui::Ui,
+    pack_url: &mut String,
+    status: &Result<String, String>,
+    loading: Option<&str>,
+) -> Option<String> {
+    let mut load = None;
+    ui.horizontal(|ui| {
+        ui.label("Resource pack:");
+        for (name, url) in RESOURCE_PACKS {
+            if ui.selectable_label(pack_url == url, name).clicked() {
+                *pack_url = url.to_string();
+                load = Some(url.to_string());
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(pack_url);
+        if ui.button("Load").clicked() {
+            load = Some(pack_url.clone());
+        }
+    });
+    match (loading, status) {
+        (Some(url), _) => ui.label(format!("Loading {url}...")),
+        (None, Ok(name)) => ui.label(format!("Using {name}")),
+        (None, Err(e)) => ui.colored_label(egui::Color32::RED, e),
+    };
+    load
+}
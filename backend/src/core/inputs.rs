@@ -1,41 +1,56 @@
-use std::{
-    cell::RefCell,
-    collections::HashSet,
-    rc::Rc,
-    sync::{Arc, Mutex},
-};
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
 
 use glam::Vec2;
 use log::warn;
-use wasm_bindgen::{closure::Closure, JsCast, JsValue};
-use web_sys::EventTarget;
 
-use crate::utils;
+/* state of the modifier keys when an event happened */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
 
-#[derive(Debug)]
-pub enum InputEventType {
-    MouseDown(Rc<web_sys::MouseEvent>),
-    MouseUp(Rc<web_sys::MouseEvent>),
-    MouseMove(Rc<web_sys::MouseEvent>),
-    MouseWheel(Rc<web_sys::WheelEvent>),
-    KeyDown(Rc<web_sys::KeyboardEvent>),
-    KeyUp(Rc<web_sys::KeyboardEvent>),
+/* follows the DOM MouseEvent conventions so that every platform maps onto the same values */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MouseEvent {
+    /* in pixels from the top left corner of the view */
+    pub position: Vec2,
+    /* button that changed: 0 primary, 1 middle, 2 secondary */
+    pub button: i16,
+    /* buttons held down, as a bitmask: 1 primary, 2 secondary, 4 middle */
+    pub buttons: u16,
+    pub modifiers: Modifiers,
 }
 
-impl Clone for InputEventType {
-    fn clone(&self) -> Self {
-        match self {
-            InputEventType::MouseDown(e) => InputEventType::MouseDown(e.clone()),
-            InputEventType::MouseUp(e) => InputEventType::MouseUp(e.clone()),
-            InputEventType::MouseMove(e) => InputEventType::MouseMove(e.clone()),
-            InputEventType::MouseWheel(e) => InputEventType::MouseWheel(e.clone()),
-            InputEventType::KeyDown(e) => InputEventType::KeyDown(e.clone()),
-            InputEventType::KeyUp(e) => InputEventType::KeyUp(e.clone()),
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WheelEvent {
+    /* positive when scrolling down */
+    pub delta_y: f64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyEvent {
+    /* logical key, named like KeyboardEvent.key: "a", "Escape", "F2" */
+    pub key: String,
+    /* physical key, named like KeyboardEvent.code: "KeyW", "Space", "F2" */
+    pub code: String,
+    pub repeat: bool,
+    pub modifiers: Modifiers,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEventType {
+    MouseDown(MouseEvent),
+    MouseUp(MouseEvent),
+    MouseMove(MouseEvent),
+    MouseWheel(WheelEvent),
+    KeyDown(KeyEvent),
+    KeyUp(KeyEvent),
+}
+
+#[derive(Debug, Default)]
 pub struct InputState {
     mouse_pos: Vec2,
     mouse_delta: Vec2,
@@ -46,13 +61,7 @@ pub struct InputState {
 
 impl InputState {
     pub fn new() -> Self {
-        Self {
-            mouse_pos: Vec2::new(0.0, 0.0),
-            mouse_delta: Vec2::new(0.0, 0.0),
-            mouse_down: false,
-            keys_down: HashSet::new(),
-            current_events: Vec::new(),
-        }
+        Self::default()
     }
     pub fn _get_mouse_pos(&self) -> Vec2 {
         self.mouse_pos
@@ -76,18 +85,17 @@ impl InputState {
             InputEventType::MouseDown(_) => self.mouse_down = true,
             InputEventType::MouseUp(_) => self.mouse_down = false,
             InputEventType::MouseMove(e) => {
-                let new_pos = Vec2::new(e.client_x() as _, e.client_y() as _);
-                self.mouse_delta = new_pos - self.mouse_pos;
-                self.mouse_pos = new_pos;
+                self.mouse_delta = e.position - self.mouse_pos;
+                self.mouse_pos = e.position;
             }
             InputEventType::KeyDown(e) => {
-                if !e.repeat() && !self.keys_down.insert(e.code()) {
-                    warn!("Key already down: {}", e.code());
+                if !e.repeat && !self.keys_down.insert(e.code.clone()) {
+                    warn!("Key already down: {}", e.code);
                 }
             }
             InputEventType::KeyUp(e) => {
-                if !self.keys_down.remove(&e.code()) {
-                    warn!("Key not down: {}", e.code());
+                if !self.keys_down.remove(&e.code) {
+                    warn!("Key not down: {}", e.code);
                 }
             }
             InputEventType::MouseWheel(_) => (),
@@ -112,110 +120,142 @@ pub trait HandleInputs {
     fn handle_inputs(&mut self, inputs: &InputState);
 }
 
+/* collects the events of a frame, pushed by the platform layer (canvas listeners on the web) */
 #[derive(Debug)]
 pub struct InputSystem {
     current_inputs: Rc<RefCell<InputState>>,
-    closures: Vec<Arc<Mutex<Closure<dyn FnMut(JsValue)>>>>,
+    /* only held to keep the DOM callbacks alive */
+    #[cfg(target_arch = "wasm32")]
+    _listeners: web::CanvasListeners,
 }
 
 impl InputSystem {
-    pub fn new() -> Result<Self, JsValue> {
-        let mut system = Self {
-            current_inputs: Rc::new(RefCell::new(InputState::new())),
-            closures: Vec::new(),
-        };
-        system.sub_to_all_events()?;
-
-        Ok(system)
+    pub fn new() -> Result<Self, String> {
+        let current_inputs = Rc::new(RefCell::new(InputState::new()));
+        Ok(Self {
+            #[cfg(target_arch = "wasm32")]
+            _listeners: web::CanvasListeners::new(current_inputs.clone())
+                .map_err(|e| format!("Could not listen to canvas events: {e:?}"))?,
+            current_inputs,
+        })
     }
 
     pub fn get_inputs(&self) -> InputState {
         self.current_inputs.borrow().clone()
     }
 
+    pub fn push_event(&self, event: InputEventType) {
+        self.current_inputs.borrow_mut().add_event(event);
+    }
+
     pub fn clear_events(&mut self) {
         self.current_inputs.borrow_mut().current_events.clear();
     }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use std::{cell::RefCell, rc::Rc};
+
+    use glam::Vec2;
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use web_sys::EventTarget;
+
+    use super::{InputEventType, InputState, KeyEvent, Modifiers, MouseEvent, WheelEvent};
+    use crate::utils;
+
+    /* keys the game handles, kept away from the browser (F2 would focus the address bar) */
+    const CAPTURED_KEYS: [&str; 1] = ["F2"];
+
+    #[derive(Debug)]
+    pub struct CanvasListeners {
+        closures: Vec<Closure<dyn FnMut(JsValue)>>,
+    }
+
+    impl CanvasListeners {
+        pub fn new(inputs: Rc<RefCell<InputState>>) -> Result<Self, JsValue> {
+            let mut listeners = Self {
+                closures: Vec::new(),
+            };
+            let canvas = &utils::get_canvas()?;
+            let mouse = |event: &JsValue| to_mouse_event(event.unchecked_ref());
+            listeners.listen(canvas, "mousedown", &inputs, move |event| {
+                InputEventType::MouseDown(mouse(&event))
+            })?;
+            listeners.listen(canvas, "mouseup", &inputs, move |event| {
+                InputEventType::MouseUp(mouse(&event))
+            })?;
+            listeners.listen(canvas, "mousemove", &inputs, move |event| {
+                InputEventType::MouseMove(mouse(&event))
+            })?;
+            listeners.listen(canvas, "wheel", &inputs, |event| {
+                let event: &web_sys::WheelEvent = event.unchecked_ref();
+                InputEventType::MouseWheel(WheelEvent {
+                    delta_y: event.delta_y(),
+                })
+            })?;
+            listeners.listen(canvas, "keydown", &inputs, |event| {
+                let event: &web_sys::KeyboardEvent = event.unchecked_ref();
+                if CAPTURED_KEYS.contains(&event.code().as_str()) {
+                    event.prevent_default();
+                }
+                InputEventType::KeyDown(to_key_event(event))
+            })?;
+            listeners.listen(canvas, "keyup", &inputs, |event| {
+                InputEventType::KeyUp(to_key_event(event.unchecked_ref()))
+            })?;
+
+            Ok(listeners)
+        }
+
+        fn listen<F>(
+            &mut self,
+            target: &EventTarget,
+            event_type: &str,
+            inputs: &Rc<RefCell<InputState>>,
+            mut convert: F,
+        ) -> Result<(), JsValue>
+        where
+            F: 'static + FnMut(JsValue) -> InputEventType,
+        {
+            let inputs = inputs.clone();
+            let closure = Closure::wrap(Box::new(move |event: JsValue| {
+                inputs.borrow_mut().add_event(convert(event));
+            }) as Box<dyn FnMut(JsValue)>);
+            target
+                .add_event_listener_with_callback(event_type, closure.as_ref().unchecked_ref())?;
+
+            // Keep the closure from being dropped
+            self.closures.push(closure);
+
+            Ok(())
+        }
+    }
+
+    fn to_modifiers(get_modifier_state: impl Fn(&str) -> bool) -> Modifiers {
+        Modifiers {
+            shift: get_modifier_state("Shift"),
+            ctrl: get_modifier_state("Control"),
+            alt: get_modifier_state("Alt"),
+            meta: get_modifier_state("Meta"),
+        }
+    }
 
-    fn sub_to_all_events(&mut self) -> Result<(), JsValue> {
-        let canvas = &utils::get_canvas()?;
-        self.add_event_listener(
-            canvas,
-            "mousedown",
-            Box::new(|event: JsValue| {
-                let event: web_sys::MouseEvent = event.dyn_into().unwrap();
-                InputEventType::MouseDown(Rc::new(event))
-            }),
-        )?;
-        self.add_event_listener(
-            canvas,
-            "mouseup",
-            Box::new(|event: JsValue| {
-                let event: web_sys::MouseEvent = event.dyn_into().unwrap();
-                InputEventType::MouseUp(Rc::new(event))
-            }),
-        )?;
-        self.add_event_listener(
-            canvas,
-            "mousemove",
-            Box::new(|event: JsValue| {
-                let event: web_sys::MouseEvent = event.dyn_into().unwrap();
-                InputEventType::MouseMove(Rc::new(event))
-            }),
-        )?;
-        self.add_event_listener(
-            canvas,
-            "wheel",
-            Box::new(|event: JsValue| {
-                let event: web_sys::WheelEvent = event.dyn_into().unwrap();
-                InputEventType::MouseWheel(Rc::new(event))
-            }),
-        )?;
-
-        self.add_event_listener(
-            canvas,
-            "keydown",
-            Box::new(|event: JsValue| {
-                let event: web_sys::KeyboardEvent = event.dyn_into().unwrap();
-                InputEventType::KeyDown(Rc::new(event))
-            }),
-        )?;
-
-        self.add_event_listener(
-            canvas,
-            "keyup",
-            Box::new(|event: JsValue| {
-                let event: web_sys::KeyboardEvent = event.dyn_into().unwrap();
-                InputEventType::KeyUp(Rc::new(event))
-            }),
-        )?;
-
-        Ok(())
-    }
-
-    fn add_event_listener<F>(
-        &mut self,
-        target: &EventTarget,
-        event_type: &str,
-        mut convert: Box<F>,
-    ) -> Result<(), JsValue>
-    where
-        F: 'static + FnMut(JsValue) -> InputEventType,
-    {
-        let inputs = self.current_inputs.clone();
-        let closure = Closure::wrap(Box::new(move |event: JsValue| {
-            let event_type = convert(event);
-            inputs.borrow_mut().add_event(event_type);
-        }) as Box<dyn FnMut(JsValue)>);
-        let rc_closure = Arc::new(Mutex::new(closure));
-        target.add_event_listener_with_callback(
-            event_type,
-            rc_closure.lock().unwrap().as_ref().unchecked_ref(),
-        )?;
-
-        // Keep the closure from being dropped
-        self.closures.push(rc_closure);
-
-        Ok(())
+    fn to_mouse_event(event: &web_sys::MouseEvent) -> MouseEvent {
+        MouseEvent {
+            position: Vec2::new(event.client_x() as _, event.client_y() as _),
+            button: event.button(),
+            buttons: event.buttons(),
+            modifiers: to_modifiers(|key| event.get_modifier_state(key)),
+        }
+    }
+
+    fn to_key_event(event: &web_sys::KeyboardEvent) -> KeyEvent {
+        KeyEvent {
+            key: event.key(),
+            code: event.code(),
+            repeat: event.repeat(),
+            modifiers: to_modifiers(|key| event.get_modifier_state(key)),
+        }
     }
 }
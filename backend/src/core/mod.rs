@@ -1,7 +1,6 @@
 mod game;
 mod inputs;
-mod time;
 
+pub use engine_core::time::*;
 pub use game::Game;
 pub use inputs::*;
-pub use time::Time;
@@ -61,10 +61,32 @@ impl Camera {
         }
     }
 
+    pub fn get_position(&self) -> Vec3 {
+        self.target + self.target_offset
+    }
+
+    /* corners of the view frustum cut between the near and far distances, in world space */
+    pub fn get_frustum_corners(&self, near: f32, far: f32) -> [Vec3; 8] {
+        let inverse = (self.projection * self.look_at).inverse();
+        let mut corners = [Vec3::ZERO; 8];
+        for (i, (x, y)) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+            .into_iter()
+            .enumerate()
+        {
+            let near_plane = inverse.project_point3(Vec3::new(x, y, -1.0));
+            let far_plane = inverse.project_point3(Vec3::new(x, y, 1.0));
+            // view depth is linear along each corner ray
+            let ray = far_plane - near_plane;
+            corners[i] = near_plane + ray * (near - NEAR_VIEW) / (FAR_VIEW - NEAR_VIEW);
+            corners[i + 4] = near_plane + ray * (far - NEAR_VIEW) / (FAR_VIEW - NEAR_VIEW);
+        }
+        corners
+    }
+
     pub fn update(&mut self, time: &Time) {
         // let velocity = velocity.normalize_or_zero() * self.camera_speed;
         // self.position += velocity * (time.delta_time() as f32 / 5.0);
-        let position = self.target + self.target_offset;
+        let position = self.get_position();
         self.look_at = Mat4::look_at_rh(position, self.target, Vec3::Y);
     }
 }
@@ -87,7 +109,7 @@ impl HandleInputs for Camera {
         for e in inputs.get_events() {
             match e {
                 InputEventType::MouseMove(e) => {
-                    if e.buttons() % 2 == 1 {
+                    if e.buttons & 1 == 1 {
                         let speed = self.mouse_sensitivity;
                         let d = inputs.get_mouse_delta();
                         let yaw = -d.x * speed.to_radians();
@@ -107,7 +129,7 @@ impl HandleInputs for Camera {
                     }
                 }
                 InputEventType::MouseWheel(e) => {
-                    let factor = if e.delta_y() >= 0.0 {
+                    let factor = if e.delta_y >= 0.0 {
                         WHEEL_ZOOM_FACTOR
                     } else {
                         1.0 / WHEEL_ZOOM_FACTOR
@@ -0,0 +1,35 @@
+use glam::Vec3;
+
+const DEFAULT_START_RATIO: f32 = 0.6;
+const DEFAULT_DENSITY: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FogMode {
+    Off = 0,
+    Linear,
+    Exponential,
+}
+
+#[derive(Debug, Clone)]
+pub struct Fog {
+    pub mode: FogMode,
+    pub color: Vec3,
+    /* distance at which geometry is fully fogged, usually the world load radius */
+    pub end: f32,
+    /* where linear fog starts, as a fraction of end */
+    pub start_ratio: f32,
+    /* exponential fog density, relative to end */
+    pub density: f32,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self {
+            mode: FogMode::Linear,
+            color: Vec3::ZERO,
+            end: 1000.0,
+            start_ratio: DEFAULT_START_RATIO,
+            density: DEFAULT_DENSITY,
+        }
+    }
+}
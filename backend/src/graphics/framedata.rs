@@ -0,0 +1,110 @@
+use glam::{Mat4, Vec3};
+
+use crate::{
+    core::Time,
+    utils::{BufferHandle, GlContext},
+};
+
+use super::{Camera, Fog, Lighting, ShadowMap};
+
+/* binding point of the FrameData uniform block, shared by every program declaring it */
+pub const FRAME_DATA_BINDING: u32 = 0;
+pub const FRAME_DATA_BLOCK: (&str, u32) = ("FrameData", FRAME_DATA_BINDING);
+
+/* std140 layout of the block, in floats, as declared in frame_data.glsl. Scalars fill the
+ * last component of the vec3 before them */
+const VIEW_OFFSET: usize = 0;
+const PROJECTION_OFFSET: usize = 16;
+const SHADOW_MATRIX_OFFSET: usize = 32;
+const CAMERA_POSITION_OFFSET: usize = 48;
+const TIME_OFFSET: usize = 51;
+const SUN_DIRECTION_OFFSET: usize = 52;
+const SHADOWS_ENABLED_OFFSET: usize = 55;
+const SUN_COLOR_OFFSET: usize = 56;
+const FOG_MODE_OFFSET: usize = 59;
+const AMBIENT_COLOR_OFFSET: usize = 60;
+const FOG_START_OFFSET: usize = 63;
+const FOG_COLOR_OFFSET: usize = 64;
+const FOG_END_OFFSET: usize = 67;
+const FOG_DENSITY_OFFSET: usize = 68;
+/* blocks are padded to a whole vec4 */
+const FRAME_DATA_FLOATS: usize = 72;
+
+/* uniform buffer holding everything that is the same for all draws of a frame,
+ * uploaded once per frame instead of once per program */
+#[derive(Debug)]
+pub struct FrameData {
+    buffer: BufferHandle,
+    data: [f32; FRAME_DATA_FLOATS],
+}
+
+impl FrameData {
+    pub fn new(gl: &GlContext) -> Result<Self, String> {
+        unsafe {
+            let buffer = gl.new_buffer()?;
+            gl.bind_buffer(glow::UNIFORM_BUFFER, Some(buffer.key()));
+            gl.buffer_data_size(
+                glow::UNIFORM_BUFFER,
+                (FRAME_DATA_FLOATS * 4) as i32,
+                glow::DYNAMIC_DRAW,
+            );
+            gl.bind_buffer(glow::UNIFORM_BUFFER, None);
+            gl.bind_buffer_base(glow::UNIFORM_BUFFER, FRAME_DATA_BINDING, Some(buffer.key()));
+            Ok(Self {
+                buffer,
+                data: [0.0; FRAME_DATA_FLOATS],
+            })
+        }
+    }
+
+    /* the shadow map has to be fitted to the camera of this frame already */
+    pub fn update(
+        &mut self,
+        gl: &GlContext,
+        camera: &Camera,
+        time: &Time,
+        lighting: &Lighting,
+        fog: &Fog,
+        shadow_map: Option<&ShadowMap>,
+    ) {
+        self.write_matrix(VIEW_OFFSET, &camera.look_at);
+        self.write_matrix(PROJECTION_OFFSET, &camera.projection);
+        let shadow_matrix = shadow_map.map_or(Mat4::IDENTITY, ShadowMap::get_matrix);
+        self.write_matrix(SHADOW_MATRIX_OFFSET, &shadow_matrix);
+        self.write_vec3(CAMERA_POSITION_OFFSET, camera.get_position());
+        self.data[TIME_OFFSET] = (time.time() / 1000.0) as f32;
+        self.write_vec3(SUN_DIRECTION_OFFSET, lighting.sun_direction);
+        let shadows_enabled = shadow_map.is_some_and(|shadow_map| shadow_map.enabled);
+        self.write_i32(SHADOWS_ENABLED_OFFSET, shadows_enabled as i32);
+        self.write_vec3(SUN_COLOR_OFFSET, lighting.sun_color);
+        self.write_i32(FOG_MODE_OFFSET, fog.mode as i32);
+        self.write_vec3(AMBIENT_COLOR_OFFSET, lighting.ambient_color);
+        self.data[FOG_START_OFFSET] = fog.end * fog.start_ratio;
+        self.write_vec3(FOG_COLOR_OFFSET, fog.color);
+        self.data[FOG_END_OFFSET] = fog.end;
+        self.data[FOG_DENSITY_OFFSET] = fog.density;
+        unsafe {
+            gl.bind_buffer(glow::UNIFORM_BUFFER, Some(self.buffer.key()));
+            gl.buffer_sub_data_u8_slice(glow::UNIFORM_BUFFER, 0, self.data.align_to::<u8>().1);
+            gl.bind_buffer(glow::UNIFORM_BUFFER, None);
+            gl.bind_buffer_base(
+                glow::UNIFORM_BUFFER,
+                FRAME_DATA_BINDING,
+                Some(self.buffer.key()),
+            );
+        }
+    }
+
+    fn write_matrix(&mut self, offset: usize, matrix: &Mat4) {
+        self.data[offset..offset + 16].copy_from_slice(&matrix.to_cols_array());
+    }
+
+    fn write_vec3(&mut self, offset: usize, value: Vec3) {
+        self.data[offset..offset + 3].copy_from_slice(&value.to_array());
+    }
+
+    /* ints go into the buffer as they are, not converted */
+    fn write_i32(&mut self, offset: usize, value: i32) {
+        self.data[offset] = f32::from_bits(value as u32);
+    }
+}
@@ -0,0 +1,72 @@
+use std::rc::Rc;
+
+use crate::utils::{BufferHandle, GlContext, GpuMemoryCategory};
+
+use super::{layout_stride, Mesh, MeshRenderer, VertexAttrType, VertexAttribute};
+
+/* model matrix of each instance, read by `in mat4 instance_transform` */
+pub const INSTANCE_TRANSFORM: VertexAttribute =
+    VertexAttribute::f32_matrix(VertexAttrType::InstanceTransform, 4);
+
+/* draws copies of one mesh in a single call, each instance reading its own attributes (like
+ * INSTANCE_TRANSFORM) from an instance buffer meant to be updated every frame */
+#[derive(Debug)]
+pub struct InstancedRenderer {
+    renderer: MeshRenderer,
+    instance_buffer: BufferHandle,
+    instance_layout: Vec<VertexAttribute>,
+    instance_count: usize,
+}
+
+impl InstancedRenderer {
+    pub fn new(
+        gl: &GlContext,
+        mut renderer: MeshRenderer,
+        mesh: Rc<Mesh>,
+        instance_layout: Vec<VertexAttribute>,
+    ) -> Result<Self, String> {
+        if instance_layout.is_empty() {
+            return Err("Instanced renderers need at least one instance attribute".to_string());
+        }
+        let instance_buffer = gl.new_buffer()?;
+        renderer.set_instanced_mesh(gl, mesh, Some((&instance_buffer, &instance_layout)))?;
+        Ok(Self {
+            renderer,
+            instance_buffer,
+            instance_layout,
+            instance_count: 0,
+        })
+    }
+
+    /* replaces every instance, the data being interleaved as described by the instance layout */
+    pub fn set_instances<T: bytemuck::Pod>(
+        &mut self,
+        gl: &GlContext,
+        instances: &[T],
+    ) -> Result<(), String> {
+        let data: &[u8] = bytemuck::cast_slice(instances);
+        let stride = layout_stride(&self.instance_layout);
+        if !data.len().is_multiple_of(stride) {
+            return Err(format!(
+                "Instance data of size {} doesn't match instance layout size of {}",
+                data.len(),
+                stride
+            ));
+        }
+        unsafe {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.instance_buffer.key()));
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, data, glow::STREAM_DRAW);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+        self.instance_buffer
+            .set_size(GpuMemoryCategory::Meshes, data.len());
+        self.instance_count = data.len() / stride;
+        Ok(())
+    }
+
+    pub fn render(&self, gl: &GlContext) {
+        if self.instance_count > 0 {
+            self.renderer.render_instanced(gl, self.instance_count);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use glam::{vec3, Vec3};
+
+use crate::core::TimeOfDay;
+
+const DAY_SUN_COLOR: Vec3 = vec3(1.0, 0.95, 0.85);
+const SUNSET_SUN_COLOR: Vec3 = vec3(1.0, 0.6, 0.35);
+const MOON_COLOR: Vec3 = vec3(0.15, 0.17, 0.25);
+const DAY_AMBIENT_COLOR: Vec3 = vec3(0.35, 0.38, 0.45);
+const NIGHT_AMBIENT_COLOR: Vec3 = vec3(0.06, 0.07, 0.12);
+
+#[derive(Debug, Clone)]
+pub struct Lighting {
+    /* direction the sun light travels in, i.e. from the sun towards the ground */
+    pub sun_direction: Vec3,
+    pub sun_color: Vec3,
+    pub ambient_color: Vec3,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            sun_direction: vec3(-0.4, -1.0, -0.25).normalize(),
+            sun_color: DAY_SUN_COLOR,
+            ambient_color: DAY_AMBIENT_COLOR,
+        }
+    }
+}
+
+impl Lighting {
+    /* the main light follows the sun during the day and the moon at night */
+    pub fn set_time_of_day(&mut self, time_of_day: &TimeOfDay) {
+        let daylight = time_of_day.get_daylight();
+        let sun_position = time_of_day.get_sun_position();
+        if sun_position.y > 0.0 {
+            let sunset = 1.0 - (sun_position.y * 3.0).min(1.0);
+            self.sun_direction = -sun_position;
+            self.sun_color = DAY_SUN_COLOR.lerp(SUNSET_SUN_COLOR, sunset) * daylight;
+        } else {
+            self.sun_direction = -time_of_day.get_moon_position();
+            self.sun_color = MOON_COLOR * (1.0 - daylight);
+        }
+        self.ambient_color = NIGHT_AMBIENT_COLOR.lerp(DAY_AMBIENT_COLOR, daylight);
+    }
+}
@@ -0,0 +1,134 @@
+use std::rc::Rc;
+
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::utils::GlContext;
+
+use super::{ProgramRef, ShaderProgram, TextureDef, TextureType, SHADOW_MAP_UNIT};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniformValue {
+    Float(f32),
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Mat4(Mat4),
+    /* texture unit the sampler reads from */
+    Sampler(u32),
+}
+
+impl UniformValue {
+    /* whether a uniform of the given GLSL type can hold this value */
+    pub fn matches_gl_type(&self, gl_type: u32) -> bool {
+        match self {
+            UniformValue::Float(_) => gl_type == glow::FLOAT,
+            UniformValue::Vec2(_) => gl_type == glow::FLOAT_VEC2,
+            UniformValue::Vec3(_) => gl_type == glow::FLOAT_VEC3,
+            UniformValue::Mat4(_) => gl_type == glow::FLOAT_MAT4,
+            UniformValue::Sampler(_) => is_sampler_type(gl_type),
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            UniformValue::Float(_) => "float",
+            UniformValue::Vec2(_) => "vec2",
+            UniformValue::Vec3(_) => "vec3",
+            UniformValue::Mat4(_) => "mat4",
+            UniformValue::Sampler(_) => "sampler",
+        }
+    }
+}
+
+fn is_sampler_type(gl_type: u32) -> bool {
+    [
+        glow::SAMPLER_2D,
+        glow::SAMPLER_3D,
+        glow::SAMPLER_CUBE,
+        glow::SAMPLER_2D_SHADOW,
+        glow::SAMPLER_2D_ARRAY,
+        glow::SAMPLER_2D_ARRAY_SHADOW,
+        glow::INT_SAMPLER_2D,
+        glow::UNSIGNED_INT_SAMPLER_2D,
+    ]
+    .contains(&gl_type)
+}
+
+/* a program together with the uniform values and textures it is drawn with,
+ * every value is checked against the uniforms the program actually has */
+#[derive(Debug, Clone)]
+pub struct Material {
+    program: ProgramRef,
+    uniforms: Vec<(String, UniformValue)>,
+    textures: Vec<(u32, Rc<TextureDef>)>,
+}
+
+impl Material {
+    pub fn new(program: &ProgramRef) -> Self {
+        Self {
+            program: program.clone(),
+            uniforms: Vec::new(),
+            textures: Vec::new(),
+        }
+    }
+
+    /* the current version of the program, it changes when the program is reloaded */
+    pub fn get_program(&self) -> Rc<ShaderProgram> {
+        self.program.get()
+    }
+
+    pub fn with_uniform(mut self, name: &str, value: UniformValue) -> Result<Self, String> {
+        self.set_uniform(name, value)?;
+        Ok(self)
+    }
+
+    /* binds the texture to the next free unit and points the named sampler at it */
+    pub fn with_texture(mut self, name: &str, texture: Rc<TextureDef>) -> Result<Self, String> {
+        let program = self.get_program();
+        let sampler_type = program.get_uniform_type(name).ok_or(format!(
+            "Material: no sampler '{name}' in {}",
+            program.get_name()
+        ))?;
+        let expected_type = match texture.0 {
+            TextureType::Texture2D => glow::SAMPLER_2D,
+            TextureType::Texture2DArray(_) => glow::SAMPLER_2D_ARRAY,
+        };
+        if sampler_type != expected_type {
+            return Err(format!(
+                "Material: sampler '{name}' in {} doesn't match texture type {:?}",
+                program.get_name(),
+                texture.0
+            ));
+        }
+        let unit = self.textures.len() as u32;
+        self.set_uniform(name, UniformValue::Sampler(unit))?;
+        self.textures.push((unit, texture));
+        Ok(self)
+    }
+
+    /* points the shadow_map sampler at the unit ShadowMap::bind binds the map to */
+    pub fn with_shadow_map(self) -> Result<Self, String> {
+        self.with_uniform("shadow_map", UniformValue::Sampler(SHADOW_MAP_UNIT))
+    }
+
+    pub fn set_uniform(&mut self, name: &str, value: UniformValue) -> Result<(), String> {
+        self.get_program().check_uniform(name, &value)?;
+        match self.uniforms.iter_mut().find(|(n, _)| n == name) {
+            Some((_, current)) => *current = value,
+            None => self.uniforms.push((name.to_string(), value)),
+        }
+        Ok(())
+    }
+
+    /* uses the program and uploads all values and textures */
+    pub fn apply(&self, gl: &GlContext) {
+        let program = self.get_program();
+        program.gl_use(gl);
+        for (name, value) in self.uniforms.iter() {
+            program.set_uniform(gl, name, value);
+        }
+        for (unit, texture) in self.textures.iter() {
+            let (texture_type, handle) = &**texture;
+            gl.set_texture(*unit, (*texture_type).into(), Some(handle.key()));
+        }
+    }
+}
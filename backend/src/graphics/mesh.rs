@@ -3,7 +3,7 @@
 
 use glam::Vec3;
 
-use crate::world::BlockSideTexture;
+use crate::world::{BlockSideTexture, Side, SIDE_NORMS};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum VertexDataType {
@@ -17,6 +17,20 @@ pub enum VertexDataType {
     F32 = glow::FLOAT as _,
 }
 
+impl VertexDataType {
+    pub fn byte_size(&self) -> usize {
+        match self {
+            VertexDataType::U8 | VertexDataType::I8 => 1,
+            VertexDataType::U16 | VertexDataType::I16 | VertexDataType::F16 => 2,
+            VertexDataType::U32 | VertexDataType::I32 | VertexDataType::F32 => 4,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, VertexDataType::F16 | VertexDataType::F32)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum VertexAttrType {
     Position,
@@ -24,15 +38,143 @@ pub enum VertexAttrType {
     Normal,
     UVs,
     Depth,
+    /* per instance attributes, see InstancedRenderer */
+    InstanceColor,
+    InstanceTransform,
     Custom(VertexDataType),
 }
 
+impl VertexAttrType {
+    /* the types following the naming convention */
+    pub const NAMED: [VertexAttrType; 7] = [
+        VertexAttrType::Position,
+        VertexAttrType::Color,
+        VertexAttrType::Normal,
+        VertexAttrType::UVs,
+        VertexAttrType::Depth,
+        VertexAttrType::InstanceColor,
+        VertexAttrType::InstanceTransform,
+    ];
+
+    /* naming convention between shader attributes and mesh layouts */
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMED
+            .into_iter()
+            .find(|attr_type| attr_type.get_name() == Some(name))
+    }
+
+    pub fn get_name(&self) -> Option<&'static str> {
+        match self {
+            VertexAttrType::Position => Some("position"),
+            VertexAttrType::Color => Some("color"),
+            VertexAttrType::Normal => Some("normal"),
+            VertexAttrType::UVs => Some("uv"),
+            VertexAttrType::Depth => Some("depth"),
+            VertexAttrType::InstanceColor => Some("instance_color"),
+            VertexAttrType::InstanceTransform => Some("instance_transform"),
+            VertexAttrType::Custom(_) => None,
+        }
+    }
+
+    /* location bound to the named attributes of every program, a mat4 takes 4 of them */
+    pub fn get_location(&self) -> Option<u32> {
+        match self {
+            VertexAttrType::Position => Some(0),
+            VertexAttrType::Color => Some(1),
+            VertexAttrType::Normal => Some(2),
+            VertexAttrType::UVs => Some(3),
+            VertexAttrType::Depth => Some(4),
+            VertexAttrType::InstanceColor => Some(5),
+            VertexAttrType::InstanceTransform => Some(6),
+            VertexAttrType::Custom(_) => None,
+        }
+    }
+}
+
+/* how one attribute of interleaved vertices is stored */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexAttribute {
+    pub attr_type: VertexAttrType,
+    pub components: usize,
+    /* matrices are made of columns of `components` values, 1 for vectors */
+    pub columns: usize,
+    pub data_type: VertexDataType,
+    /* integers read as floats in [0, 1] (or [-1, 1] when signed) instead of their value */
+    pub normalized: bool,
+    /* integers read by int/uint shader inputs, the others are converted to floats */
+    pub integer: bool,
+}
+
+impl VertexAttribute {
+    pub const fn f32(attr_type: VertexAttrType, components: usize) -> Self {
+        Self::float(attr_type, components, VertexDataType::F32)
+    }
+
+    /* read as floats, like i16 positions */
+    pub const fn float(
+        attr_type: VertexAttrType,
+        components: usize,
+        data_type: VertexDataType,
+    ) -> Self {
+        Self {
+            attr_type,
+            components,
+            columns: 1,
+            data_type,
+            normalized: false,
+            integer: false,
+        }
+    }
+
+    /* square f32 matrix, like a mat4 transform */
+    pub const fn f32_matrix(attr_type: VertexAttrType, size: usize) -> Self {
+        Self {
+            columns: size,
+            ..Self::f32(attr_type, size)
+        }
+    }
+
+    /* integers read as floats in [0, 1] or [-1, 1], like u8 colors */
+    pub const fn normalized(
+        attr_type: VertexAttrType,
+        components: usize,
+        data_type: VertexDataType,
+    ) -> Self {
+        Self {
+            normalized: true,
+            ..Self::float(attr_type, components, data_type)
+        }
+    }
+
+    /* read as is by int or uint inputs, like packed data */
+    pub const fn integer(
+        attr_type: VertexAttrType,
+        components: usize,
+        data_type: VertexDataType,
+    ) -> Self {
+        Self {
+            integer: true,
+            ..Self::float(attr_type, components, data_type)
+        }
+    }
+
+    pub fn byte_size(&self) -> usize {
+        self.columns * self.components * self.data_type.byte_size()
+    }
+}
+
+/* byte size of one vertex */
+pub fn layout_stride(layout: &[VertexAttribute]) -> usize {
+    layout.iter().map(VertexAttribute::byte_size).sum()
+}
+
 pub type QuadSideData = (Side, BlockSideTexture, Vec3);
 
+/* interleaved vertices, each one laid out as described by the layout */
 pub struct Mesh {
-    pub data: Vec<f32>,
+    pub data: Vec<u8>,
     pub indices: Option<Vec<u32>>,
-    pub layout: Vec<(VertexAttrType, usize)>,
+    pub layout: Vec<VertexAttribute>,
     pub primitive_type: u32,
 }
 
@@ -41,10 +183,32 @@ pub trait ToMesh {
 }
 
 impl Mesh {
-    pub fn get_data(&self) -> &Vec<f32> {
+    /* vertices made only of f32 attributes */
+    pub fn from_f32(data: &[f32], layout: &[(VertexAttrType, usize)], primitive_type: u32) -> Self {
+        Mesh {
+            data: bytemuck::cast_slice(data).to_vec(),
+            indices: None,
+            layout: layout
+                .iter()
+                .map(|&(attr_type, components)| VertexAttribute::f32(attr_type, components))
+                .collect(),
+            primitive_type,
+        }
+    }
+
+    pub fn with_indices(mut self, indices: Vec<u32>) -> Self {
+        self.indices = Some(indices);
+        self
+    }
+
+    pub fn get_data(&self) -> &[u8] {
         &self.data
     }
 
+    pub fn vertex_count(&self) -> usize {
+        self.data.len() / layout_stride(&self.layout)
+    }
+
     pub fn from_offset_sides<I>(
         sides: I,
         block_size: f32,
@@ -77,44 +241,13 @@ impl Mesh {
                 data.push(texture as usize as _);
             }
         }
-        Mesh {
-            data,
-            layout: vec![
-                (VertexAttrType::Position, 3),
-                (VertexAttrType::UVs, 2),
-                (VertexAttrType::Normal, 3),
-                (VertexAttrType::Depth, 1),
-            ],
-            indices: None,
-            primitive_type: glow::TRIANGLES,
-        }
-    }
-}
-
-#[derive(Clone, Copy, Debug)]
-pub enum Side {
-    Top = 0,
-    Bottom,
-    Front,
-    Back,
-    Right,
-    Left,
-}
-
-pub const SIDE_NORMS: [Vec3; 6] = [
-    Vec3::Y,
-    Vec3::NEG_Y,
-    Vec3::Z,
-    Vec3::NEG_Z,
-    Vec3::X,
-    Vec3::NEG_X,
-];
-
-type Norm = Vec3;
-
-impl Into<Norm> for Side {
-    fn into(self) -> Norm {
-        SIDE_NORMS[self as usize]
+        let layout = [
+            (VertexAttrType::Position, 3),
+            (VertexAttrType::UVs, 2),
+            (VertexAttrType::Normal, 3),
+            (VertexAttrType::Depth, 1),
+        ];
+        Mesh::from_f32(&data, &layout, glow::TRIANGLES)
     }
 }
 
@@ -1,20 +1,25 @@
-use std::rc::Rc;
+use std::{mem::size_of, rc::Rc};
 
-use glow::{HasContext, WebBufferKey, WebVertexArrayKey};
 use log::warn;
 
-use crate::utils;
+use crate::utils::BufferHandle;
+use crate::utils::GlContext;
 use crate::utils::GlRenderFlags;
-use crate::utils::GlState;
+use crate::utils::GpuMemoryCategory;
+use crate::utils::VertexArrayHandle;
 
+use super::layout_stride;
+use super::Material;
 use super::Mesh;
 use super::ShaderProgram;
+use super::VertexAttrType;
+use super::VertexAttribute;
 
 #[derive(Debug)]
 enum DisplayData {
     None,
-    Array(Option<WebVertexArrayKey>, usize),
-    Elements(Option<WebVertexArrayKey>, Option<WebBufferKey>, usize),
+    Array(VertexArrayHandle, usize),
+    Elements(VertexArrayHandle, BufferHandle, usize),
 }
 
 const DEFAULT_FLAGS: [GlRenderFlags; 3] = [
@@ -25,79 +30,94 @@ const DEFAULT_FLAGS: [GlRenderFlags; 3] = [
 
 #[derive(Debug)]
 pub struct MeshRenderer {
-    program: Rc<ShaderProgram>,
+    material: Material,
     primitive_type: u32,
     display_data: DisplayData,
+    /* kept alive for as long as the vertex array reads from it */
+    _vertex_buffer: Option<BufferHandle>,
+    memory_category: GpuMemoryCategory,
     render_flags: &'static [GlRenderFlags], // vertex_count: i32,
 }
 
 impl MeshRenderer {
-    pub fn get_program(&self) -> &ShaderProgram {
-        &self.program
+    pub fn get_program(&self) -> Rc<ShaderProgram> {
+        self.material.get_program()
     }
 
-    pub fn new(program: &Rc<ShaderProgram>) -> Self {
+    pub fn get_material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn get_material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    pub fn new(material: Material) -> Self {
         MeshRenderer {
             display_data: DisplayData::None,
+            _vertex_buffer: None,
+            memory_category: GpuMemoryCategory::Meshes,
             primitive_type: glow::TRIANGLES,
             // vertex_count: 0,
-            program: program.clone(),
+            material,
             render_flags: &DEFAULT_FLAGS,
         }
     }
 
-    pub fn with_render_flags(
-        render_flags: &'static [GlRenderFlags],
-        program: &Rc<ShaderProgram>,
-    ) -> Self {
-        let mut result = Self::new(program);
+    pub fn with_render_flags(render_flags: &'static [GlRenderFlags], material: Material) -> Self {
+        let mut result = Self::new(material);
         result.render_flags = render_flags;
         result
     }
 
-    pub fn set_mesh(&mut self, gl: &glow::Context, mesh: Rc<Mesh>) -> Result<(), String> {
+    /* what the uploaded meshes are counted as in the GPU memory usage */
+    pub fn with_memory_category(mut self, category: GpuMemoryCategory) -> Self {
+        self.memory_category = category;
+        self
+    }
+
+    pub fn set_mesh(&mut self, gl: &GlContext, mesh: Rc<Mesh>) -> Result<(), String> {
+        self.set_instanced_mesh(gl, mesh, None)
+    }
+
+    /* same as set_mesh, with attributes read once per instance from the given buffer. The
+     * objects of the previous mesh are released */
+    pub fn set_instanced_mesh(
+        &mut self,
+        gl: &GlContext,
+        mesh: Rc<Mesh>,
+        instances: Option<(&BufferHandle, &[VertexAttribute])>,
+    ) -> Result<(), String> {
+        let program = self.get_program();
+        match instances {
+            None => program.check_layout(&mesh.layout)?,
+            Some((_, instance_layout)) => {
+                program.check_layout(&[mesh.layout.as_slice(), instance_layout].concat())?
+            }
+        }
         unsafe {
-            let vao = Some(gl.create_vertex_array()?);
-            gl.bind_vertex_array(vao);
-            let buffer = gl.create_buffer()?;
-            gl.bind_buffer(glow::ARRAY_BUFFER, Some(buffer));
-            gl.bind_vertex_array(vao);
-            gl.buffer_data_u8_slice(
-                glow::ARRAY_BUFFER,
-                mesh.get_data().align_to::<u8>().1,
-                glow::STATIC_DRAW,
-            );
-
-            let layout_size: usize = mesh.layout.iter().map(|(_type, size)| size).sum();
-
-            if mesh.get_data().len() % layout_size != 0 {
+            let vao = gl.new_vertex_array()?;
+            gl.set_vertex_array(Some(vao.key()));
+            let buffer = gl.new_buffer()?;
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(buffer.key()));
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, mesh.get_data(), glow::STATIC_DRAW);
+            buffer.set_size(self.memory_category, mesh.get_data().len());
+
+            let stride = layout_stride(&mesh.layout);
+            if !mesh.get_data().len().is_multiple_of(stride) {
                 warn!(
                     "Mesh data of size {} doesn't match layout size of {}.",
                     mesh.get_data().len(),
-                    layout_size
+                    stride
                 );
             }
 
-            let stride: i32 = 4 * layout_size as i32;
-
-            let mut offset: i32 = 0;
-            for &(data_type, size) in mesh.layout.iter() {
-                match self.program.get_attr_location(data_type) {
-                    Some(&location) => {
-                        let location = location as u32;
-                        gl.vertex_attrib_pointer_f32(
-                            location,
-                            size as _,
-                            glow::FLOAT,
-                            false,
-                            stride,
-                            offset,
-                        );
-                        gl.enable_vertex_attrib_array(location);
-                    }
-                    None => warn!("Unable to get location for type {:?}", data_type),
-                }
-                offset += 4 * size as i32;
+            // check_layout made sure the program doesn't need the attributes it has no location for
+            let get_location = |attr_type| program.get_attr_location(attr_type).copied();
+            enable_vertex_layout(gl, &mesh.layout, 0, get_location);
+            if let Some((instance_buffer, instance_layout)) = instances {
+                gl.bind_buffer(glow::ARRAY_BUFFER, Some(instance_buffer.key()));
+                enable_vertex_layout(gl, instance_layout, 1, get_location);
             }
 
             self.display_data = match &mesh.indices {
@@ -105,64 +125,141 @@ impl MeshRenderer {
                     // info!(
                     //     "created Mesh of type {:?} with {} vertices.",
                     //     mesh.primitive_type,
-                    //     mesh.get_data().len() / layout_size
+                    //     mesh.vertex_count()
                     // );
-                    DisplayData::Array(vao, mesh.get_data().len() / layout_size)
+                    DisplayData::Array(vao, mesh.vertex_count())
                 }
                 Some(indices) => {
                     /* create vbo out of mesh indices */
-                    let vbo = Some(gl.create_buffer()?);
-                    gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, vbo);
+                    let vbo = gl.new_buffer()?;
+                    gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(vbo.key()));
                     gl.buffer_data_u8_slice(
                         glow::ELEMENT_ARRAY_BUFFER,
                         indices.align_to::<u8>().1,
                         glow::STATIC_DRAW,
                     );
+                    vbo.set_size(self.memory_category, indices.len() * size_of::<u32>());
 
                     // info!("created Mesh with {} indices.", indices.len());
 
                     DisplayData::Elements(vao, vbo, indices.len() as _)
                 }
             };
+            self._vertex_buffer = Some(buffer);
             self.primitive_type = mesh.primitive_type;
 
             Ok(())
         }
     }
 
-    pub fn render(&self, gl: &glow::Context) {
+    pub fn render(&self, gl: &GlContext) {
+        self.render_with(gl, &self.material);
+    }
+
+    /* renders with another material of the same program, like a copy holding per object
+     * values */
+    pub fn render_with(&self, gl: &GlContext, material: &Material) {
+        self.set_gl_flags(gl);
+        material.apply(gl);
+        self.draw(gl);
+    }
+
+    pub fn render_instanced(&self, gl: &GlContext, instance_count: usize) {
         self.set_gl_flags(gl);
-        match self.display_data {
+        self.material.apply(gl);
+        self.draw_instanced(gl, instance_count);
+    }
+
+    pub fn draw_instanced(&self, gl: &GlContext, instance_count: usize) {
+        let instance_count = instance_count as i32;
+        match &self.display_data {
             DisplayData::None => {}
             DisplayData::Array(vao, vertex_count) => unsafe {
-                self.get_program().gl_use(gl);
-                gl.bind_vertex_array(vao);
-                gl.draw_arrays(self.primitive_type as _, 0, vertex_count as _);
+                gl.set_vertex_array(Some(vao.key()));
+                let vertex_count = *vertex_count as i32;
+                gl.draw_arrays_instanced(self.primitive_type, 0, vertex_count, instance_count);
             },
-            DisplayData::Elements(vao, vbo, count) => unsafe {
-                self.get_program().gl_use(gl);
-                gl.bind_vertex_array(vao);
-                gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, vbo);
-                gl.draw_elements(self.primitive_type as _, count as _, glow::UNSIGNED_INT, 0);
+            DisplayData::Elements(vao, indices, count) => unsafe {
+                gl.set_vertex_array(Some(vao.key()));
+                gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(indices.key()));
+                gl.draw_elements_instanced(
+                    self.primitive_type,
+                    *count as _,
+                    glow::UNSIGNED_INT,
+                    0,
+                    instance_count,
+                );
             },
         }
     }
 
-    fn set_gl_flags(&self, gl: &glow::Context) {
-        GlState::set_flag(
-            gl,
+    /* draws with whatever program and flags are current, e.g. for the shadow pass */
+    pub fn draw(&self, gl: &GlContext) {
+        match &self.display_data {
+            DisplayData::None => {}
+            DisplayData::Array(vao, vertex_count) => unsafe {
+                gl.set_vertex_array(Some(vao.key()));
+                gl.draw_arrays(self.primitive_type as _, 0, *vertex_count as _);
+            },
+            DisplayData::Elements(vao, indices, count) => unsafe {
+                gl.set_vertex_array(Some(vao.key()));
+                gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(indices.key()));
+                gl.draw_elements(self.primitive_type as _, *count as _, glow::UNSIGNED_INT, 0);
+            },
+        }
+    }
+
+    fn set_gl_flags(&self, gl: &GlContext) {
+        for flag in [
             GlRenderFlags::CullFace,
-            self.render_flags.contains(&utils::GlRenderFlags::CullFace),
-        );
-        GlState::set_flag(
-            gl,
             GlRenderFlags::Blend,
-            self.render_flags.contains(&utils::GlRenderFlags::Blend),
-        );
-        GlState::set_flag(
-            gl,
             GlRenderFlags::DepthTest,
-            self.render_flags.contains(&utils::GlRenderFlags::DepthTest),
-        )
+        ] {
+            gl.set_flag(flag, self.render_flags.contains(&flag));
+        }
+    }
+}
+
+/* points the attributes of the bound vertex array at the interleaved vertices of the bound
+ * buffer, attributes without a location are skipped. A divisor of 0 reads the attributes per
+ * vertex, 1 per instance. Matrices take one location per column */
+pub fn enable_vertex_layout<F>(
+    gl: &GlContext,
+    layout: &[VertexAttribute],
+    divisor: u32,
+    get_location: F,
+) where
+    F: Fn(VertexAttrType) -> Option<u32>,
+{
+    let stride = layout_stride(layout) as i32;
+    let mut offset = 0;
+    for attribute in layout.iter() {
+        let column_size = (attribute.byte_size() / attribute.columns) as i32;
+        if let Some(first_location) = get_location(attribute.attr_type) {
+            let (size, data_type) = (attribute.components as i32, attribute.data_type as u32);
+            for column in 0..attribute.columns {
+                let location = first_location + column as u32;
+                let offset = offset + column as i32 * column_size;
+                // offsets are into the bound buffer, which GL checks when drawing
+                unsafe {
+                    match attribute.integer {
+                        true => {
+                            gl.vertex_attrib_pointer_i32(location, size, data_type, stride, offset)
+                        }
+                        false => gl.vertex_attrib_pointer_f32(
+                            location,
+                            size,
+                            data_type,
+                            attribute.normalized,
+                            stride,
+                            offset,
+                        ),
+                    }
+                    gl.vertex_attrib_divisor(location, divisor);
+                    gl.enable_vertex_attrib_array(location);
+                }
+            }
+        }
+        offset += attribute.byte_size() as i32;
     }
 }
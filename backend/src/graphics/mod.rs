@@ -1,13 +1,39 @@
 mod camera;
+mod fog;
+mod framedata;
+mod instancedrenderer;
+mod lighting;
+mod material;
 mod mesh;
 mod meshrenderer;
+mod postprocess;
+mod rendertarget;
+mod resourcepack;
+#[cfg(feature = "shader_hot_reload")]
+mod shaderreload;
 mod shaders;
+mod shadows;
+mod sky;
+mod texturearray;
 mod textureloader;
 mod texure;
 
 pub use camera::*;
+pub use fog::*;
+pub use framedata::*;
+pub use instancedrenderer::*;
+pub use lighting::*;
+pub use material::*;
 pub use mesh::*;
 pub use meshrenderer::*;
+pub use postprocess::*;
+pub use rendertarget::*;
+pub use resourcepack::*;
+#[cfg(feature = "shader_hot_reload")]
+pub use shaderreload::*;
 pub use shaders::*;
+pub use shadows::*;
+pub use sky::*;
+pub use texturearray::*;
 pub use textureloader::*;
 pub use texure::*;
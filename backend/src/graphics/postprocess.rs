@@ -0,0 +1,249 @@
+use std::{collections::HashMap, rc::Rc};
+
+use glam::Vec2;
+
+use crate::{
+    shader_def,
+    utils::{self, GlContext, GlRenderFlags},
+};
+
+use super::{Camera, Material, MeshRenderer, ProgramRef, RenderTarget, ShaderDef, UniformValue};
+
+const DEPTH_TEXTURE_UNIT: u32 = 1;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PostEffect {
+    /* plain copy, used when no effect is enabled */
+    Copy,
+    Ssao,
+    ToneMapping,
+    Fxaa,
+    Vignette,
+}
+
+impl PostEffect {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            PostEffect::Copy => "Copy",
+            PostEffect::Ssao => "SSAO",
+            PostEffect::ToneMapping => "Tone mapping",
+            PostEffect::Fxaa => "FXAA",
+            PostEffect::Vignette => "Vignette",
+        }
+    }
+
+    /* range of the strength setting, None if the effect has none */
+    pub fn get_strength_range(&self) -> Option<(f32, f32)> {
+        match self {
+            PostEffect::Copy | PostEffect::Fxaa => None,
+            PostEffect::Ssao => Some((0.0, 2.0)),
+            PostEffect::ToneMapping => Some((0.1, 4.0)),
+            PostEffect::Vignette => Some((0.0, 1.0)),
+        }
+    }
+
+    fn compile(&self, gl: &GlContext) -> Result<ProgramRef, String> {
+        let def = match self {
+            PostEffect::Copy => shader_def!("post.vert", "post_copy.frag", vec![]),
+            PostEffect::Fxaa => shader_def!("post.vert", "post_fxaa.frag", vec![]),
+            PostEffect::ToneMapping => shader_def!("post.vert", "post_tonemap.frag", vec![]),
+            PostEffect::Vignette => shader_def!("post.vert", "post_vignette.frag", vec![]),
+            PostEffect::Ssao => shader_def!("post.vert", "post_ssao.frag", vec![]),
+        };
+        def.compile_shared(gl)
+    }
+
+    /* the effect program reading the source from unit 0 and the scene depth from
+     * DEPTH_TEXTURE_UNIT */
+    fn make_material(&self, gl: &GlContext) -> Result<Material, String> {
+        let material = Material::new(&self.compile(gl)?)
+            .with_uniform("color_texture", UniformValue::Sampler(0))?;
+        match self {
+            PostEffect::Ssao => {
+                material.with_uniform("depth_texture", UniformValue::Sampler(DEPTH_TEXTURE_UNIT))
+            }
+            _ => Ok(material),
+        }
+    }
+
+    /* sets the values of the effect that change with the settings and the frame */
+    fn update_material(
+        &self,
+        material: &mut Material,
+        strength: f32,
+        texel_size: Vec2,
+        camera: &Camera,
+    ) -> Result<(), String> {
+        if self.get_strength_range().is_some() {
+            material.set_uniform("strength", UniformValue::Float(strength))?;
+        }
+        match self {
+            PostEffect::Fxaa => material.set_uniform("texel_size", UniformValue::Vec2(texel_size)),
+            PostEffect::Ssao => {
+                let projection = camera.projection;
+                material.set_uniform("projection", UniformValue::Mat4(projection))?;
+                let inverse = UniformValue::Mat4(projection.inverse());
+                material.set_uniform("inverse_projection", inverse)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PostEffectSettings {
+    pub effect: PostEffect,
+    pub enabled: bool,
+    pub strength: f32,
+}
+
+impl PostEffectSettings {
+    fn new(effect: PostEffect, strength: f32) -> Self {
+        Self {
+            effect,
+            enabled: true,
+            strength,
+        }
+    }
+}
+
+/* the scene is rendered into an offscreen target, then goes through the enabled effects in
+ * order, ping-ponging between two targets, the last one drawing to the default framebuffer */
+#[derive(Debug)]
+pub struct PostProcessStack {
+    pub effects: Vec<PostEffectSettings>,
+    materials: HashMap<PostEffect, Material>,
+    quad: MeshRenderer,
+    color_format: u32,
+    scene: Option<RenderTarget>,
+    ping_pong: Vec<RenderTarget>,
+    /* viewport of the default framebuffer, restored when the chain is done */
+    viewport: [i32; 4],
+}
+
+impl PostProcessStack {
+    pub fn new(gl: &GlContext) -> Result<Self, String> {
+        let effects = vec![
+            PostEffectSettings::new(PostEffect::Ssao, 1.0),
+            PostEffectSettings::new(PostEffect::ToneMapping, 1.0),
+            PostEffectSettings::new(PostEffect::Fxaa, 1.0),
+            PostEffectSettings::new(PostEffect::Vignette, 0.35),
+        ];
+        let mut materials = HashMap::new();
+        for effect in effects
+            .iter()
+            .map(|settings| settings.effect)
+            .chain([PostEffect::Copy])
+        {
+            materials.insert(effect, effect.make_material(gl)?);
+        }
+        // all post shaders share post.vert, so the quad works with any of them
+        let mut quad = MeshRenderer::with_render_flags(&[], materials[&PostEffect::Copy].clone());
+        quad.set_mesh(gl, Rc::new(utils::make_quad()))?;
+        // keep the scene in HDR when the browser can render to float textures
+        let color_format = match gl.supported_extensions().contains("EXT_color_buffer_float") {
+            true => glow::RGBA16F,
+            false => glow::RGBA8,
+        };
+        Ok(Self {
+            effects,
+            materials,
+            quad,
+            color_format,
+            scene: None,
+            ping_pong: Vec::new(),
+            viewport: [0; 4],
+        })
+    }
+
+    /* moves the effect at index one step earlier in the chain */
+    pub fn move_up(&mut self, index: usize) {
+        if index > 0 && index < self.effects.len() {
+            self.effects.swap(index - 1, index);
+        }
+    }
+
+    /* binds the offscreen scene target, (re)created to the size of the current viewport */
+    pub fn begin_scene(&mut self, gl: &GlContext) -> Result<(), String> {
+        unsafe { gl.get_parameter_i32_slice(glow::VIEWPORT, &mut self.viewport) };
+        let size = (self.viewport[2], self.viewport[3]);
+        if self.scene.as_ref().map(|scene| scene.get_size()) != Some(size) {
+            // the old targets are released at the end of the frame
+            self.scene = None;
+            self.ping_pong.clear();
+            let (width, height) = size;
+            self.scene = Some(RenderTarget::new(
+                gl,
+                width,
+                height,
+                self.color_format,
+                true,
+            )?);
+            for _ in 0..2 {
+                let target = RenderTarget::new(gl, width, height, self.color_format, false)?;
+                self.ping_pong.push(target);
+            }
+        }
+        if let Some(scene) = &self.scene {
+            scene.bind(gl);
+        }
+        Ok(())
+    }
+
+    /* runs the effect chain on the scene, ends with the default framebuffer bound */
+    pub fn finish(&mut self, gl: &GlContext, camera: &Camera) -> Result<(), String> {
+        let Some(scene) = &self.scene else {
+            return Ok(());
+        };
+        let mut chain: Vec<_> = self
+            .effects
+            .iter()
+            .filter(|settings| settings.enabled)
+            .copied()
+            .collect();
+        if chain.is_empty() {
+            chain.push(PostEffectSettings::new(PostEffect::Copy, 0.0));
+        }
+
+        let (width, height) = scene.get_size();
+        let texel_size = Vec2::new(1.0 / width as f32, 1.0 / height as f32);
+        gl.set_flag(GlRenderFlags::DepthTest, false);
+        gl.set_flag(GlRenderFlags::Blend, false);
+        gl.set_flag(GlRenderFlags::CullFace, false);
+        gl.set_texture(
+            DEPTH_TEXTURE_UNIT,
+            glow::TEXTURE_2D,
+            scene.get_depth_texture(),
+        );
+
+        let mut source = scene.get_color_texture();
+        for (i, settings) in chain.iter().enumerate() {
+            let is_last = i == chain.len() - 1;
+            let target = &self.ping_pong[i % 2];
+            match is_last {
+                true => unsafe {
+                    gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+                    let [x, y, width, height] = self.viewport;
+                    gl.viewport(x, y, width, height);
+                },
+                false => target.bind(gl),
+            }
+
+            let effect = settings.effect;
+            let material = self
+                .materials
+                .get_mut(&effect)
+                .ok_or(format!("No material for the {} effect", effect.get_name()))?;
+            effect.update_material(material, settings.strength, texel_size, camera)?;
+            material.apply(gl);
+            gl.set_texture(0, glow::TEXTURE_2D, Some(source));
+            self.quad.draw(gl);
+            source = target.get_color_texture();
+        }
+
+        gl.set_texture(0, glow::TEXTURE_2D, None);
+        gl.set_texture(DEPTH_TEXTURE_UNIT, glow::TEXTURE_2D, None);
+        gl.set_flag(GlRenderFlags::DepthTest, true);
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use glow::Texture;
+
+use crate::utils::{FramebufferHandle, GlContext, GpuMemoryCategory, TextureHandle};
+
+use super::{format_texel_bytes, texture_bytes};
+
+/* offscreen framebuffer with a colour texture and an optional depth texture, all released when
+ * the target is dropped */
+#[derive(Debug)]
+pub struct RenderTarget {
+    framebuffer: FramebufferHandle,
+    color_texture: TextureHandle,
+    depth_texture: Option<TextureHandle>,
+    width: i32,
+    height: i32,
+}
+
+impl RenderTarget {
+    pub fn new(
+        gl: &GlContext,
+        width: i32,
+        height: i32,
+        color_format: u32,
+        with_depth: bool,
+    ) -> Result<Self, String> {
+        unsafe {
+            let framebuffer = gl.new_framebuffer()?;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer.key()));
+
+            let color_texture = create_texture(gl, width, height, color_format, glow::LINEAR)?;
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(color_texture.key()),
+                0,
+            );
+
+            let depth_texture = match with_depth {
+                false => None,
+                true => {
+                    let format = glow::DEPTH_COMPONENT24;
+                    let texture = create_texture(gl, width, height, format, glow::NEAREST)?;
+                    gl.framebuffer_texture_2d(
+                        glow::FRAMEBUFFER,
+                        glow::DEPTH_ATTACHMENT,
+                        glow::TEXTURE_2D,
+                        Some(texture.key()),
+                        0,
+                    );
+                    Some(texture)
+                }
+            };
+
+            let status = gl.check_framebuffer_status(glow::FRAMEBUFFER);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                return Err(format!("Render target framebuffer incomplete: {status:#x}"));
+            }
+            Ok(Self {
+                framebuffer,
+                color_texture,
+                depth_texture,
+                width,
+                height,
+            })
+        }
+    }
+
+    pub fn get_size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    pub fn get_color_texture(&self) -> Texture {
+        self.color_texture.key()
+    }
+
+    pub fn get_depth_texture(&self) -> Option<Texture> {
+        self.depth_texture.as_ref().map(|texture| texture.key())
+    }
+
+    /* renders into this target from now on */
+    pub fn bind(&self, gl: &GlContext) {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.framebuffer.key()));
+            gl.viewport(0, 0, self.width, self.height);
+        }
+    }
+}
+
+unsafe fn create_texture(
+    gl: &GlContext,
+    width: i32,
+    height: i32,
+    format: u32,
+    filter: u32,
+) -> Result<TextureHandle, String> {
+    let texture = gl.new_texture()?;
+    gl.set_texture(0, glow::TEXTURE_2D, Some(texture.key()));
+    gl.tex_storage_2d(glow::TEXTURE_2D, 1, format, width, height);
+    let bytes = texture_bytes(
+        width as _,
+        height as _,
+        1,
+        format_texel_bytes(format),
+        false,
+    );
+    texture.set_size(GpuMemoryCategory::Textures, bytes);
+    gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, filter as i32);
+    gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, filter as i32);
+    gl.tex_parameter_i32(
+        glow::TEXTURE_2D,
+        glow::TEXTURE_WRAP_S,
+        glow::CLAMP_TO_EDGE as i32,
+    );
+    gl.tex_parameter_i32(
+        glow::TEXTURE_2D,
+        glow::TEXTURE_WRAP_T,
+        glow::CLAMP_TO_EDGE as i32,
+    );
+    gl.set_texture(0, glow::TEXTURE_2D, None);
+    Ok(texture)
+}
@@ -0,0 +1,220 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::utils::{fetch_text, GlContext};
+
+use super::{TextureArray, TextureArrayLoader, TextureArraySource};
+
+/* chunk shader files a pack can replace, sources can't #include other files */
+pub const PACK_SHADER_FILES: [&str; 2] = ["chunk.vert", "chunk.frag"];
+
+type Fetched<T> = Rc<RefCell<Option<Result<T, String>>>>;
+
+/* textures, chunk shaders and block colors read from a pack.txt manifest. Paths are relative
+ * to the manifest, unless they start with / or are full URLs */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourcePack {
+    pub name: String,
+    pub textures: Vec<TextureArraySource>,
+    /* paths of chunk.vert and chunk.frag replacements */
+    pub shaders: [Option<String>; 2],
+    /* (texture name, color) multiplied into the layers */
+    pub colors: Vec<(String, [u8; 3])>,
+}
+
+/* a pack ready to be drawn with */
+#[derive(Debug)]
+pub struct LoadedResourcePack {
+    pub name: String,
+    pub texture_array: TextureArray,
+    /* sources of chunk.vert and chunk.frag, None keeps the built-in one */
+    pub shaders: [Option<String>; 2],
+}
+
+impl ResourcePack {
+    /* one directive per line, lines starting with # are comments:
+     *   name = <pack name>
+     *   texture <name> = <path>
+     *   strip <path> = <name> <name> ...
+     *   shader chunk.vert|chunk.frag = <path>
+     *   color <name> = #rrggbb */
+    pub fn parse(manifest: &str, manifest_url: &str) -> Result<Self, String> {
+        let base_url = manifest_url.rsplit_once('/').map(|(dir, _)| dir);
+        let mut pack = Self {
+            name: manifest_url.to_string(),
+            textures: Vec::new(),
+            shaders: [None, None],
+            colors: Vec::new(),
+        };
+        for (index, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| format!("{manifest_url}:{}: {message}", index + 1);
+            let (directive, value) = line
+                .split_once('=')
+                .ok_or(error("expected <directive> = <value>"))?;
+            let value = value.trim();
+            let mut words = directive.split_whitespace();
+            let (Some(keyword), argument, None) = (words.next(), words.next(), words.next()) else {
+                return Err(error("expected one word before ="));
+            };
+            match (keyword, argument) {
+                ("name", None) => pack.name = value.to_string(),
+                ("texture", Some(name)) => pack.textures.push(TextureArraySource::Image {
+                    name: name.to_string(),
+                    path: resolve_path(base_url, value),
+                }),
+                ("strip", Some(path)) => {
+                    let names: Vec<String> = value.split_whitespace().map(String::from).collect();
+                    if names.is_empty() {
+                        return Err(error("a strip needs at least one texture name"));
+                    }
+                    pack.textures.push(TextureArraySource::Strip {
+                        names,
+                        path: resolve_path(base_url, path),
+                    });
+                }
+                ("shader", Some(file)) => {
+                    let index = PACK_SHADER_FILES
+                        .iter()
+                        .position(|f| *f == file)
+                        .ok_or(error(&format!("can't replace shader {file}")))?;
+                    pack.shaders[index] = Some(resolve_path(base_url, value));
+                }
+                ("color", Some(name)) => {
+                    let color = parse_color(value).ok_or(error("expected a #rrggbb color"))?;
+                    pack.colors.push((name.to_string(), color));
+                }
+                _ => return Err(error(&format!("unknown directive {directive}"))),
+            }
+        }
+        if pack.textures.is_empty() {
+            return Err(format!("{manifest_url}: a pack needs at least one texture"));
+        }
+        Ok(pack)
+    }
+}
+
+fn resolve_path(base_url: Option<&str>, path: &str) -> String {
+    match base_url {
+        Some(base_url) if !path.starts_with('/') && !path.contains("://") => {
+            format!("{base_url}/{path}")
+        }
+        _ => path.to_string(),
+    }
+}
+
+fn parse_color(value: &str) -> Option<[u8; 3]> {
+    let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[derive(Debug)]
+enum PackLoading {
+    Manifest(Fetched<String>),
+    Content {
+        pack: ResourcePack,
+        textures: TextureArrayLoader,
+        shaders: [Option<Fetched<String>>; 2],
+    },
+    Done,
+}
+
+/* fetches a manifest then everything it lists, in the background on the web */
+#[derive(Debug)]
+pub struct ResourcePackLoader {
+    url: String,
+    loading: PackLoading,
+}
+
+impl ResourcePackLoader {
+    pub fn new(manifest_url: &str) -> Self {
+        let manifest: Fetched<String> = Rc::new(RefCell::new(None));
+        let result = manifest.clone();
+        fetch_text(manifest_url, move |text| *result.borrow_mut() = Some(text));
+        Self {
+            url: manifest_url.to_string(),
+            loading: PackLoading::Manifest(manifest),
+        }
+    }
+
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    /* Some once the pack is loaded or failed to, None while files are still fetched */
+    pub fn poll(&mut self, gl: &GlContext) -> Option<Result<LoadedResourcePack, String>> {
+        if let PackLoading::Manifest(manifest) = &self.loading {
+            let manifest = manifest.borrow_mut().take()?;
+            match manifest.and_then(|text| ResourcePack::parse(&text, &self.url)) {
+                Ok(pack) => self.start_content(pack),
+                Err(e) => {
+                    self.loading = PackLoading::Done;
+                    return Some(Err(e));
+                }
+            }
+        }
+        let PackLoading::Content {
+            pack,
+            textures,
+            shaders,
+        } = &self.loading
+        else {
+            return None;
+        };
+        if shaders.iter().flatten().any(|s| s.borrow().is_none()) {
+            return None;
+        }
+        let texture_array = textures.build(gl)?;
+        let result = texture_array.and_then(|texture_array| {
+            let mut sources = [None, None];
+            for (i, shader) in shaders.iter().enumerate() {
+                if let Some(shader) = shader {
+                    let source = shader.borrow_mut().take().expect("checked above")?;
+                    sources[i] = Some(check_self_contained(PACK_SHADER_FILES[i], source)?);
+                }
+            }
+            Ok(LoadedResourcePack {
+                name: pack.name.clone(),
+                texture_array,
+                shaders: sources,
+            })
+        });
+        self.loading = PackLoading::Done;
+        Some(result)
+    }
+
+    fn start_content(&mut self, pack: ResourcePack) {
+        let textures =
+            TextureArrayLoader::new(pack.textures.clone()).with_tints(pack.colors.clone());
+        let shaders = pack.shaders.clone().map(|path| {
+            path.map(|path| {
+                let source: Fetched<String> = Rc::new(RefCell::new(None));
+                let result = source.clone();
+                fetch_text(&path, move |text| *result.borrow_mut() = Some(text));
+                source
+            })
+        });
+        self.loading = PackLoading::Content {
+            pack,
+            textures,
+            shaders,
+        };
+    }
+}
+
+/* built-in shaders get their includes resolved at build time, pack ones can't */
+fn check_self_contained(name: &str, source: String) -> Result<String, String> {
+    match source
+        .lines()
+        .position(|line| line.trim().starts_with("#include"))
+    {
+        Some(index) => Err(format!(
+            "{name}:{}: #include isn't supported in pack shaders",
+            index + 1
+        )),
+        None => Ok(source),
+    }
+}
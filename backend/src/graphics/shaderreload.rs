@@ -0,0 +1,224 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    rc::{Rc, Weak},
+};
+
+use log::info;
+
+use crate::utils::{fetch_text, performance_now, GlContext};
+
+use super::{ProgramRef, ShaderDef, ShaderProgram};
+
+/* the dev server serves the repository, shaders included */
+const SHADERS_URL: &str = "backend/shaders";
+const POLL_INTERVAL_MS: f64 = 1000.0;
+
+struct WatchedProgram {
+    def: ShaderDef,
+    slot: Weak<RefCell<Rc<ShaderProgram>>>,
+    /* sources that failed to compile, not retried until they change again */
+    failed_sources: Option<(String, String)>,
+}
+
+thread_local! {
+    static WATCHED_PROGRAMS: RefCell<Vec<WatchedProgram>> = const { RefCell::new(Vec::new()) };
+}
+
+/* called by ShaderDef::compile_shared, the program is watched as long as it is alive */
+pub fn watch_shader(def: &ShaderDef, program: &ProgramRef) {
+    WATCHED_PROGRAMS.with_borrow_mut(|watched| {
+        watched.push(WatchedProgram {
+            def: def.clone(),
+            slot: program.downgrade(),
+            failed_sources: None,
+        })
+    });
+}
+
+/* polls the dev server for the shader files of every watched program and recompiles the
+ * programs whose sources changed. A program that fails to compile keeps its previous version
+ * and its error is kept until it compiles again */
+#[derive(Debug, Default)]
+pub struct ShaderReloader {
+    /* latest version of each shader file by name, None when it couldn't be fetched */
+    files: Rc<RefCell<HashMap<String, Option<String>>>>,
+    pending_fetches: Rc<Cell<usize>>,
+    polling: bool,
+    last_poll: f64,
+    /* (program name, error) */
+    errors: Vec<(String, String)>,
+}
+
+impl ShaderReloader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_errors(&self) -> impl Iterator<Item = &str> {
+        self.errors.iter().map(|(_, error)| error.as_str())
+    }
+
+    pub fn update(&mut self, gl: &GlContext) {
+        if self.pending_fetches.get() > 0 {
+            return;
+        }
+        if self.polling {
+            self.polling = false;
+            self.reload_changed(gl);
+        }
+        let now = performance_now();
+        if now - self.last_poll < POLL_INTERVAL_MS {
+            return;
+        }
+        self.last_poll = now;
+        self.polling = true;
+        for name in self.get_files_to_poll() {
+            self.fetch(name, now);
+        }
+    }
+
+    /* the files of every watched program, and every file included from the fetched ones */
+    fn get_files_to_poll(&self) -> HashSet<String> {
+        let mut names: HashSet<String> = WATCHED_PROGRAMS.with_borrow(|watched| {
+            watched
+                .iter()
+                .flat_map(|program| {
+                    let (vertex, fragment) = program.def.get_filenames();
+                    [vertex.to_string(), fragment.to_string()]
+                })
+                .collect()
+        });
+        for source in self.files.borrow().values().flatten() {
+            names.extend(source.lines().filter_map(|line| parse_include(line)?.ok()));
+        }
+        names
+    }
+
+    fn fetch(&self, name: String, now: f64) {
+        let files = self.files.clone();
+        let pending_fetches = self.pending_fetches.clone();
+        pending_fetches.set(pending_fetches.get() + 1);
+        // the timestamp keeps the browser from answering with a cached version
+        let url = format!("{SHADERS_URL}/{name}?t={now}");
+        fetch_text(&url, move |source| {
+            files.borrow_mut().insert(name, source.ok());
+            pending_fetches.set(pending_fetches.get() - 1);
+        });
+    }
+
+    fn reload_changed(&mut self, gl: &GlContext) {
+        let files = self.files.borrow();
+        WATCHED_PROGRAMS.with_borrow_mut(|watched| {
+            watched.retain(|program| program.slot.strong_count() > 0);
+            for program in watched.iter_mut() {
+                let Some(slot) = program.slot.upgrade() else {
+                    continue;
+                };
+                let name = slot.borrow().get_name().to_string();
+                let (vertex_filename, fragment_filename) = program.def.get_filenames();
+                let sources = resolve_includes(&files, vertex_filename).and_then(|vertex| {
+                    Some((vertex, resolve_includes(&files, fragment_filename)?))
+                });
+                let (vertex, fragment) = match sources {
+                    // not fetched yet
+                    None => continue,
+                    Some((Ok(vertex), Ok(fragment))) => (vertex, fragment),
+                    Some((Err(error), _)) | Some((_, Err(error))) => {
+                        set_error(&mut self.errors, &name, Some(error));
+                        continue;
+                    }
+                };
+                let (current_vertex, current_fragment) = program.def.get_sources();
+                let sources = (vertex, fragment);
+                if (current_vertex, current_fragment) == (&sources.0, &sources.1)
+                    || program.failed_sources.as_ref() == Some(&sources)
+                {
+                    continue;
+                }
+                let def = program.def.clone().with_sources(sources.0, sources.1);
+                match unsafe { def.compile(gl) } {
+                    Ok(new_program) => {
+                        info!("Reloaded shader program {name}");
+                        // the previous version is released once nothing draws with it anymore
+                        slot.replace(Rc::new(new_program));
+                        program.def = def;
+                        program.failed_sources = None;
+                        set_error(&mut self.errors, &name, None);
+                    }
+                    Err(error) => {
+                        let (vertex, fragment) = def.get_sources();
+                        program.failed_sources = Some((vertex.to_string(), fragment.to_string()));
+                        set_error(&mut self.errors, &name, Some(error));
+                    }
+                }
+            }
+        });
+    }
+}
+
+fn set_error(errors: &mut Vec<(String, String)>, name: &str, error: Option<String>) {
+    errors.retain(|(program_name, _)| program_name != name);
+    if let Some(error) = error {
+        errors.push((name.to_string(), error));
+    }
+}
+
+/* `#include "file"` lines give the included file name, like in build.rs */
+fn parse_include(line: &str) -> Option<Result<String, ()>> {
+    let directive = line.trim().strip_prefix("#include")?;
+    Some(
+        directive
+            .trim()
+            .strip_prefix('"')
+            .and_then(|name| name.strip_suffix('"'))
+            .map(str::to_string)
+            .ok_or(()),
+    )
+}
+
+/* same output as the resolution done by build.rs, None until every file is fetched */
+fn resolve_includes(
+    files: &HashMap<String, Option<String>>,
+    name: &str,
+) -> Option<Result<String, String>> {
+    let mut result = String::new();
+    match resolve_into(files, name, &mut HashSet::new(), &mut result) {
+        Some(Ok(())) => Some(Ok(result)),
+        Some(Err(error)) => Some(Err(error)),
+        None => None,
+    }
+}
+
+fn resolve_into(
+    files: &HashMap<String, Option<String>>,
+    name: &str,
+    included: &mut HashSet<String>,
+    result: &mut String,
+) -> Option<Result<(), String>> {
+    let Some(source) = files.get(name)? else {
+        return Some(Err(format!("Can't fetch shader {SHADERS_URL}/{name}")));
+    };
+    for (index, line) in source.lines().enumerate() {
+        let include = match parse_include(line) {
+            None => {
+                result.push_str(line);
+                result.push('\n');
+                continue;
+            }
+            Some(Ok(include)) => include,
+            Some(Err(())) => {
+                return Some(Err(format!(
+                    "{name}:{}: expected #include \"file\"",
+                    index + 1
+                )))
+            }
+        };
+        if included.insert(include.clone()) {
+            if let Err(error) = resolve_into(files, &include, included, result)? {
+                return Some(Err(error));
+            }
+        }
+    }
+    Some(Ok(()))
+}
@@ -1,20 +1,20 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, cell::RefCell, collections::HashMap, rc::Rc};
 
-use glam::Mat4;
-use glow::{HasContext, WebProgramKey, WebShaderKey};
-use web_sys::WebGlUniformLocation;
+use glow::{Shader, UniformLocation};
 
-use super::VertexAttrType;
+use crate::utils::{GlContext, ProgramHandle};
+
+use super::{UniformValue, VertexAttrType, VertexAttribute};
 
 #[macro_export]
 macro_rules! shader_def {
-    ($vert_name: expr, $frag_name: expr, $attributes: expr, $uniforms: expr) => {
+    ($vert_name: expr, $frag_name: expr, $uniforms: expr) => {
         ShaderDef::new(
             $vert_name,
             $frag_name,
-            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/", $vert_name)),
-            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/", $frag_name)),
-            $attributes,
+            // includes are resolved by build.rs
+            include_str!(concat!(env!("OUT_DIR"), "/shaders/", $vert_name)),
+            include_str!(concat!(env!("OUT_DIR"), "/shaders/", $frag_name)),
             $uniforms,
         )
     };
@@ -23,26 +23,47 @@ macro_rules! shader_def {
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum UniformTypes {
     // Texture,
-    ModelMatrix,
-    ViewMatrix,
-    ProjMatrix,
     WorldPosition,
 }
 
+/* an active attribute or uniform of a linked program, as reported by GL */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderInput {
+    pub name: String,
+    pub gl_type: u32,
+    /* number of array elements, 1 for non arrays */
+    pub size: i32,
+}
+
+/* (name, value) of a #define added at the top of both shaders. Declared as constants, which
+ * build.rs looks for to validate the shaders with every define the code asks for */
+pub type ShaderDefine = (&'static str, &'static str);
+pub type ShaderDefines = Vec<ShaderDefine>;
+
+#[derive(Clone)]
 pub struct ShaderDef {
     vertex_filename: &'static str,
     fragment_filename: &'static str,
-    vertex: &'static str,
-    fragment: &'static str,
+    vertex: Cow<'static, str>,
+    fragment: Cow<'static, str>,
     attributes: Vec<(VertexAttrType, &'static str)>,
     uniforms: Vec<(UniformTypes, &'static str)>,
+    uniform_blocks: Vec<(&'static str, u32)>,
+    defines: ShaderDefines,
 }
 
 #[derive(Debug)]
 pub struct ShaderProgram {
-    program_key: WebProgramKey,
+    name: String,
+    program: ProgramHandle,
     attribute_locations: HashMap<VertexAttrType, u32>,
-    uniform_locations: HashMap<UniformTypes, WebGlUniformLocation>,
+    uniform_locations: HashMap<UniformTypes, UniformLocation>,
+    /* every active uniform outside of blocks */
+    named_uniforms: HashMap<String, UniformLocation>,
+    active_attributes: Vec<ShaderInput>,
+    active_uniforms: Vec<ShaderInput>,
+    /* active attributes fed from mesh layouts */
+    mapped_attributes: Vec<(VertexAttrType, ShaderInput)>,
 }
 
 impl ShaderProgram {
@@ -50,23 +71,131 @@ impl ShaderProgram {
         self.attribute_locations.get(&attr)
     }
 
-    pub fn get_uniform_location(&self, uniform: UniformTypes) -> Option<&WebGlUniformLocation> {
+    pub fn get_uniform_location(&self, uniform: UniformTypes) -> Option<&UniformLocation> {
         self.uniform_locations.get(&uniform)
     }
 
-    // pub fn get_program(&self) -> WebProgramKey {
-    //     self.program_key
-    // }
+    /* shader file names, for error messages */
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
 
-    pub fn set_matrix(&self, gl: &glow::Context, matrix_type: UniformTypes, value: &Mat4) {
-        let location = self.get_uniform_location(matrix_type);
+    pub fn get_active_attributes(&self) -> &[ShaderInput] {
+        &self.active_attributes
+    }
+
+    /* includes the members of uniform blocks */
+    pub fn get_active_uniforms(&self) -> &[ShaderInput] {
+        &self.active_uniforms
+    }
+
+    /* checks that a mesh layout feeds every attribute of the program with the right number of
+     * components, as integers for int inputs and as floats for the others. Mesh attributes the
+     * program doesn't read are fine, they are just skipped */
+    pub fn check_layout(&self, layout: &[VertexAttribute]) -> Result<(), String> {
+        for (attr_type, input) in self.mapped_attributes.iter() {
+            let expected = gl_type_components(input.gl_type);
+            let reads_integers = is_integer_gl_type(input.gl_type);
+            match layout
+                .iter()
+                .find(|attribute| attribute.attr_type == *attr_type)
+            {
+                None => {
+                    return Err(format!(
+                        "{} reads attribute '{}' ({:?}) but the mesh layout {:?} has none, \
+                         the program reads {}",
+                        self.name,
+                        input.name,
+                        attr_type,
+                        layout.iter().map(|a| a.attr_type).collect::<Vec<_>>(),
+                        list_inputs(&self.active_attributes)
+                    ))
+                }
+                Some(attribute) if Some(attribute.components * attribute.columns) != expected => {
+                    return Err(format!(
+                        "{} reads attribute '{}' as {} but the mesh gives {} components for {:?}",
+                        self.name,
+                        input.name,
+                        glsl_type_name(input.gl_type),
+                        attribute.components * attribute.columns,
+                        attr_type
+                    ))
+                }
+                Some(attribute) if attribute.integer != reads_integers => {
+                    return Err(format!(
+                        "{} reads attribute '{}' as {} but the mesh gives {:?}",
+                        self.name,
+                        input.name,
+                        glsl_type_name(input.gl_type),
+                        attribute
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_uniform_type(&self, name: &str) -> Option<u32> {
+        self.active_uniforms
+            .iter()
+            .find(|input| input.name == name)
+            // members of uniform blocks can't be set one by one
+            .filter(|input| self.named_uniforms.contains_key(&input.name))
+            .map(|input| input.gl_type)
+    }
+
+    pub fn check_uniform(&self, name: &str, value: &UniformValue) -> Result<(), String> {
+        match self.get_uniform_type(name) {
+            None => Err(format!("No uniform '{name}' in {}", self.name)),
+            Some(gl_type) if !value.matches_gl_type(gl_type) => Err(format!(
+                "Uniform '{name}' in {} can't be set from a {}",
+                self.name,
+                value.get_name()
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /* sets a uniform by name, unknown names are ignored like unknown locations */
+    pub fn set_uniform(&self, gl: &GlContext, name: &str, value: &UniformValue) {
+        let location = self.named_uniforms.get(name);
         unsafe {
-            gl.uniform_matrix_4_f32_slice(location, false, &value.to_cols_array().as_slice())
-        };
+            match value {
+                UniformValue::Float(v) => gl.uniform_1_f32(location, *v),
+                UniformValue::Vec2(v) => gl.uniform_2_f32(location, v.x, v.y),
+                UniformValue::Vec3(v) => gl.uniform_3_f32(location, v.x, v.y, v.z),
+                UniformValue::Mat4(m) => {
+                    gl.uniform_matrix_4_f32_slice(location, false, &m.to_cols_array())
+                }
+                UniformValue::Sampler(unit) => gl.uniform_1_i32(location, *unit as i32),
+            }
+        }
+    }
+
+    pub fn gl_use(&self, gl: &GlContext) {
+        gl.set_program(Some(self.program.key()));
+    }
+}
+
+/* a program shared by everything drawing with it. Replacing it, like hot reload does, switches
+ * all of them to the new version at once, from their next draw on */
+#[derive(Debug, Clone)]
+pub struct ProgramRef(Rc<RefCell<Rc<ShaderProgram>>>);
+
+impl ProgramRef {
+    pub fn new(program: ShaderProgram) -> Self {
+        Self(Rc::new(RefCell::new(Rc::new(program))))
+    }
+
+    pub fn get(&self) -> Rc<ShaderProgram> {
+        self.0.borrow().clone()
     }
 
-    pub fn gl_use(&self, gl: &glow::Context) {
-        unsafe { gl.use_program(Some(self.program_key)) };
+    /* the slot holding the program, without keeping it alive */
+    #[cfg(feature = "shader_hot_reload")]
+    pub fn downgrade(&self) -> std::rc::Weak<RefCell<Rc<ShaderProgram>>> {
+        Rc::downgrade(&self.0)
     }
 }
 
@@ -76,78 +205,360 @@ impl ShaderDef {
         fragment_filename: &'static str,
         vertex: &'static str,
         fragment: &'static str,
-        attributes: Vec<(VertexAttrType, &'static str)>,
         uniforms: Vec<(UniformTypes, &'static str)>,
     ) -> Self {
         ShaderDef {
             vertex_filename,
             fragment_filename,
-            vertex,
-            fragment,
-            attributes,
+            vertex: vertex.into(),
+            fragment: fragment.into(),
+            attributes: Vec::new(),
             uniforms,
+            uniform_blocks: Vec::new(),
+            defines: Vec::new(),
         }
     }
 
-    pub unsafe fn compile(&self, gl: &glow::Context) -> Result<ShaderProgram, String> {
-        let vert = compile_shader(gl, glow::VERTEX_SHADER, self.vertex)?;
-        let frag = compile_shader(gl, glow::FRAGMENT_SHADER, self.fragment)?;
+    /* attributes whose names don't follow the naming convention of VertexAttrType::from_name */
+    pub fn with_attributes(mut self, attributes: Vec<(VertexAttrType, &'static str)>) -> Self {
+        self.attributes = attributes;
+        self
+    }
 
-        let program = link_program(gl, vert, frag)?;
+    /* uniform blocks (name, binding point) the program reads from */
+    pub fn with_uniform_blocks(mut self, uniform_blocks: Vec<(&'static str, u32)>) -> Self {
+        self.uniform_blocks = uniform_blocks;
+        self
+    }
 
+    pub fn with_defines(mut self, defines: ShaderDefines) -> Self {
+        self.defines = defines;
+        self
+    }
+
+    /* same program from other sources, like shaders fetched at runtime */
+    pub fn with_sources(mut self, vertex: String, fragment: String) -> Self {
+        self.vertex = vertex.into();
+        self.fragment = fragment.into();
+        self
+    }
+
+    #[cfg(feature = "shader_hot_reload")]
+    pub fn get_filenames(&self) -> (&'static str, &'static str) {
+        (self.vertex_filename, self.fragment_filename)
+    }
+
+    pub fn get_sources(&self) -> (&str, &str) {
+        (&self.vertex, &self.fragment)
+    }
+
+    /* compiles a program that can be swapped for a new version later on, registered for
+     * hot reload when that is enabled */
+    pub fn compile_shared(&self, gl: &GlContext) -> Result<ProgramRef, String> {
+        let program = ProgramRef::new(unsafe { self.compile(gl)? });
+        #[cfg(feature = "shader_hot_reload")]
+        super::watch_shader(self, &program);
+        Ok(program)
+    }
+
+    pub unsafe fn compile(&self, gl: &GlContext) -> Result<ShaderProgram, String> {
+        let vertex = add_defines(&self.vertex, &self.defines);
+        let fragment = add_defines(&self.fragment, &self.defines);
+        let name = match self.defines.is_empty() {
+            true => format!("({}, {})", self.vertex_filename, self.fragment_filename),
+            false => format!(
+                "({}, {}) with {:?}",
+                self.vertex_filename, self.fragment_filename, self.defines
+            ),
+        };
+
+        let vert = compile_shader(gl, glow::VERTEX_SHADER, &vertex)
+            .map_err(|log| format!("{}: {log}", self.vertex_filename))?;
+        let frag = match compile_shader(gl, glow::FRAGMENT_SHADER, &fragment) {
+            Ok(frag) => frag,
+            Err(log) => {
+                gl.delete_shader(vert);
+                return Err(format!("{}: {log}", self.fragment_filename));
+            }
+        };
+
+        // the program keeps what it needs from the shaders, linked or not
+        let linked = link_program(gl, vert, frag);
+        gl.delete_shader(vert);
+        gl.delete_shader(frag);
+        let handle = linked.map_err(|log| format!("{name}: {log}"))?;
+        let program = handle.key();
+
+        let active_attributes: Vec<_> = (0..gl.get_active_attributes(program))
+            .filter_map(|index| gl.get_active_attribute(program, index))
+            .map(|active| ShaderInput {
+                name: active.name,
+                gl_type: active.atype,
+                size: active.size,
+            })
+            .collect();
+        let active_uniforms: Vec<_> = (0..gl.get_active_uniforms(program))
+            .filter_map(|index| gl.get_active_uniform(program, index))
+            .map(|active| ShaderInput {
+                name: active.name.trim_end_matches("[0]").to_string(),
+                gl_type: active.utype,
+                size: active.size,
+            })
+            .collect();
+
+        for (_, attr_name) in self.attributes.iter() {
+            if !active_attributes
+                .iter()
+                .any(|input| input.name == *attr_name)
+            {
+                return Err(format!(
+                    "No active attribute '{attr_name}' in {name}, it has {}",
+                    list_inputs(&active_attributes)
+                ));
+            }
+        }
         let mut attribute_locations = HashMap::new();
-        for (attr_type, attr_name) in self.attributes.iter() {
-            let location = gl.get_attrib_location(program, *attr_name).ok_or(format!(
-                "Error getting attribute '{}' in ({}, {})",
-                attr_name, self.vertex_filename, self.fragment_filename
+        let mut mapped_attributes = Vec::new();
+        for input in active_attributes.iter() {
+            let attr_type = self
+                .attributes
+                .iter()
+                .find(|(_, attr_name)| input.name == *attr_name)
+                .map(|(attr_type, _)| *attr_type)
+                .or_else(|| VertexAttrType::from_name(&input.name));
+            // attributes outside the convention are set up by their owner, like chunk data
+            let Some(attr_type) = attr_type else {
+                continue;
+            };
+            let location = gl.get_attrib_location(program, &input.name).ok_or(format!(
+                "Error getting attribute '{}' in {name}",
+                input.name
             ))?;
-            attribute_locations.insert(*attr_type, location);
+            attribute_locations.insert(attr_type, location);
+            mapped_attributes.push((attr_type, input.clone()));
         }
 
         let mut uniform_locations = HashMap::new();
         for (u_type, u_name) in self.uniforms.iter() {
-            let location = gl.get_uniform_location(program, *u_name).ok_or(format!(
-                "Error getting uniform '{}' in ({}, {})",
-                u_name, self.vertex_filename, self.fragment_filename
-            ))?;
-            uniform_locations.insert(*u_type, location);
+            match gl.get_uniform_location(program, *u_name) {
+                Some(location) => {
+                    uniform_locations.insert(*u_type, location);
+                }
+                // compiled out by the defines or unused, setting it does nothing
+                None if is_declared(&vertex, u_name) || is_declared(&fragment, u_name) => {}
+                None => {
+                    return Err(format!(
+                        "No uniform '{u_name}' in {name}, it has {}",
+                        list_inputs(&active_uniforms)
+                    ))
+                }
+            }
+        }
+
+        let mut named_uniforms = HashMap::new();
+        for input in active_uniforms.iter() {
+            // uniforms living in blocks have no location
+            if let Some(location) = gl.get_uniform_location(program, &input.name) {
+                named_uniforms.insert(input.name.clone(), location);
+            }
+        }
+
+        for (block_name, binding) in self.uniform_blocks.iter() {
+            let index = gl
+                .get_uniform_block_index(program, block_name)
+                .ok_or(format!(
+                    "Error getting uniform block '{block_name}' in {name}"
+                ))?;
+            gl.uniform_block_binding(program, index, *binding);
         }
         Ok(ShaderProgram {
+            name,
             attribute_locations,
             uniform_locations,
-            program_key: program,
+            named_uniforms,
+            active_attributes,
+            active_uniforms,
+            mapped_attributes,
+            program: handle,
         })
     }
 }
 
-unsafe fn compile_shader(
-    gl: &glow::Context,
-    shader_type: u32,
-    source: &str,
-) -> Result<WebShaderKey, String> {
+/* programs compiled from one ShaderDef with different defines, each set compiled once */
+pub struct ShaderVariants {
+    def: ShaderDef,
+    programs: HashMap<ShaderDefines, ProgramRef>,
+}
+
+impl ShaderVariants {
+    pub fn new(def: ShaderDef) -> Self {
+        Self {
+            def,
+            programs: HashMap::new(),
+        }
+    }
+
+    pub fn get(
+        &mut self,
+        gl: &GlContext,
+        mut defines: ShaderDefines,
+    ) -> Result<ProgramRef, String> {
+        // the same set in another order is the same program
+        defines.sort();
+        if let Some(program) = self.programs.get(&defines) {
+            return Ok(program.clone());
+        }
+        let def = self.def.clone().with_defines(defines.clone());
+        let program = def.compile_shared(gl)?;
+        self.programs.insert(defines, program.clone());
+        Ok(program)
+    }
+}
+
+/* #defines go right after #version, which has to stay the first line */
+fn add_defines(source: &str, defines: &ShaderDefines) -> String {
+    if defines.is_empty() {
+        return source.to_string();
+    }
+    let (version, rest) = match source.starts_with("#version") {
+        true => source.split_once('\n').unwrap_or((source, "")),
+        false => ("", source),
+    };
+    let mut result = String::with_capacity(source.len() + defines.len() * 32);
+    if !version.is_empty() {
+        result.push_str(version);
+        result.push('\n');
+    }
+    for (name, value) in defines.iter() {
+        result.push_str(&format!("#define {name} {value}\n"));
+    }
+    result.push_str(rest);
+    result
+}
+
+/* whether the source mentions a uniform of that name, in code that may be compiled out */
+fn is_declared(source: &str, name: &str) -> bool {
+    source.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("uniform ")
+            && line
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .any(|word| word == name)
+    })
+}
+
+unsafe fn compile_shader(gl: &GlContext, shader_type: u32, source: &str) -> Result<Shader, String> {
     let shader = gl.create_shader(shader_type)?;
     gl.shader_source(shader, source);
     gl.compile_shader(shader);
 
-    match gl.get_shader_compile_status(shader) {
-        true => Ok(shader),
-        false => Err(gl.get_shader_info_log(shader)),
+    if gl.get_shader_compile_status(shader) {
+        return Ok(shader);
     }
+    let log = gl.get_shader_info_log(shader);
+    gl.delete_shader(shader);
+    Err(log)
 }
 
 unsafe fn link_program(
-    gl: &glow::Context,
-    vert_shader: WebShaderKey,
-    frag_shader: WebShaderKey,
-) -> Result<WebProgramKey, String> {
-    let program = gl.create_program()?;
+    gl: &GlContext,
+    vert_shader: Shader,
+    frag_shader: Shader,
+) -> Result<ProgramHandle, String> {
+    let handle = gl.new_program()?;
+    let program = handle.key();
 
     gl.attach_shader(program, vert_shader);
     gl.attach_shader(program, frag_shader);
+    // fixed locations keep vertex arrays valid for a reloaded version of the program
+    for attr_type in VertexAttrType::NAMED {
+        if let (Some(name), Some(location)) = (attr_type.get_name(), attr_type.get_location()) {
+            gl.bind_attrib_location(program, location, name);
+        }
+    }
     gl.link_program(program);
 
     match gl.get_program_link_status(program) {
-        true => Ok(program),
+        true => Ok(handle),
         false => Err(gl.get_program_info_log(program)),
     }
 }
+
+/* "vec3 position, float depth" for error messages */
+fn list_inputs(inputs: &[ShaderInput]) -> String {
+    match inputs.is_empty() {
+        true => "none".to_string(),
+        false => inputs
+            .iter()
+            .map(|input| format!("{} {}", glsl_type_name(input.gl_type), input.name))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+/* number of components of a vertex attribute type */
+pub fn gl_type_components(gl_type: u32) -> Option<usize> {
+    match gl_type {
+        glow::FLOAT | glow::INT | glow::UNSIGNED_INT => Some(1),
+        glow::FLOAT_VEC2 | glow::INT_VEC2 | glow::UNSIGNED_INT_VEC2 => Some(2),
+        glow::FLOAT_VEC3 | glow::INT_VEC3 | glow::UNSIGNED_INT_VEC3 => Some(3),
+        glow::FLOAT_VEC4 | glow::INT_VEC4 | glow::UNSIGNED_INT_VEC4 => Some(4),
+        glow::FLOAT_MAT3 => Some(9),
+        glow::FLOAT_MAT4 => Some(16),
+        _ => None,
+    }
+}
+
+pub fn is_integer_gl_type(gl_type: u32) -> bool {
+    [
+        glow::INT,
+        glow::INT_VEC2,
+        glow::INT_VEC3,
+        glow::INT_VEC4,
+        glow::UNSIGNED_INT,
+        glow::UNSIGNED_INT_VEC2,
+        glow::UNSIGNED_INT_VEC3,
+        glow::UNSIGNED_INT_VEC4,
+    ]
+    .contains(&gl_type)
+}
+
+const GLSL_TYPES: [(u32, &str); 22] = [
+    (glow::FLOAT, "float"),
+    (glow::FLOAT_VEC2, "vec2"),
+    (glow::FLOAT_VEC3, "vec3"),
+    (glow::FLOAT_VEC4, "vec4"),
+    (glow::INT, "int"),
+    (glow::INT_VEC2, "ivec2"),
+    (glow::INT_VEC3, "ivec3"),
+    (glow::INT_VEC4, "ivec4"),
+    (glow::UNSIGNED_INT, "uint"),
+    (glow::UNSIGNED_INT_VEC2, "uvec2"),
+    (glow::UNSIGNED_INT_VEC3, "uvec3"),
+    (glow::UNSIGNED_INT_VEC4, "uvec4"),
+    (glow::BOOL, "bool"),
+    (glow::FLOAT_MAT2, "mat2"),
+    (glow::FLOAT_MAT3, "mat3"),
+    (glow::FLOAT_MAT4, "mat4"),
+    (glow::SAMPLER_2D, "sampler2D"),
+    (glow::SAMPLER_3D, "sampler3D"),
+    (glow::SAMPLER_CUBE, "samplerCube"),
+    (glow::SAMPLER_2D_SHADOW, "sampler2DShadow"),
+    (glow::SAMPLER_2D_ARRAY, "sampler2DArray"),
+    (glow::SAMPLER_2D_ARRAY_SHADOW, "sampler2DArrayShadow"),
+];
+
+pub fn glsl_type_name(gl_type: u32) -> &'static str {
+    GLSL_TYPES
+        .iter()
+        .find(|(t, _)| *t == gl_type)
+        .map_or("unknown", |(_, name)| name)
+}
+
+/* the GL type enum of a GLSL type name, like glow::FLOAT_VEC3 for "vec3" */
+pub fn gl_type_from_glsl(name: &str) -> Option<u32> {
+    GLSL_TYPES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(gl_type, _)| *gl_type)
+}
@@ -0,0 +1,184 @@
+use glam::{Mat4, Vec3};
+
+use crate::{
+    shader_def,
+    utils::{FramebufferHandle, GlContext, GlRenderFlags, GpuMemoryCategory, TextureHandle},
+};
+
+use super::{
+    format_texel_bytes, texture_bytes, Camera, Material, ProgramRef, ShaderDef, UniformValue,
+    FRAME_DATA_BLOCK,
+};
+
+pub const DEFAULT_SHADOW_MAP_SIZE: i32 = 2048;
+/* how far from the camera shadows are drawn, in blocks */
+const SHADOW_DISTANCE: f32 = 96.0;
+/* casters up to this far towards the sun from the shadowed area still cast into it */
+const SHADOW_CASTER_MARGIN: f32 = 128.0;
+/* high enough not to collide with material textures */
+pub const SHADOW_MAP_UNIT: u32 = 7;
+/* slope scaled depth bias against shadow acne */
+const POLYGON_OFFSET_FACTOR: f32 = 2.0;
+const POLYGON_OFFSET_UNITS: f32 = 4.0;
+
+/* depth map rendered from the sun, with an orthographic camera covering the near part of the
+ * view frustum. The shadow camera only moves in whole texels and keeps the same size whatever
+ * the view orientation, so shadow edges don't shimmer when the camera moves */
+#[derive(Debug)]
+pub struct ShadowMap {
+    pub enabled: bool,
+    framebuffer: FramebufferHandle,
+    depth_texture: TextureHandle,
+    size: i32,
+    view: Mat4,
+    projection: Mat4,
+    /* radius of the shadowed area, in blocks */
+    radius: f32,
+    /* draws objects into the map, the model matrix being set per object */
+    object_material: Material,
+}
+
+impl ShadowMap {
+    pub fn new(gl: &GlContext, size: i32) -> Result<Self, String> {
+        unsafe {
+            let depth_texture = gl.new_texture()?;
+            gl.set_texture(0, glow::TEXTURE_2D, Some(depth_texture.key()));
+            let format = glow::DEPTH_COMPONENT24;
+            gl.tex_storage_2d(glow::TEXTURE_2D, 1, format, size, size);
+            let bytes = texture_bytes(size as _, size as _, 1, format_texel_bytes(format), false);
+            depth_texture.set_size(GpuMemoryCategory::Textures, bytes);
+            // linear filtering on a comparison sampler gives a free 2x2 PCF
+            let parameters = [
+                (glow::TEXTURE_MIN_FILTER, glow::LINEAR),
+                (glow::TEXTURE_MAG_FILTER, glow::LINEAR),
+                (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_COMPARE_MODE, glow::COMPARE_REF_TO_TEXTURE),
+                (glow::TEXTURE_COMPARE_FUNC, glow::LEQUAL),
+            ];
+            for (parameter, value) in parameters {
+                gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+            }
+            gl.set_texture(0, glow::TEXTURE_2D, None);
+
+            let framebuffer = gl.new_framebuffer()?;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer.key()));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::DEPTH_ATTACHMENT,
+                glow::TEXTURE_2D,
+                Some(depth_texture.key()),
+                0,
+            );
+            gl.draw_buffers(&[glow::NONE]);
+            gl.read_buffer(glow::NONE);
+            let status = gl.check_framebuffer_status(glow::FRAMEBUFFER);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                return Err(format!("Shadow map framebuffer incomplete: {status:#x}"));
+            }
+
+            Ok(Self {
+                enabled: true,
+                framebuffer,
+                depth_texture,
+                size,
+                view: Mat4::IDENTITY,
+                projection: Mat4::IDENTITY,
+                radius: SHADOW_DISTANCE,
+                object_material: Material::new(&compile_object_shader(gl)?)
+                    .with_uniform("model", UniformValue::Mat4(Mat4::IDENTITY))?,
+            })
+        }
+    }
+
+    /* fits the shadow camera around the near part of the camera frustum */
+    pub fn fit(&mut self, camera: &Camera, sun_direction: Vec3) {
+        let corners = camera.get_frustum_corners(0.0, SHADOW_DISTANCE);
+        let center = corners.iter().sum::<Vec3>() / corners.len() as f32;
+        // a bounding sphere doesn't change size when the camera turns
+        let radius = corners
+            .iter()
+            .map(|corner| corner.distance(center))
+            .fold(0.0, f32::max)
+            .ceil();
+
+        let up = match sun_direction.y.abs() > 0.99 {
+            true => Vec3::Z,
+            false => Vec3::Y,
+        };
+        let light_view = Mat4::look_at_rh(Vec3::ZERO, sun_direction, up);
+        let texel_size = 2.0 * radius / self.size as f32;
+        let light_center = light_view.transform_point3(center);
+        let snapped_center = Vec3::new(
+            (light_center.x / texel_size).floor() * texel_size,
+            (light_center.y / texel_size).floor() * texel_size,
+            light_center.z,
+        );
+
+        self.radius = radius;
+        self.view = Mat4::from_translation(-snapped_center) * light_view;
+        self.projection = Mat4::orthographic_rh_gl(
+            -radius,
+            radius,
+            -radius,
+            radius,
+            -radius - SHADOW_CASTER_MARGIN,
+            radius,
+        );
+    }
+
+    pub fn get_matrix(&self) -> Mat4 {
+        self.projection * self.view
+    }
+
+    /* whether a sphere can cast a shadow into the shadowed area */
+    pub fn may_cast_shadow(&self, center: Vec3, radius: f32) -> bool {
+        let light_position = self.view.transform_point3(center);
+        let max_distance = self.radius + radius;
+        light_position.x.abs() <= max_distance && light_position.y.abs() <= max_distance
+    }
+
+    pub fn get_object_material(&self) -> &Material {
+        &self.object_material
+    }
+
+    /* binds the shadow framebuffer, returns the viewport to give back to end() */
+    pub fn begin(&self, gl: &GlContext) -> [i32; 4] {
+        let mut viewport = [0; 4];
+        unsafe {
+            gl.get_parameter_i32_slice(glow::VIEWPORT, &mut viewport);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.framebuffer.key()));
+            gl.viewport(0, 0, self.size, self.size);
+            gl.depth_mask(true);
+            gl.clear(glow::DEPTH_BUFFER_BIT);
+            gl.set_flag(GlRenderFlags::DepthTest, true);
+            gl.set_flag(GlRenderFlags::CullFace, true);
+            gl.set_flag(GlRenderFlags::Blend, false);
+            gl.set_flag(GlRenderFlags::PolygonOffsetFill, true);
+            gl.polygon_offset(POLYGON_OFFSET_FACTOR, POLYGON_OFFSET_UNITS);
+        }
+        viewport
+    }
+
+    pub fn end(&self, gl: &GlContext, viewport: [i32; 4]) {
+        unsafe {
+            gl.set_flag(GlRenderFlags::PolygonOffsetFill, false);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        }
+    }
+
+    /* binds the shadow map for the materials sampling it, see Material::with_shadow_map. The
+     * matrix and whether shadows are enabled are in the FrameData block */
+    pub fn bind(&self, gl: &GlContext) {
+        let texture = self.enabled.then(|| self.depth_texture.key());
+        gl.set_texture(SHADOW_MAP_UNIT, glow::TEXTURE_2D, texture);
+    }
+}
+
+fn compile_object_shader(gl: &GlContext) -> Result<ProgramRef, String> {
+    shader_def!("shadow_object.vert", "shadow.frag", vec![])
+        .with_uniform_blocks(vec![FRAME_DATA_BLOCK])
+        .compile_shared(gl)
+}
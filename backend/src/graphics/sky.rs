@@ -0,0 +1,69 @@
+use std::rc::Rc;
+
+use glam::{vec3, Vec3};
+
+use crate::{
+    core::TimeOfDay,
+    shader_def,
+    utils::{self, GlContext, GlRenderFlags},
+};
+
+use super::{Material, MeshRenderer, ProgramRef, ShaderDef, UniformValue, FRAME_DATA_BLOCK};
+
+const DAY_ZENITH: Vec3 = vec3(0.25, 0.45, 0.85);
+const DAY_HORIZON: Vec3 = vec3(0.65, 0.8, 0.95);
+const NIGHT_ZENITH: Vec3 = vec3(0.01, 0.01, 0.04);
+const NIGHT_HORIZON: Vec3 = vec3(0.04, 0.05, 0.1);
+const SUNSET_HORIZON: Vec3 = vec3(0.95, 0.5, 0.25);
+
+/* the sky is drawn first, behind everything, so it neither tests nor writes depth */
+const SKY_GL_PARAMS: [GlRenderFlags; 0] = [];
+
+#[derive(Debug, Clone, Copy)]
+pub struct SkyColors {
+    pub zenith: Vec3,
+    pub horizon: Vec3,
+}
+
+impl SkyColors {
+    pub fn at(time_of_day: &TimeOfDay) -> Self {
+        let daylight = time_of_day.get_daylight();
+        let sunset = 1.0 - (time_of_day.get_sun_position().y * 3.0).abs().min(1.0);
+        let horizon = NIGHT_HORIZON.lerp(DAY_HORIZON, daylight);
+        Self {
+            zenith: NIGHT_ZENITH.lerp(DAY_ZENITH, daylight),
+            horizon: horizon.lerp(SUNSET_HORIZON, sunset * daylight.max(0.3)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Sky {
+    renderer: MeshRenderer,
+}
+
+impl Sky {
+    pub fn new(gl: &GlContext) -> Result<Self, String> {
+        let program = compile_shader(gl)?;
+        let mut renderer = MeshRenderer::with_render_flags(&SKY_GL_PARAMS, Material::new(&program));
+        renderer.set_mesh(gl, Rc::new(utils::make_quad()))?;
+        Ok(Self { renderer })
+    }
+
+    pub fn render(&mut self, gl: &GlContext, time_of_day: &TimeOfDay) -> Result<(), String> {
+        let colors = SkyColors::at(time_of_day);
+        let sun_position = time_of_day.get_sun_position();
+        let material = self.renderer.get_material_mut();
+        material.set_uniform("sun_position", UniformValue::Vec3(sun_position))?;
+        material.set_uniform("zenith_color", UniformValue::Vec3(colors.zenith))?;
+        material.set_uniform("horizon_color", UniformValue::Vec3(colors.horizon))?;
+        self.renderer.render(gl);
+        Ok(())
+    }
+}
+
+fn compile_shader(gl: &GlContext) -> Result<ProgramRef, String> {
+    shader_def!("sky.vert", "sky.frag", vec![])
+        .with_uniform_blocks(vec![FRAME_DATA_BLOCK])
+        .compile_shared(gl)
+}
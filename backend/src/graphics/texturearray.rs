@@ -0,0 +1,283 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::utils::{fetch_bytes, GlContext};
+
+use super::{upload_loaded_texture, TextureDef, TextureType};
+
+/* the bytes of each source, None until fetched */
+type FetchedSources = Rc<RefCell<Vec<Option<Result<Vec<u8>, String>>>>>;
+
+/* 8 bit RGBA pixels, rows from top to bottom */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, String> {
+        if pixels.len() != width as usize * height as usize * 4 {
+            return Err(format!(
+                "{} bytes of pixels for a {width}x{height} image",
+                pixels.len()
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn decode_png(bytes: &[u8]) -> Result<Self, String> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(|e| e.to_string())?;
+        let bytes = &buffer[..info.buffer_size()];
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => bytes.to_vec(),
+            png::ColorType::Rgb => bytes
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => bytes
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            png::ColorType::Grayscale => bytes.iter().flat_map(|g| [*g, *g, *g, 255]).collect(),
+            png::ColorType::Indexed => return Err("indexed colors were not expanded".to_string()),
+        };
+        Self::new(info.width, info.height, pixels)
+    }
+
+    /* cuts an image made of `count` layers stacked vertically, like blocks_atlas.png */
+    pub fn split_layers(&self, count: u32) -> Result<Vec<RgbaImage>, String> {
+        if count == 0 || !self.height.is_multiple_of(count) {
+            return Err(format!(
+                "A {}x{} image can't be split in {count} layers",
+                self.width, self.height
+            ));
+        }
+        let layer_height = self.height / count;
+        let layer_bytes = self.pixels.len() / count as usize;
+        Ok(self
+            .pixels
+            .chunks_exact(layer_bytes)
+            .map(|pixels| RgbaImage {
+                width: self.width,
+                height: layer_height,
+                pixels: pixels.to_vec(),
+            })
+            .collect())
+    }
+}
+
+/* a TEXTURE_2D_ARRAY and the layer of each image it was built from */
+#[derive(Debug, Clone)]
+pub struct TextureArray {
+    pub texture: Rc<TextureDef>,
+    pub layers: HashMap<String, u32>,
+}
+
+/* stacks named images of the same size into the layers of a texture array */
+#[derive(Debug, Default)]
+pub struct TextureArrayBuilder {
+    layers: Vec<(String, RgbaImage)>,
+}
+
+impl TextureArrayBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_image(mut self, name: &str, image: RgbaImage) -> Result<Self, String> {
+        self.add_image(name, image)?;
+        Ok(self)
+    }
+
+    /* returns the layer of the image. An image with the name of a previous one replaces it
+     * in its layer */
+    pub fn add_image(&mut self, name: &str, image: RgbaImage) -> Result<u32, String> {
+        if let Some((_, first)) = self.layers.first() {
+            if (image.width, image.height) != (first.width, first.height) {
+                return Err(format!(
+                    "Texture array: {name} is {}x{}, the other layers are {}x{}",
+                    image.width, image.height, first.width, first.height
+                ));
+            }
+        } else if image.width == 0 || image.height == 0 {
+            return Err(format!("Texture array: {name} is empty"));
+        }
+        match self.layers.iter().position(|(n, _)| n == name) {
+            Some(layer) => {
+                self.layers[layer].1 = image;
+                Ok(layer as u32)
+            }
+            None => {
+                self.layers.push((name.to_string(), image));
+                Ok(self.layers.len() as u32 - 1)
+            }
+        }
+    }
+
+    /* adds the layers of an image made of layers stacked vertically, one name per layer */
+    pub fn add_strip(&mut self, names: &[String], strip: &RgbaImage) -> Result<(), String> {
+        let layers = strip.split_layers(names.len() as u32)?;
+        for (name, image) in names.iter().zip(layers) {
+            self.add_image(name, image)?;
+        }
+        Ok(())
+    }
+
+    /* multiplies the color of every pixel of a layer, alpha is kept */
+    pub fn tint_layer(&mut self, name: &str, color: [u8; 3]) -> Result<(), String> {
+        let (_, image) = self
+            .layers
+            .iter_mut()
+            .find(|(n, _)| n == name)
+            .ok_or(format!("Texture array: no layer {name} to tint"))?;
+        for pixel in image.pixels.chunks_exact_mut(4) {
+            for (channel, tint) in pixel.iter_mut().zip(color) {
+                *channel = (*channel as u32 * tint as u32 / 255) as u8;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_layers(&self) -> HashMap<String, u32> {
+        self.layers
+            .iter()
+            .enumerate()
+            .map(|(layer, (name, _))| (name.clone(), layer as u32))
+            .collect()
+    }
+
+    pub fn build(&self, gl: &GlContext) -> Result<TextureArray, String> {
+        let (width, height) = match self.layers.first() {
+            Some((_, image)) => (image.width as i32, image.height as i32),
+            None => return Err("Texture array: no layers to build".to_string()),
+        };
+        let depth = self.layers.len() as i32;
+        let pixels: Vec<u8> = self
+            .layers
+            .iter()
+            .flat_map(|(_, image)| image.pixels.iter().copied())
+            .collect();
+        let texture_type = TextureType::Texture2DArray(depth as u32);
+        let key = gl.new_texture()?;
+        unsafe {
+            upload_loaded_texture(
+                gl,
+                &key,
+                texture_type,
+                width,
+                height * depth,
+                |target, layer_height| {
+                    gl.tex_image_3d(
+                        target,
+                        0,
+                        glow::RGBA as _,
+                        width,
+                        layer_height,
+                        depth,
+                        0,
+                        glow::RGBA,
+                        glow::UNSIGNED_BYTE,
+                        Some(&pixels),
+                    )
+                },
+            );
+        }
+        Ok(TextureArray {
+            texture: Rc::new((texture_type, key)),
+            layers: self.get_layers(),
+        })
+    }
+}
+
+/* where layers of a texture array come from */
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureArraySource {
+    /* a single layer */
+    Image { name: String, path: String },
+    /* layers stacked vertically, one name per layer */
+    Strip { names: Vec<String>, path: String },
+}
+
+impl TextureArraySource {
+    pub fn get_path(&self) -> &str {
+        match self {
+            TextureArraySource::Image { path, .. } => path,
+            TextureArraySource::Strip { path, .. } => path,
+        }
+    }
+}
+
+/* fetches the images of every source, then builds the array in the order of the sources */
+#[derive(Debug)]
+pub struct TextureArrayLoader {
+    sources: Vec<TextureArraySource>,
+    /* (layer name, color) applied once every source is added */
+    tints: Vec<(String, [u8; 3])>,
+    fetched: FetchedSources,
+}
+
+impl TextureArrayLoader {
+    pub fn new(sources: Vec<TextureArraySource>) -> Self {
+        let fetched = Rc::new(RefCell::new(vec![None; sources.len()]));
+        for (index, source) in sources.iter().enumerate() {
+            let fetched = fetched.clone();
+            fetch_bytes(source.get_path(), move |bytes| {
+                fetched.borrow_mut()[index] = Some(bytes);
+            });
+        }
+        Self {
+            sources,
+            tints: Vec::new(),
+            fetched,
+        }
+    }
+
+    pub fn with_tints(mut self, tints: Vec<(String, [u8; 3])>) -> Self {
+        self.tints = tints;
+        self
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.fetched.borrow().iter().all(Option::is_some)
+    }
+
+    /* None until every image is fetched */
+    pub fn build(&self, gl: &GlContext) -> Option<Result<TextureArray, String>> {
+        if !self.is_ready() {
+            return None;
+        }
+        let fetched = self.fetched.borrow();
+        let mut builder = TextureArrayBuilder::new();
+        for (source, bytes) in self.sources.iter().zip(fetched.iter().flatten()) {
+            let image = bytes
+                .as_ref()
+                .map_err(String::clone)
+                .and_then(|bytes| RgbaImage::decode_png(bytes))
+                .map_err(|e| format!("Texture array: {}: {e}", source.get_path()));
+            let added = image.and_then(|image| match source {
+                TextureArraySource::Image { name, .. } => {
+                    builder.add_image(name, image).map(|_| ())
+                }
+                TextureArraySource::Strip { names, .. } => builder.add_strip(names, &image),
+            });
+            if let Err(e) = added {
+                return Some(Err(e));
+            }
+        }
+        for (name, color) in self.tints.iter() {
+            if let Err(e) = builder.tint_layer(name, *color) {
+                return Some(Err(e));
+            }
+        }
+        Some(builder.build(gl))
+    }
+}
@@ -1,241 +1,319 @@
-use glow::{HasContext, WebTextureKey};
-use log::info;
-use std::cell::RefCell;
-use std::rc::Rc;
-use std::sync::Mutex;
-use wasm_bindgen::JsCast;
-use wasm_bindgen::{closure::Closure, JsValue};
-use web_sys::HtmlImageElement;
-
-use super::TextureType;
-use crate::utils::{get_document, get_performance};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+use log::{info, warn};
+
+use super::{new_placeholder_texture, upload_loaded_texture, RgbaImage, TextureType};
+use crate::utils::{fetch_bytes, performance_now, GlContext, TextureHandle};
+
+pub const DEFAULT_MAX_LOADING_TEXTURES: usize = 6;
+pub const DEFAULT_TEXTURE_TIMEOUT_MS: f64 = 10000.0;
+pub const DEFAULT_TEXTURE_RETRIES: u32 = 2;
+
+type ErrorCallback = Box<dyn FnOnce(&str)>;
+type FetchedBytes = Rc<RefCell<Option<Result<Vec<u8>, String>>>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureState {
+    /* queued or being fetched, the texture is a 1x1 placeholder */
+    Pending,
+    Ready,
+    /* every attempt failed, the placeholder stays */
+    Failed(String),
+}
+
+struct RequestData {
+    src: String,
+    state: TextureState,
+    on_error: Vec<ErrorCallback>,
+    waker: Option<Waker>,
+}
+
+/* handle to a requested texture, usable right away. Awaiting it gives the texture once loaded,
+ * as long as the loader is ticked */
+#[derive(Clone)]
+pub struct TextureRequest {
+    texture: TextureHandle,
+    data: Rc<RefCell<RequestData>>,
+}
+
+impl TextureRequest {
+    pub fn get_texture(&self) -> &TextureHandle {
+        &self.texture
+    }
+
+    pub fn get_state(&self) -> TextureState {
+        self.data.borrow().state.clone()
+    }
+
+    /* called once every attempt failed, right away if that already happened */
+    pub fn on_error(&self, callback: impl FnOnce(&str) + 'static) {
+        let mut data = self.data.borrow_mut();
+        match &data.state {
+            TextureState::Failed(error) => {
+                let error = error.clone();
+                drop(data);
+                callback(&error);
+            }
+            _ => data.on_error.push(Box::new(callback)),
+        }
+    }
+
+    fn settle(&self, state: TextureState) {
+        let mut data = self.data.borrow_mut();
+        data.state = state.clone();
+        let callbacks = std::mem::take(&mut data.on_error);
+        let waker = data.waker.take();
+        drop(data);
+        if let TextureState::Failed(error) = state {
+            for callback in callbacks {
+                callback(&error);
+            }
+        }
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl fmt::Debug for TextureRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let data = self.data.borrow();
+        f.debug_struct("TextureRequest")
+            .field("src", &data.src)
+            .field("state", &data.state)
+            .finish()
+    }
+}
+
+impl Future for TextureRequest {
+    type Output = Result<TextureHandle, String>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut data = self.data.borrow_mut();
+        match &data.state {
+            TextureState::Pending => {
+                data.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+            TextureState::Ready => Poll::Ready(Ok(self.texture.clone())),
+            TextureState::Failed(error) => Poll::Ready(Err(error.clone())),
+        }
+    }
+}
 
 #[derive(Debug)]
-enum TextureStatus {
-    Idle,
-    Busy(f64, Rc<RefCell<Closure<dyn FnMut()>>>),
+struct QueuedTexture {
+    request: TextureRequest,
+    texture_type: TextureType,
+    attempts: u32,
 }
 
 #[derive(Debug)]
 struct LoadingTexture {
-    img: HtmlImageElement,
-    key: Option<WebTextureKey>,
-    texture_type: TextureType,
-    status: TextureStatus,
+    queued: QueuedTexture,
+    start_time: f64,
+    fetched: FetchedBytes,
 }
 
+/* loads PNG images into textures in the background. Requests wait in a queue while
+ * `max_loading` others are fetched, the same way on the web and natively where paths are read
+ * relative to the working directory */
 #[derive(Debug)]
 pub struct TextureLoader {
-    pool: Rc<RefCell<Vec<LoadingTexture>>>,
+    queue: VecDeque<QueuedTexture>,
+    loading: Vec<LoadingTexture>,
+    max_loading: usize,
+    timeout_ms: f64,
+    retries: u32,
 }
 
-const EMPTY_TEXTURE: [u8; 4] = [255, 0, 255, 255];
+impl Default for TextureLoader {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            loading: Vec::new(),
+            max_loading: DEFAULT_MAX_LOADING_TEXTURES,
+            timeout_ms: DEFAULT_TEXTURE_TIMEOUT_MS,
+            retries: DEFAULT_TEXTURE_RETRIES,
+        }
+    }
+}
 
 impl TextureLoader {
-    pub fn new(pool_size: usize) -> Result<Self, JsValue> {
-        let document = get_document()?;
-        let body = document.body().ok_or("document should have a body")?;
-        let pool = (0..pool_size)
-            .map(|_| {
-                let img = document
-                    .create_element("img")
-                    .unwrap()
-                    .dyn_into::<HtmlImageElement>()
-                    .unwrap();
-                let _ = body.append_child(&img);
-                LoadingTexture {
-                    img,
-                    status: TextureStatus::Idle,
-                    key: None,
-                    texture_type: TextureType::Texture2D,
-                }
-            })
-            .collect();
-        let pool = Rc::new(RefCell::new(pool));
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_loading(mut self, max_loading: usize) -> Self {
+        self.max_loading = max_loading.max(1);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout_ms: f64) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
 
-        Ok(TextureLoader { pool })
+    /* attempts made after the first one fails or times out */
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
     }
 
-    // pub fn get_num_loading(&self) -> usize {
-    //     self.pool
-    //         .borrow()
-    //         .iter()
-    //         .filter(|tex| matches!(tex.status, TextureStatus::Busy(_, _)))
-    //         .count()
-    // }
+    /* requests that aren't settled yet */
+    pub fn get_num_pending(&self) -> usize {
+        self.queue.len() + self.loading.len()
+    }
 
     pub fn load(
         &mut self,
-        gl: &glow::Context,
+        gl: &GlContext,
         src: &str,
         texture_type: TextureType,
-    ) -> Result<WebTextureKey, String> {
-        let mut pool = self.pool.borrow_mut();
-        if let Some((index, loading_tex)) = pool
-            .iter_mut()
-            .enumerate()
-            .find(|(_, tex)| matches!(tex.status, TextureStatus::Idle))
-        {
-            if matches!(loading_tex.status, TextureStatus::Idle) {
-                let on_load = {
-                    Closure::wrap(Box::new(move || {
-                        MessageSystem::add_completed_load(index);
-                    }) as Box<dyn FnMut()>)
-                };
-
-                let rc_closure = Rc::new(RefCell::new(on_load));
-
-                loading_tex
-                    .img
-                    .set_onload(Some(rc_closure.borrow().as_ref().unchecked_ref()));
-
-                loading_tex.img.set_src(src);
-                let start_time: f64 = get_performance()?.now(); // TODO
-                loading_tex.status = TextureStatus::Busy(start_time, rc_closure);
-                unsafe {
-                    let key = Some(gl.create_texture().expect("Can't create texture"));
-                    gl.bind_texture(texture_type.into(), key);
-                    match texture_type {
-                        TextureType::Texture2D => {
-                            gl.tex_image_2d(
-                                texture_type.into(),
-                                0,
-                                glow::RGBA as _,
-                                1,
-                                1,
-                                0,
-                                glow::RGBA,
-                                glow::UNSIGNED_BYTE,
-                                Some(&EMPTY_TEXTURE),
-                            );
-                        }
-                        TextureType::Texture2DArray(_) => gl.tex_image_3d(
-                            texture_type.into(),
-                            0,
-                            glow::RGBA as _,
-                            1,
-                            1,
-                            1,
-                            0,
-                            glow::RGBA,
-                            glow::UNSIGNED_BYTE,
-                            Some(&EMPTY_TEXTURE),
-                        ),
-                    }
-
-                    loading_tex.key = key;
-                    loading_tex.texture_type = texture_type;
-                }
-
-                Ok(loading_tex.key.expect("Shouldn't be none"))
-            } else {
-                Err("Texture is already loading".to_string())
-            }
-        } else {
-            Err("Invalid texture index".to_string())
+    ) -> Result<TextureRequest, String> {
+        // no image height splits into zero layers
+        if texture_type.get_layers() == 0 {
+            return Err(format!(
+                "Texture {src}: an array texture needs at least one layer"
+            ));
         }
+        let texture = unsafe { new_placeholder_texture(gl, texture_type)? };
+        let request = TextureRequest {
+            texture,
+            data: Rc::new(RefCell::new(RequestData {
+                src: src.to_string(),
+                state: TextureState::Pending,
+                on_error: Vec::new(),
+                waker: None,
+            })),
+        };
+        self.queue.push_back(QueuedTexture {
+            request: request.clone(),
+            texture_type,
+            attempts: 0,
+        });
+        Ok(request)
     }
 
-    pub fn tick(&mut self, gl: &glow::Context) -> Result<(), String> {
-        let completed_loads = MessageSystem::take_completed_loads();
-
-        if completed_loads.len() > 0 {
-            info!("{} new messages", completed_loads.len());
+    /* starts fetching queued images and uploads the fetched ones. Natively files are read
+     * right away, so a request is settled by the tick that starts it */
+    pub fn tick(&mut self, gl: &GlContext) -> Result<(), String> {
+        let now = performance_now();
+        while self.loading.len() < self.max_loading {
+            let Some(queued) = self.queue.pop_front() else {
+                break;
+            };
+            self.start_fetch(queued, now);
         }
-
-        let mut errors = "".to_string();
-
-        for index in completed_loads {
-            if let Some(tex) = self.pool.borrow_mut().get_mut(index) {
-                match &mut tex.status {
-                    TextureStatus::Idle => {
-                        errors = format!(
-                            "{}, received message from idle LoadingTexture index {}",
-                            errors, index
-                        );
-                    }
-                    TextureStatus::Busy(start_time, _) => {
-                        let img = &tex.img;
-                        let now: f64 = get_performance()?.now();
-                        let total_time = now - *start_time;
-                        info!(
-                            "Texture loaded: {} ({}x{}) in {}ms",
-                            img.src(),
-                            img.client_width(),
-                            img.client_height(),
-                            total_time.round()
-                        );
-
-                        let texture_type = tex.texture_type.into();
-                        unsafe {
-                            gl.bind_texture(texture_type, tex.key);
-
-                            gl.tex_parameter_i32(
-                                texture_type,
-                                glow::TEXTURE_MIN_FILTER,
-                                glow::NEAREST as _,
-                            );
-                            gl.tex_parameter_i32(
-                                texture_type,
-                                glow::TEXTURE_MAG_FILTER,
-                                glow::NEAREST as _,
-                            );
-
-                            let lod = 0;
-                            let internal_format = glow::RGBA;
-                            let src_format: u32 = glow::RGBA;
-                            let src_type = glow::UNSIGNED_BYTE;
-
-                            match tex.texture_type {
-                                TextureType::Texture2D => {
-                                    gl.tex_image_2d_with_html_image(
-                                        glow::TEXTURE_2D,
-                                        lod,
-                                        internal_format as _,
-                                        src_format,
-                                        src_type,
-                                        &img,
-                                    );
-                                }
-                                TextureType::Texture2DArray(depth) => gl
-                                    .tex_image_3d_with_html_image_element(
-                                        texture_type,
-                                        lod,
-                                        internal_format as _,
-                                        img.client_width(),
-                                        img.client_height() / depth as i32,
-                                        depth as _,
-                                        0,
-                                        src_format,
-                                        glow::UNSIGNED_BYTE,
-                                        &img,
-                                    ),
-                            }
-
-                            gl.generate_mipmap(texture_type);
+        for loading in std::mem::take(&mut self.loading) {
+            let fetched = loading.fetched.borrow_mut().take();
+            let queued = loading.queued;
+            match fetched {
+                Some(Ok(bytes)) => {
+                    // a broken image won't get better with another attempt
+                    let state = match upload_image(gl, &queued, &bytes) {
+                        Ok(()) => {
+                            let src = queued.request.data.borrow().src.clone();
+                            let time = (now - loading.start_time).round();
+                            info!("Texture loaded: {src} in {time}ms");
+                            TextureState::Ready
                         }
-
-                        tex.status = TextureStatus::Idle;
-                    }
-                };
+                        Err(e) => TextureState::Failed(e),
+                    };
+                    queued.request.settle(state);
+                }
+                Some(Err(e)) => self.retry_or_fail(queued, e),
+                None if now - loading.start_time > self.timeout_ms => {
+                    let error = format!("Timed out after {}ms", self.timeout_ms);
+                    self.retry_or_fail(queued, error);
+                }
+                None => self.loading.push(LoadingTexture { queued, ..loading }),
             }
         }
-
-        // Handle any additional logic, e.g., timeouts
         Ok(())
     }
-}
 
-type MessageType = usize;
-static PENDING_MESSAGES: Mutex<Vec<MessageType>> = Mutex::new(Vec::new());
+    /* a timed out fetch keeps going, its result is dropped */
+    fn start_fetch(&mut self, mut queued: QueuedTexture, now: f64) {
+        queued.attempts += 1;
+        let fetched = Rc::new(RefCell::new(None));
+        let result = fetched.clone();
+        let src = queued.request.data.borrow().src.clone();
+        self.loading.push(LoadingTexture {
+            queued,
+            start_time: now,
+            fetched,
+        });
+        fetch_bytes(&src, move |bytes| *result.borrow_mut() = Some(bytes));
+    }
 
-struct MessageSystem {}
+    fn retry_or_fail(&mut self, queued: QueuedTexture, error: String) {
+        let src = queued.request.data.borrow().src.clone();
+        if queued.attempts <= self.retries {
+            warn!("Texture {src}: attempt {} failed: {error}", queued.attempts);
+            self.queue.push_back(queued);
+        } else {
+            warn!("Could not load texture {src}: {error}");
+            queued.request.settle(TextureState::Failed(error));
+        }
+    }
+}
 
-impl MessageSystem {
-    fn add_completed_load(msg: MessageType) {
-        PENDING_MESSAGES.lock().unwrap().push(msg);
+fn upload_image(gl: &GlContext, queued: &QueuedTexture, bytes: &[u8]) -> Result<(), String> {
+    let RgbaImage {
+        width,
+        height,
+        pixels,
+    } = RgbaImage::decode_png(bytes)?;
+    let (width, height) = (width as i32, height as i32);
+    let texture_type = queued.texture_type;
+    if height % texture_type.get_layers() as i32 != 0 {
+        return Err(format!(
+            "Height {height} isn't a multiple of {} layers",
+            texture_type.get_layers()
+        ));
     }
-    fn take_completed_loads() -> Vec<MessageType> {
-        PENDING_MESSAGES.lock().unwrap().drain(..).collect()
+    unsafe {
+        upload_loaded_texture(
+            gl,
+            &queued.request.texture,
+            texture_type,
+            width,
+            height,
+            |target, layer_height| match texture_type {
+                TextureType::Texture2D => gl.tex_image_2d(
+                    target,
+                    0,
+                    glow::RGBA as _,
+                    width,
+                    height,
+                    0,
+                    glow::RGBA,
+                    glow::UNSIGNED_BYTE,
+                    Some(&pixels),
+                ),
+                TextureType::Texture2DArray(depth) => gl.tex_image_3d(
+                    target,
+                    0,
+                    glow::RGBA as _,
+                    width,
+                    layer_height,
+                    depth as _,
+                    0,
+                    glow::RGBA,
+                    glow::UNSIGNED_BYTE,
+                    Some(&pixels),
+                ),
+            },
+        );
     }
+    Ok(())
 }
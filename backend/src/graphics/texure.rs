@@ -1,4 +1,4 @@
-use glow::WebTextureKey;
+use crate::utils::{GlContext, GpuMemoryCategory, TextureHandle};
 
 #[derive(Clone, Copy, Debug, Default)]
 pub enum TextureType {
@@ -7,7 +7,10 @@ pub enum TextureType {
     Texture2DArray(u32),
 }
 
-pub type TextureDef = (TextureType, WebTextureKey);
+pub type TextureDef = (TextureType, TextureHandle);
+
+/* shown until the loaded image is uploaded */
+const EMPTY_TEXTURE: [u8; 4] = [255, 0, 255, 255];
 
 impl Into<u32> for TextureType {
     fn into(self) -> u32 {
@@ -17,3 +20,103 @@ impl Into<u32> for TextureType {
         }
     }
 }
+
+impl TextureType {
+    pub fn get_layers(&self) -> u32 {
+        match self {
+            TextureType::Texture2D => 1,
+            TextureType::Texture2DArray(layers) => *layers,
+        }
+    }
+}
+
+/* a 1x1 texture for the loaders to fill once the image is available */
+pub(super) unsafe fn new_placeholder_texture(
+    gl: &GlContext,
+    texture_type: TextureType,
+) -> Result<TextureHandle, String> {
+    let key = gl.new_texture()?;
+    gl.set_texture(0, texture_type.into(), Some(key.key()));
+    match texture_type {
+        TextureType::Texture2D => gl.tex_image_2d(
+            texture_type.into(),
+            0,
+            glow::RGBA as _,
+            1,
+            1,
+            0,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            Some(&EMPTY_TEXTURE),
+        ),
+        TextureType::Texture2DArray(_) => gl.tex_image_3d(
+            texture_type.into(),
+            0,
+            glow::RGBA as _,
+            1,
+            1,
+            1,
+            0,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            Some(&EMPTY_TEXTURE),
+        ),
+    }
+    key.set_size(GpuMemoryCategory::Textures, EMPTY_TEXTURE.len());
+    Ok(key)
+}
+
+/* replaces a placeholder with an RGBA image of `width` x `height`, layers stacked vertically.
+ * `upload` receives the bound target and the height of one layer */
+pub(super) unsafe fn upload_loaded_texture(
+    gl: &GlContext,
+    handle: &TextureHandle,
+    texture_type: TextureType,
+    width: i32,
+    height: i32,
+    upload: impl FnOnce(u32, i32),
+) {
+    let target = texture_type.into();
+    let layers = texture_type.get_layers();
+    let layer_height = height / layers as i32;
+    gl.set_texture(0, target, Some(handle.key()));
+    gl.tex_parameter_i32(target, glow::TEXTURE_MIN_FILTER, glow::NEAREST as _);
+    gl.tex_parameter_i32(target, glow::TEXTURE_MAG_FILTER, glow::NEAREST as _);
+    upload(target, layer_height);
+    gl.generate_mipmap(target);
+
+    let bytes = texture_bytes(
+        width as usize,
+        layer_height as usize,
+        layers as usize,
+        4,
+        true,
+    );
+    handle.set_size(GpuMemoryCategory::Textures, bytes);
+}
+
+/* bytes per texel of a sized internal format */
+pub fn format_texel_bytes(format: u32) -> usize {
+    match format {
+        glow::RGBA16F => 8,
+        // depth formats are padded to 32 bits
+        _ => 4,
+    }
+}
+
+/* bytes used by a texture with the given number of layers, including its mip chain */
+pub fn texture_bytes(
+    width: usize,
+    height: usize,
+    layers: usize,
+    texel_bytes: usize,
+    mipmapped: bool,
+) -> usize {
+    let (mut width, mut height) = (width.max(1), height.max(1));
+    let mut texels = width * height;
+    while mipmapped && (width > 1 || height > 1) {
+        (width, height) = ((width / 2).max(1), (height / 2).max(1));
+        texels += width * height;
+    }
+    texels * layers * texel_bytes
+}
@@ -1,15 +1,15 @@
 use std::{collections::HashMap, convert::TryInto, rc::Rc};
 
-use crate::core::{HandleInputs, InputEventType, InputState};
+use crate::core::{HandleInputs, InputEventType, InputState, Modifiers, MouseEvent};
 use crate::shader_def;
-use crate::utils::{GlRenderFlags, GlState};
+use crate::utils::{GlContext, GlRenderFlags, GpuMemoryCategory, TextureHandle};
 use crate::{core::Time, graphics::MeshRenderer};
 
-use crate::graphics::{Mesh, ShaderDef, UniformTypes, VertexAttrType};
+use crate::graphics::{
+    Material, Mesh, ShaderDef, UniformValue, VertexAttrType, VertexAttribute, VertexDataType,
+};
 use egui::{epaint::Primitive, Event, Key, TextureFilter, TextureId, TextureWrapMode};
-use glow::{HasContext, WebTextureKey};
 use log::{info, warn};
-use web_sys::{KeyboardEvent, MouseEvent};
 
 // struct DemoData {
 //     pub name: String,
@@ -42,7 +42,7 @@ use web_sys::{KeyboardEvent, MouseEvent};
 pub struct EguiBackend {
     egui_ctx: egui::Context,
     // egui_once: bool,
-    textures: HashMap<TextureId, WebTextureKey>,
+    textures: HashMap<TextureId, TextureHandle>,
     mesh_renderer: MeshRenderer,
     current_events: Vec<Event>,
     size: (usize, usize),
@@ -52,26 +52,15 @@ pub struct EguiBackend {
 const RENDER_FLAGS: [GlRenderFlags; 1] = [GlRenderFlags::Blend];
 
 impl EguiBackend {
-    pub fn new(gl: &glow::Context) -> Self {
-        let program = unsafe {
-            shader_def!(
-                "egui.vert",
-                "egui.frag",
-                vec!(
-                    (VertexAttrType::Position, "position"),
-                    (VertexAttrType::UVs, "uv"),
-                    (VertexAttrType::Color, "color"),
-                ),
-                vec!((UniformTypes::ProjMatrix, "u_ortho"),)
-            )
-            .compile(gl)
-        }
-        .expect("Cant compile eguis shaders");
-        let program = Rc::new(program);
+    pub fn new(gl: &GlContext) -> Self {
+        let program = shader_def!("egui.vert", "egui.frag", vec![])
+            .compile_shared(gl)
+            .expect("Cant compile eguis shaders");
         Self {
             egui_ctx: egui::Context::default(),
             // egui_once: true,
-            mesh_renderer: MeshRenderer::with_render_flags(&RENDER_FLAGS, &program),
+            mesh_renderer: MeshRenderer::with_render_flags(&RENDER_FLAGS, Material::new(&program))
+                .with_memory_category(GpuMemoryCategory::Egui),
             textures: HashMap::new(),
             current_events: Vec::new(),
             size: (800, 600),
@@ -79,7 +68,7 @@ impl EguiBackend {
         }
     }
 
-    pub fn render_ui<F>(&mut self, gl: &glow::Context, mut build_gui: F)
+    pub fn render_ui<F>(&mut self, gl: &GlContext, mut build_gui: F)
     where
         F: FnMut(&egui::Context),
     {
@@ -100,7 +89,7 @@ impl EguiBackend {
 
     fn paint(
         &mut self,
-        gl: &glow::Context,
+        gl: &GlContext,
         textures_delta: egui::TexturesDelta,
         clipped_primitives: Vec<egui::ClippedPrimitive>,
     ) {
@@ -109,31 +98,27 @@ impl EguiBackend {
         for primitive in &clipped_primitives {
             match &primitive.primitive {
                 Primitive::Mesh(p) => {
-                    unsafe {
-                        let tex = self.textures.get(&p.texture_id);
-                        match tex {
-                            Some(t) => gl.bind_texture(glow::TEXTURE_2D, Some(*t)),
-                            None => {
-                                warn!("Texture not found: {:?}", p.texture_id);
-                                continue;
-                            }
+                    let tex = self.textures.get(&p.texture_id);
+                    match tex {
+                        Some(t) => gl.set_texture(0, glow::TEXTURE_2D, Some(t.key())),
+                        None => {
+                            warn!("Texture not found: {:?}", p.texture_id);
+                            continue;
                         }
                     }
                     let mesh = Rc::new(Mesh::from(p));
                     self.mesh_renderer
                         .set_mesh(gl, mesh)
                         .expect("Can't set egui mesh");
-                    let program = self.mesh_renderer.get_program();
-                    program.gl_use(gl);
-                    program.set_matrix(
-                        gl,
-                        UniformTypes::ProjMatrix,
-                        &self.make_projection(800.0, 600.0),
-                    );
+                    let projection = UniformValue::Mat4(self.make_projection(800.0, 600.0));
+                    self.mesh_renderer
+                        .get_material_mut()
+                        .set_uniform("u_ortho", projection)
+                        .expect("Can't set egui projection");
                     unsafe {
-                        gl.enable(glow::BLEND);
+                        gl.set_flag(GlRenderFlags::Blend, true);
                         gl.blend_equation_separate(glow::FUNC_ADD, glow::FUNC_ADD);
-                        gl.blend_func_separate(
+                        gl.set_blend_func_separate(
                             // egui outputs colors with premultiplied alpha:
                             glow::ONE,
                             glow::ONE_MINUS_SRC_ALPHA,
@@ -150,13 +135,17 @@ impl EguiBackend {
                 }
             }
         }
+        // textures egui is done with, only after drawing as this frame may still use them
+        for id in &textures_delta.free {
+            self.textures.remove(id);
+        }
     }
 
     fn make_projection(&self, width: f32, height: f32) -> glam::Mat4 {
         glam::Mat4::orthographic_rh_gl(0.0, width, height, 0.0, -1.0, 1.0)
     }
 
-    fn update_textures(&mut self, gl: &glow::Context, textures_delta: &egui::TexturesDelta) {
+    fn update_textures(&mut self, gl: &GlContext, textures_delta: &egui::TexturesDelta) {
         unsafe {
             for (id, img_delta) in &textures_delta.set {
                 if !img_delta.is_whole() {
@@ -165,8 +154,8 @@ impl EguiBackend {
                 }
                 info!("loading texture: {:?}", id);
                 let options = &img_delta.options;
-                let key: WebTextureKey = gl.create_texture().expect("Can't create texture");
-                gl.bind_texture(glow::TEXTURE_2D, Some(key));
+                let key = gl.new_texture().expect("Can't create texture");
+                gl.set_texture(0, glow::TEXTURE_2D, Some(key.key()));
                 let wrap_mode = wrap_to_glow(options.wrap_mode);
                 gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, wrap_mode as i32);
                 gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, wrap_mode as i32);
@@ -195,7 +184,6 @@ impl EguiBackend {
                             glow::UNSIGNED_BYTE,
                             Some(data),
                         );
-                        self.textures.insert(*id, key);
                     }
                     egui::ImageData::Font(image) => {
                         gl.tex_image_2d(
@@ -216,6 +204,8 @@ impl EguiBackend {
                         );
                     }
                 };
+                let [width, height] = img_delta.image.size();
+                key.set_size(GpuMemoryCategory::Egui, width * height * 4);
                 self.textures.insert(*id, key);
             }
         }
@@ -292,31 +282,31 @@ impl TryInto<Event> for &InputEventType {
     fn try_into(self) -> Result<Event, Self::Error> {
         let event = match self {
             InputEventType::KeyDown(event) => Event::Key {
-                key: try_parse_key(event.key())?,
+                key: try_parse_key(event.key.clone())?,
                 pressed: true,
-                modifiers: get_key_modifiers(&event),
+                modifiers: to_egui_modifiers(event.modifiers),
                 physical_key: None,
-                repeat: event.repeat(),
+                repeat: event.repeat,
             },
             InputEventType::KeyUp(event) => Event::Key {
-                key: try_parse_key(event.key())?,
+                key: try_parse_key(event.key.clone())?,
                 pressed: false,
-                modifiers: get_key_modifiers(&event),
+                modifiers: to_egui_modifiers(event.modifiers),
                 physical_key: None,
-                repeat: event.repeat(),
+                repeat: event.repeat,
             },
             InputEventType::MouseMove(event) => Event::PointerMoved(mouse_event_to_pos2(&event)),
             InputEventType::MouseDown(event) => Event::PointerButton {
                 pos: mouse_event_to_pos2(&event),
-                button: try_parse_mouse_button(event.button())?,
+                button: try_parse_mouse_button(event.button)?,
                 pressed: true,
-                modifiers: get_mouse_modifiers(&event),
+                modifiers: to_egui_modifiers(event.modifiers),
             },
             InputEventType::MouseUp(event) => Event::PointerButton {
                 pos: mouse_event_to_pos2(&event),
-                button: try_parse_mouse_button(event.button())?,
+                button: try_parse_mouse_button(event.button)?,
                 pressed: false,
-                modifiers: get_mouse_modifiers(&event),
+                modifiers: to_egui_modifiers(event.modifiers),
             },
             _ => Err(ConversionError::Unhandled)?,
         };
@@ -324,24 +314,14 @@ impl TryInto<Event> for &InputEventType {
     }
 }
 
-fn get_key_modifiers(event: &KeyboardEvent) -> egui::Modifiers {
-    let mut modifiers = egui::Modifiers::default();
-    modifiers.shift = event.get_modifier_state("Shift");
-    modifiers.ctrl = event.get_modifier_state("Control");
-    modifiers.alt = event.get_modifier_state("Alt");
-    modifiers.command = event.get_modifier_state("Meta");
+fn to_egui_modifiers(modifiers: Modifiers) -> egui::Modifiers {
+    let mut egui_modifiers = egui::Modifiers::default();
+    egui_modifiers.shift = modifiers.shift;
+    egui_modifiers.ctrl = modifiers.ctrl;
+    egui_modifiers.alt = modifiers.alt;
+    egui_modifiers.command = modifiers.meta;
 
-    modifiers
-}
-
-fn get_mouse_modifiers(event: &MouseEvent) -> egui::Modifiers {
-    let mut modifiers = egui::Modifiers::default();
-    modifiers.shift = event.get_modifier_state("Shift");
-    modifiers.ctrl = event.get_modifier_state("Control");
-    modifiers.alt = event.get_modifier_state("Alt");
-    modifiers.command = event.get_modifier_state("Meta");
-
-    modifiers
+    egui_modifiers
 }
 
 fn try_parse_mouse_button(button: i16) -> Result<egui::PointerButton, ConversionError> {
@@ -362,7 +342,7 @@ fn try_parse_key(key: String) -> Result<egui::Key, ConversionError> {
 }
 
 fn mouse_event_to_pos2(event: &MouseEvent) -> egui::Pos2 {
-    egui::pos2(event.client_x() as f32, event.client_y() as f32)
+    egui::pos2(event.position.x, event.position.y)
 }
 
 /* convert a TextureFilter to a glow filter */
@@ -384,24 +364,14 @@ fn wrap_to_glow(wrap: TextureWrapMode) -> u32 {
 
 impl From<&egui::Mesh> for Mesh {
     fn from(mesh: &egui::Mesh) -> Self {
-        let mut data = Vec::<f32>::with_capacity(mesh.vertices.len() * 8);
-        for v in &mesh.vertices {
-            data.push(v.pos.x);
-            data.push(v.pos.y);
-            data.push(v.uv.x);
-            data.push(v.uv.y);
-            data.push(v.color.r() as f32 / 255.0);
-            data.push(v.color.g() as f32 / 255.0);
-            data.push(v.color.b() as f32 / 255.0);
-            data.push(v.color.a() as f32 / 255.0);
-        }
+        // egui vertices are already interleaved this way, colors being 4 bytes
         Mesh {
-            data,
+            data: bytemuck::cast_slice(&mesh.vertices).to_vec(),
             primitive_type: glow::TRIANGLES,
             layout: vec![
-                (VertexAttrType::Position, 2),
-                (VertexAttrType::UVs, 2),
-                (VertexAttrType::Color, 4),
+                VertexAttribute::f32(VertexAttrType::Position, 2),
+                VertexAttribute::f32(VertexAttrType::UVs, 2),
+                VertexAttribute::normalized(VertexAttrType::Color, 4, VertexDataType::U8),
             ],
             indices: Some(mesh.indices.clone()),
         }
@@ -3,15 +3,15 @@ use std::{cell::RefCell, rc::Rc};
 use cfg_if::cfg_if;
 use wasm_bindgen::prelude::*;
 
-use crate::core::Game;
+use crate::{core::Game, utils::GlContext};
 
-mod core;
-mod graphics;
+pub mod core;
+pub mod graphics;
 mod gui;
-pub mod math;
+pub use engine_core::math;
 mod objects;
-mod utils;
-mod world;
+pub mod utils;
+pub mod world;
 
 cfg_if! {
     if #[cfg(feature = "console_log")] {
@@ -40,7 +40,7 @@ fn start() -> Result<(), JsValue> {
 
     init_log();
 
-    let gl = get_webgl2_context()?;
+    let gl = GlContext::new(get_webgl2_context()?);
 
     let mut game = Game::new()?;
 
@@ -53,7 +53,7 @@ fn start() -> Result<(), JsValue> {
     Ok(())
 }
 
-fn main_loop(game: Game, gl: glow::Context) -> Result<(), JsValue> {
+fn main_loop(game: Game, gl: GlContext) -> Result<(), JsValue> {
     let context = Rc::new(RefCell::new(gl));
     let update: Rc<RefCell<Option<Closure<dyn FnMut(f64) -> Result<(), JsValue>>>>> =
         Rc::new(RefCell::new(None));
@@ -64,6 +64,7 @@ fn main_loop(game: Game, gl: glow::Context) -> Result<(), JsValue> {
     *request_update.borrow_mut() = Some(Closure::new(move |time| {
         let gl = &context.borrow();
         game.borrow_mut().tick(gl, time)?;
+        gl.end_frame();
 
         // Request the next animation frame.
         request_animation_frame(update.borrow().as_ref().unwrap());
@@ -0,0 +1,93 @@
+use std::rc::Rc;
+
+use glam::{Mat4, Vec3, Vec4};
+
+use crate::{
+    graphics::{
+        InstancedRenderer, Material, Mesh, MeshRenderer, ProgramRef, ShaderDef, VertexAttrType,
+        VertexAttribute, FRAME_DATA_BLOCK, INSTANCE_TRANSFORM,
+    },
+    shader_def,
+    utils::{GlContext, GlRenderFlags},
+};
+
+const DEBUG_BOX_GL_PARAMS: [GlRenderFlags; 1] = [GlRenderFlags::DepthTest];
+
+/* color then transform, see BoxInstance */
+const INSTANCE_LAYOUT: [VertexAttribute; 2] = [
+    VertexAttribute::f32(VertexAttrType::InstanceColor, 4),
+    INSTANCE_TRANSFORM,
+];
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct BoxInstance {
+    color: [f32; 4],
+    transform: [f32; 16],
+}
+
+unsafe impl bytemuck::Zeroable for BoxInstance {}
+unsafe impl bytemuck::Pod for BoxInstance {}
+
+/* wire boxes, all drawn in a single instanced call. They are added again every frame */
+#[derive(Debug, Default)]
+pub struct DebugBoxes {
+    renderer: Option<InstancedRenderer>,
+    boxes: Vec<(Vec3, Vec3, Vec4)>,
+}
+
+impl DebugBoxes {
+    pub fn add(&mut self, min: Vec3, size: Vec3, color: Vec4) {
+        self.boxes.push((min, size, color));
+    }
+
+    /* draws the boxes added since the last call */
+    pub fn render(&mut self, gl: &GlContext) -> Result<(), String> {
+        if self.renderer.is_none() {
+            let renderer = MeshRenderer::with_render_flags(
+                &DEBUG_BOX_GL_PARAMS,
+                Material::new(&compile_shader(gl)?),
+            );
+            let mesh = Mesh::from_f32(&BOX_EDGES, &[(VertexAttrType::Position, 3)], glow::LINES);
+            let instance_layout = INSTANCE_LAYOUT.to_vec();
+            let renderer = InstancedRenderer::new(gl, renderer, Rc::new(mesh), instance_layout)?;
+            self.renderer = Some(renderer);
+        }
+        let instances: Vec<BoxInstance> = self
+            .boxes
+            .drain(..)
+            .map(|(min, size, color)| BoxInstance {
+                color: color.to_array(),
+                transform: (Mat4::from_translation(min) * Mat4::from_scale(size)).to_cols_array(),
+            })
+            .collect();
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_instances(gl, &instances)?;
+            renderer.render(gl);
+        }
+        Ok(())
+    }
+}
+
+fn compile_shader(gl: &GlContext) -> Result<ProgramRef, String> {
+    shader_def!("debug_box.vert", "debug_box.frag", vec![])
+        .with_uniform_blocks(vec![FRAME_DATA_BLOCK])
+        .compile_shared(gl)
+}
+
+/* the 12 edges of the unit cube */
+#[rustfmt::skip]
+const BOX_EDGES: [f32; 72] = [
+    0.0, 0.0, 0.0,  1.0, 0.0, 0.0,
+    0.0, 1.0, 0.0,  1.0, 1.0, 0.0,
+    0.0, 0.0, 1.0,  1.0, 0.0, 1.0,
+    0.0, 1.0, 1.0,  1.0, 1.0, 1.0,
+    0.0, 0.0, 0.0,  0.0, 1.0, 0.0,
+    1.0, 0.0, 0.0,  1.0, 1.0, 0.0,
+    0.0, 0.0, 1.0,  0.0, 1.0, 1.0,
+    1.0, 0.0, 1.0,  1.0, 1.0, 1.0,
+    0.0, 0.0, 0.0,  0.0, 0.0, 1.0,
+    1.0, 0.0, 0.0,  1.0, 0.0, 1.0,
+    0.0, 1.0, 0.0,  0.0, 1.0, 1.0,
+    1.0, 1.0, 0.0,  1.0, 1.0, 1.0,
+];
@@ -4,16 +4,14 @@ use glam::IVec3;
 use glam::Mat4;
 use glam::Quat;
 use glam::Vec3;
-use glow::HasContext;
-use glow::WebTextureKey;
 use log::info;
 
 use crate::core::Time;
-use crate::graphics::Camera;
+use crate::graphics::Material;
 use crate::graphics::MeshRenderer;
-use crate::graphics::TextureDef;
-use crate::graphics::TextureType;
-use crate::graphics::UniformTypes;
+use crate::graphics::ShadowMap;
+use crate::graphics::UniformValue;
+use crate::utils::GlContext;
 use crate::world::CHUNK_SIZE;
 
 pub trait Transform {
@@ -42,21 +40,22 @@ pub struct GameObject {
     scale: Vec3,
     velocity: Vec3,
     renderer: Rc<MeshRenderer>,
+    /* the renderer material with the model matrix of this object */
+    material: Material,
 
     transform: Mat4,
     transform_dirty: bool,
-    texture: Rc<TextureDef>,
 }
 
 impl GameObject {
-    pub fn new(texture: &Rc<TextureDef>, renderer: &Rc<MeshRenderer>) -> Self {
+    pub fn new(renderer: &Rc<MeshRenderer>) -> Self {
         GameObject {
             position: Vec3::ZERO,
             rotation: Quat::IDENTITY,
             scale: Vec3::ONE,
-            texture: texture.clone(),
             transform: Mat4::IDENTITY,
             renderer: renderer.clone(),
+            material: renderer.get_material().clone(),
             transform_dirty: true,
             velocity: Vec3::ZERO,
         }
@@ -77,28 +76,19 @@ impl GameObject {
         &self.renderer
     }
 
-    pub fn render(&self, gl: &glow::Context, camera: &Camera) {
-        unsafe {
-            let program = self.renderer.get_program();
-            program.gl_use(gl);
-
-            program.set_matrix(gl, UniformTypes::ViewMatrix, &camera.look_at);
-            program.set_matrix(gl, UniformTypes::ProjMatrix, &camera.projection);
-            program.set_matrix(gl, UniformTypes::ModelMatrix, &self.transform);
-
-            let (tex_type, key) = *self.texture;
-            if key != WebTextureKey::default() {
-                let texture = Some(key);
-                match tex_type {
-                    TextureType::Texture2D => gl.bind_texture(glow::TEXTURE_2D, texture),
-                    TextureType::Texture2DArray(_depth) => {
-                        gl.bind_texture(glow::TEXTURE_2D_ARRAY, texture)
-                    }
-                };
-            }
-
-            self.renderer.render(gl);
-        }
+    pub fn render(&mut self, gl: &GlContext) -> Result<(), String> {
+        let model = UniformValue::Mat4(self.transform);
+        self.material.set_uniform("model", model)?;
+        self.renderer.render_with(gl, &self.material);
+        Ok(())
+    }
+
+    pub fn render_shadow(&self, gl: &GlContext, shadow_map: &ShadowMap) -> Result<(), String> {
+        let model = UniformValue::Mat4(self.transform);
+        let material = shadow_map.get_object_material().clone();
+        material.with_uniform("model", model)?.apply(gl);
+        self.renderer.draw(gl);
+        Ok(())
     }
 }
 
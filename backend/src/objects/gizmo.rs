@@ -7,10 +7,12 @@ use super::{GameObject, LazyRenderGameObject, MakeRenderer};
 
 use crate::{
     core::Time,
-    graphics::{Mesh, MeshRenderer, ShaderDef, ShaderProgram, UniformTypes, VertexAttrType},
+    graphics::{
+        Material, Mesh, MeshRenderer, ProgramRef, ShaderDef, VertexAttrType, FRAME_DATA_BLOCK,
+    },
     objects::gameobject::Transform,
     shader_def,
-    utils::GlRenderFlags,
+    utils::{GlContext, GlRenderFlags},
 };
 
 #[derive(Debug)]
@@ -20,15 +22,12 @@ pub struct CreateGizmoRenderer {
 }
 
 impl MakeRenderer for CreateGizmoRenderer {
-    fn make_renderer(&self, gl: &glow::Context) -> Result<Rc<MeshRenderer>, String> {
+    fn make_renderer(&self, gl: &GlContext) -> Result<Rc<MeshRenderer>, String> {
         let program = compile_shader(gl)?;
-        let mut renderer = MeshRenderer::with_render_flags(&GIZMO_GL_PARAMS, &program);
-        let mesh = Mesh {
-            data: GIZMO_VERTICES.to_vec(),
-            layout: vec![(VertexAttrType::Position, 3), (VertexAttrType::Color, 3)],
-            indices: None,
-            primitive_type: glow::LINES,
-        };
+        let mut renderer =
+            MeshRenderer::with_render_flags(&GIZMO_GL_PARAMS, Material::new(&program));
+        let layout = [(VertexAttrType::Position, 3), (VertexAttrType::Color, 3)];
+        let mesh = Mesh::from_f32(&GIZMO_VERTICES, &layout, glow::LINES);
         renderer.set_mesh(gl, Rc::new(mesh))?;
         Ok(Rc::new(renderer))
     }
@@ -68,22 +67,8 @@ const GIZMO_VERTICES: [f32; 36] = [
     0.0, 0.0, 1.0, 0.0, 0.0, 1.0, //
 ];
 
-fn compile_shader(gl: &glow::Context) -> Result<Rc<ShaderProgram>, String> {
-    unsafe {
-        let program = shader_def!(
-            "gizmo.vert",
-            "gizmo.frag",
-            vec!(
-                (VertexAttrType::Position, "position"),
-                (VertexAttrType::Color, "color")
-            ),
-            vec!(
-                (UniformTypes::ModelMatrix, "model"),
-                (UniformTypes::ViewMatrix, "view"),
-                (UniformTypes::ProjMatrix, "projection"),
-            )
-        )
-        .compile(gl)?;
-        Ok(Rc::new(program))
-    }
+fn compile_shader(gl: &GlContext) -> Result<ProgramRef, String> {
+    shader_def!("gizmo.vert", "gizmo.frag", vec![])
+        .with_uniform_blocks(vec![FRAME_DATA_BLOCK])
+        .compile_shared(gl)
 }
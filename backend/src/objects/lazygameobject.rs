@@ -1,18 +1,13 @@
 use std::rc::Rc;
 
-use glow::HasContext;
 use log::info;
 
-use crate::{
-    core::Time,
-    graphics::{Camera, MeshRenderer},
-    utils::GlState,
-};
+use crate::{core::Time, graphics::MeshRenderer, utils::GlContext};
 
 use super::GameObject;
 
 pub trait MakeRenderer {
-    fn make_renderer(&self, gl: &glow::Context) -> Result<Rc<MeshRenderer>, String>;
+    fn make_renderer(&self, gl: &GlContext) -> Result<Rc<MeshRenderer>, String>;
     fn init_gameobject(&self, _gameobject: &mut GameObject) {}
 }
 
@@ -33,19 +28,19 @@ where
         self.gameobject.as_ref()
     }
 
-    pub fn render_lazy(&mut self, gl: &glow::Context, camera: &Camera) {
+    pub fn render_lazy(&mut self, gl: &GlContext) -> Result<(), String> {
         if self.gameobject.is_none() {
             self.load(gl);
         }
-        self.gameobject.as_ref().unwrap().render(gl, camera);
+        self.gameobject.as_mut().unwrap().render(gl)
     }
 
-    pub fn load(&mut self, gl: &glow::Context) {
+    pub fn load(&mut self, gl: &GlContext) {
         if self.gameobject.is_some() {
             return;
         }
         let renderer = self.renderer_creator.make_renderer(gl).unwrap();
-        let mut gameobject = GameObject::new(&Rc::new(Default::default()), &renderer);
+        let mut gameobject = GameObject::new(&renderer);
         self.renderer_creator.init_gameobject(&mut gameobject);
         self.gameobject = Some(gameobject);
     }
@@ -1,8 +1,10 @@
+mod debugboxes;
 mod gameobject;
 mod gizmo;
 mod lazygameobject;
 mod player;
 
+pub use debugboxes::*;
 pub use gameobject::*;
 pub use gizmo::Gizmo;
 pub use lazygameobject::*;
@@ -7,9 +7,10 @@ use super::{GameObject, LazyRenderGameObject, MakeRenderer};
 
 use crate::{
     core::HandleInputs,
-    graphics::{MeshRenderer, ShaderDef, ShaderProgram, UniformTypes, VertexAttrType},
+    graphics::{Material, MeshRenderer, ProgramRef, ShaderDef, FRAME_DATA_BLOCK},
     objects::gameobject::Transform,
-    shader_def, utils,
+    shader_def,
+    utils::{self, GlContext},
 };
 
 const MAX_SPEED: f32 = 10.0;
@@ -21,9 +22,9 @@ pub struct CreatePlayerRenderer {
 }
 
 impl MakeRenderer for CreatePlayerRenderer {
-    fn make_renderer(&self, gl: &glow::Context) -> Result<Rc<MeshRenderer>, String> {
+    fn make_renderer(&self, gl: &GlContext) -> Result<Rc<MeshRenderer>, String> {
         let program = compile_shader(gl)?;
-        let mut renderer = MeshRenderer::new(&program);
+        let mut renderer = MeshRenderer::new(Material::new(&program).with_shadow_map()?);
         let mesh = utils::make_cube();
         renderer.set_mesh(gl, Rc::new(mesh))?;
         Ok(Rc::new(renderer))
@@ -84,24 +85,8 @@ impl HandleInputs for Player {
     }
 }
 
-fn compile_shader(gl: &glow::Context) -> Result<Rc<ShaderProgram>, String> {
-    unsafe {
-        let program = shader_def!(
-            "cube.vert",
-            "cube.frag",
-            vec!(
-                (VertexAttrType::Position, "position"),
-                (VertexAttrType::Normal, "normal"),
-                (VertexAttrType::UVs, "uv"),
-                (VertexAttrType::Depth, "depth"),
-            ),
-            vec!(
-                (UniformTypes::ModelMatrix, "model"),
-                (UniformTypes::ViewMatrix, "view"),
-                (UniformTypes::ProjMatrix, "projection"),
-            )
-        )
-        .compile(gl)?;
-        Ok(Rc::new(program))
-    }
+fn compile_shader(gl: &GlContext) -> Result<ProgramRef, String> {
+    shader_def!("cube.vert", "cube.frag", vec![])
+        .with_uniform_blocks(vec![FRAME_DATA_BLOCK])
+        .compile_shared(gl)
 }
@@ -4,10 +4,13 @@
 use glam::Vec3;
 
 use crate::{
-    graphics::{uv_definitions, Mesh, Side, SideVertices, VertexAttrType},
-    world::BlockSideTexture,
+    graphics::{uv_definitions, Mesh, SideVertices, VertexAttrType},
+    world::{BlockSideTexture, Side},
 };
 
+const QUAD_LAYOUT: [(VertexAttrType, usize); 2] =
+    [(VertexAttrType::Position, 3), (VertexAttrType::UVs, 2)];
+
 pub fn make_quad() -> Mesh {
     let verts = [
         (A, uv_definitions::TOP_LEFT),
@@ -23,12 +26,7 @@ pub fn make_quad() -> Mesh {
         data.push(uv.0 as _);
         data.push(uv.1 as _);
     }
-    Mesh {
-        data,
-        indices: None,
-        layout: vec![(VertexAttrType::Position, 3), (VertexAttrType::UVs, 2)],
-        primitive_type: glow::TRIANGLE_STRIP,
-    }
+    Mesh::from_f32(&data, &QUAD_LAYOUT, glow::TRIANGLE_STRIP)
 }
 
 // . .
@@ -50,12 +48,7 @@ pub fn make_quad_triangles() -> Mesh {
         data.push(uv.0 as _);
         data.push(uv.1 as _);
     }
-    Mesh {
-        data,
-        indices: None,
-        layout: vec![(VertexAttrType::Position, 3), (VertexAttrType::UVs, 2)],
-        primitive_type: glow::TRIANGLES,
-    }
+    Mesh::from_f32(&data, &QUAD_LAYOUT, glow::TRIANGLES)
 }
 
 pub fn make_quad_elements() -> Mesh {
@@ -73,12 +66,7 @@ pub fn make_quad_elements() -> Mesh {
         data.push(uv.0 as _);
         data.push(uv.1 as _);
     }
-    Mesh {
-        data,
-        indices: Some(vec![0, 1, 2, 1, 3, 2]),
-        layout: vec![(VertexAttrType::Position, 3), (VertexAttrType::UVs, 2)],
-        primitive_type: glow::TRIANGLES,
-    }
+    Mesh::from_f32(&data, &QUAD_LAYOUT, glow::TRIANGLES).with_indices(vec![0, 1, 2, 1, 3, 2])
 }
 
 pub fn make_cube() -> Mesh {
@@ -0,0 +1,49 @@
+/* reads a file the game ships with and hands its bytes to `on_done`. The path is relative to
+ * the page on the web, where the file is fetched in the background, and to the working
+ * directory natively, where it is read before returning */
+pub fn fetch_bytes(path: &str, on_done: impl FnOnce(Result<Vec<u8>, String>) + 'static) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let path = path.to_string();
+        wasm_bindgen_futures::spawn_local(async move {
+            let bytes = web::fetch_bytes(&path)
+                .await
+                .map_err(|e| format!("Can't fetch {path}: {e:?}"));
+            on_done(bytes);
+        });
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    on_done(std::fs::read(path).map_err(|e| format!("Can't read {path}: {e}")));
+}
+
+/* same as fetch_bytes, for UTF-8 text files */
+pub fn fetch_text(path: &str, on_done: impl FnOnce(Result<String, String>) + 'static) {
+    let name = path.to_string();
+    fetch_bytes(path, move |bytes| {
+        on_done(bytes.and_then(|bytes| {
+            String::from_utf8(bytes).map_err(|_| format!("{name} isn't UTF-8 text"))
+        }))
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use js_sys::Uint8Array;
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::Response;
+
+    use crate::utils::get_window;
+
+    pub async fn fetch_bytes(url: &str) -> Result<Vec<u8>, JsValue> {
+        let window = get_window()?;
+        let response: Response = JsFuture::from(window.fetch_with_str(url))
+            .await?
+            .dyn_into()?;
+        if !response.ok() {
+            return Err(format!("{url}: {}", response.status()).into());
+        }
+        let buffer = JsFuture::from(response.array_buffer()?).await?;
+        Ok(Uint8Array::new(&buffer).to_vec())
+    }
+}
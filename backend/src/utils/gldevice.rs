@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+
+use glow::{
+    ActiveAttribute, ActiveUniform, Buffer, Framebuffer, HasContext, Program, Shader, Texture,
+    UniformLocation, VertexArray,
+};
+
+/* declares GlDevice along with its implementation for glow, the calls listed under `forward`
+ * going straight to the glow method of the same name */
+macro_rules! gl_device {
+    (
+        $(#[$attr:meta])*
+        pub trait GlDevice {
+            $($trait_item:tt)*
+        }
+        impl GlDevice for glow::Context {
+            $($impl_item:tt)*
+        }
+        forward {
+            $(unsafe fn $name:ident(&self $(, $arg:ident: $arg_type:ty)* $(,)?) $(-> $ret:ty)?;)*
+        }
+    ) => {
+        $(#[$attr])*
+        pub trait GlDevice {
+            $($trait_item)*
+            $(unsafe fn $name(&self $(, $arg: $arg_type)*) $(-> $ret)?;)*
+        }
+
+        impl GlDevice for glow::Context {
+            $($impl_item)*
+            $(unsafe fn $name(&self $(, $arg: $arg_type)*) $(-> $ret)? {
+                HasContext::$name(self $(, $arg)*)
+            })*
+        }
+    };
+}
+
+gl_device! {
+    /* the GL calls the renderer makes, with the same signatures as in glow. Implemented by
+     * glow::Context and by RecordingDevice, which runs without a GPU */
+    #[allow(clippy::missing_safety_doc, clippy::too_many_arguments)]
+    pub trait GlDevice {
+        fn supported_extensions(&self) -> &HashSet<String>;
+    }
+
+    impl GlDevice for glow::Context {
+        fn supported_extensions(&self) -> &HashSet<String> {
+            HasContext::supported_extensions(self)
+        }
+    }
+
+    forward {
+        unsafe fn active_texture(&self, unit: u32);
+        unsafe fn attach_shader(&self, program: Program, shader: Shader);
+        unsafe fn bind_attrib_location(&self, program: Program, index: u32, name: &str);
+        unsafe fn bind_buffer(&self, target: u32, buffer: Option<Buffer>);
+        unsafe fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<Buffer>);
+        unsafe fn bind_framebuffer(&self, target: u32, framebuffer: Option<Framebuffer>);
+        unsafe fn bind_texture(&self, target: u32, texture: Option<Texture>);
+        unsafe fn bind_vertex_array(&self, vertex_array: Option<VertexArray>);
+        unsafe fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32);
+        unsafe fn blend_func_separate(
+            &self,
+            src_rgb: u32,
+            dst_rgb: u32,
+            src_alpha: u32,
+            dst_alpha: u32,
+        );
+        unsafe fn buffer_data_size(&self, target: u32, size: i32, usage: u32);
+        unsafe fn buffer_data_u8_slice(&self, target: u32, data: &[u8], usage: u32);
+        unsafe fn buffer_sub_data_u8_slice(&self, target: u32, offset: i32, src_data: &[u8]);
+        unsafe fn check_framebuffer_status(&self, target: u32) -> u32;
+        unsafe fn clear(&self, mask: u32);
+        unsafe fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32);
+        unsafe fn compile_shader(&self, shader: Shader);
+        unsafe fn create_buffer(&self) -> Result<Buffer, String>;
+        unsafe fn create_framebuffer(&self) -> Result<Framebuffer, String>;
+        unsafe fn create_program(&self) -> Result<Program, String>;
+        unsafe fn create_shader(&self, shader_type: u32) -> Result<Shader, String>;
+        unsafe fn create_texture(&self) -> Result<Texture, String>;
+        unsafe fn create_vertex_array(&self) -> Result<VertexArray, String>;
+        unsafe fn delete_buffer(&self, buffer: Buffer);
+        unsafe fn delete_framebuffer(&self, framebuffer: Framebuffer);
+        unsafe fn delete_program(&self, program: Program);
+        unsafe fn delete_shader(&self, shader: Shader);
+        unsafe fn delete_texture(&self, texture: Texture);
+        unsafe fn delete_vertex_array(&self, vertex_array: VertexArray);
+        unsafe fn depth_func(&self, func: u32);
+        unsafe fn depth_mask(&self, value: bool);
+        unsafe fn disable(&self, parameter: u32);
+        unsafe fn draw_arrays(&self, mode: u32, first: i32, count: i32);
+        unsafe fn draw_arrays_instanced(
+            &self,
+            mode: u32,
+            first: i32,
+            count: i32,
+            instance_count: i32,
+        );
+        unsafe fn draw_buffers(&self, buffers: &[u32]);
+        unsafe fn draw_elements(&self, mode: u32, count: i32, element_type: u32, offset: i32);
+        unsafe fn draw_elements_instanced(
+            &self,
+            mode: u32,
+            count: i32,
+            element_type: u32,
+            offset: i32,
+            instance_count: i32,
+        );
+        unsafe fn enable(&self, parameter: u32);
+        unsafe fn enable_vertex_attrib_array(&self, index: u32);
+        unsafe fn framebuffer_texture_2d(
+            &self,
+            target: u32,
+            attachment: u32,
+            texture_target: u32,
+            texture: Option<Texture>,
+            level: i32,
+        );
+        unsafe fn generate_mipmap(&self, target: u32);
+        unsafe fn get_active_attribute(
+            &self,
+            program: Program,
+            index: u32,
+        ) -> Option<ActiveAttribute>;
+        unsafe fn get_active_attributes(&self, program: Program) -> u32;
+        unsafe fn get_active_uniform(&self, program: Program, index: u32) -> Option<ActiveUniform>;
+        unsafe fn get_active_uniforms(&self, program: Program) -> u32;
+        unsafe fn get_attrib_location(&self, program: Program, name: &str) -> Option<u32>;
+        unsafe fn get_parameter_i32_slice(&self, parameter: u32, out: &mut [i32]);
+        unsafe fn get_program_info_log(&self, program: Program) -> String;
+        unsafe fn get_program_link_status(&self, program: Program) -> bool;
+        unsafe fn get_shader_compile_status(&self, shader: Shader) -> bool;
+        unsafe fn get_shader_info_log(&self, shader: Shader) -> String;
+        unsafe fn get_uniform_block_index(&self, program: Program, name: &str) -> Option<u32>;
+        unsafe fn get_uniform_location(
+            &self,
+            program: Program,
+            name: &str,
+        ) -> Option<UniformLocation>;
+        unsafe fn link_program(&self, program: Program);
+        unsafe fn polygon_offset(&self, factor: f32, units: f32);
+        unsafe fn read_buffer(&self, src: u32);
+        unsafe fn shader_source(&self, shader: Shader, source: &str);
+        unsafe fn tex_image_2d(
+            &self,
+            target: u32,
+            level: i32,
+            internal_format: i32,
+            width: i32,
+            height: i32,
+            border: i32,
+            format: u32,
+            ty: u32,
+            pixels: Option<&[u8]>,
+        );
+        unsafe fn tex_image_3d(
+            &self,
+            target: u32,
+            level: i32,
+            internal_format: i32,
+            width: i32,
+            height: i32,
+            depth: i32,
+            border: i32,
+            format: u32,
+            ty: u32,
+            pixels: Option<&[u8]>,
+        );
+        unsafe fn tex_parameter_i32(&self, target: u32, parameter: u32, value: i32);
+        unsafe fn tex_storage_2d(
+            &self,
+            target: u32,
+            levels: i32,
+            internal_format: u32,
+            width: i32,
+            height: i32,
+        );
+        unsafe fn uniform_1_f32(&self, location: Option<&UniformLocation>, x: f32);
+        unsafe fn uniform_1_i32(&self, location: Option<&UniformLocation>, x: i32);
+        unsafe fn uniform_2_f32(&self, location: Option<&UniformLocation>, x: f32, y: f32);
+        unsafe fn uniform_3_f32(&self, location: Option<&UniformLocation>, x: f32, y: f32, z: f32);
+        unsafe fn uniform_4_f32(
+            &self,
+            location: Option<&UniformLocation>,
+            x: f32,
+            y: f32,
+            z: f32,
+            w: f32,
+        );
+        unsafe fn uniform_block_binding(&self, program: Program, index: u32, binding: u32);
+        unsafe fn uniform_matrix_3_f32_slice(
+            &self,
+            location: Option<&UniformLocation>,
+            transpose: bool,
+            v: &[f32],
+        );
+        unsafe fn uniform_matrix_4_f32_slice(
+            &self,
+            location: Option<&UniformLocation>,
+            transpose: bool,
+            v: &[f32],
+        );
+        unsafe fn use_program(&self, program: Option<Program>);
+        unsafe fn vertex_attrib_divisor(&self, index: u32, divisor: u32);
+        unsafe fn vertex_attrib_pointer_f32(
+            &self,
+            index: u32,
+            size: i32,
+            data_type: u32,
+            normalized: bool,
+            stride: i32,
+            offset: i32,
+        );
+        unsafe fn vertex_attrib_pointer_i32(
+            &self,
+            index: u32,
+            size: i32,
+            data_type: u32,
+            stride: i32,
+            offset: i32,
+        );
+        unsafe fn viewport(&self, x: i32, y: i32, width: i32, height: i32);
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    rc::Rc,
+};
+
+use glow::{Buffer, Framebuffer, Program, Texture, VertexArray};
+
+/* a GL object of any of the types handed out as handles */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GlObject {
+    Buffer(Buffer),
+    VertexArray(VertexArray),
+    Texture(Texture),
+    Program(Program),
+    Framebuffer(Framebuffer),
+}
+
+/* the type of object a handle holds, as a marker type so handles of different types can't be
+ * mixed up even where the key types are the same */
+pub trait GlObjectType {
+    type Key: Copy + fmt::Debug;
+
+    fn to_object(key: Self::Key) -> GlObject;
+}
+
+#[derive(Debug)]
+pub struct BufferObject;
+#[derive(Debug)]
+pub struct VertexArrayObject;
+#[derive(Debug)]
+pub struct TextureObject;
+#[derive(Debug)]
+pub struct ProgramObject;
+#[derive(Debug)]
+pub struct FramebufferObject;
+
+impl GlObjectType for BufferObject {
+    type Key = Buffer;
+
+    fn to_object(key: Buffer) -> GlObject {
+        GlObject::Buffer(key)
+    }
+}
+
+impl GlObjectType for VertexArrayObject {
+    type Key = VertexArray;
+
+    fn to_object(key: VertexArray) -> GlObject {
+        GlObject::VertexArray(key)
+    }
+}
+
+impl GlObjectType for TextureObject {
+    type Key = Texture;
+
+    fn to_object(key: Texture) -> GlObject {
+        GlObject::Texture(key)
+    }
+}
+
+impl GlObjectType for ProgramObject {
+    type Key = Program;
+
+    fn to_object(key: Program) -> GlObject {
+        GlObject::Program(key)
+    }
+}
+
+impl GlObjectType for FramebufferObject {
+    type Key = Framebuffer;
+
+    fn to_object(key: Framebuffer) -> GlObject {
+        GlObject::Framebuffer(key)
+    }
+}
+
+/* what GPU memory is used for, each having its own total */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuMemoryCategory {
+    ChunkGeometry,
+    Meshes,
+    Textures,
+    Egui,
+}
+
+impl GpuMemoryCategory {
+    pub const ALL: [GpuMemoryCategory; 4] = [
+        GpuMemoryCategory::ChunkGeometry,
+        GpuMemoryCategory::Meshes,
+        GpuMemoryCategory::Textures,
+        GpuMemoryCategory::Egui,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            GpuMemoryCategory::ChunkGeometry => "chunks",
+            GpuMemoryCategory::Meshes => "meshes",
+            GpuMemoryCategory::Textures => "textures",
+            GpuMemoryCategory::Egui => "egui",
+        }
+    }
+}
+
+/* bytes held by the live objects of each category */
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GpuMemoryUsage {
+    bytes: [usize; GpuMemoryCategory::ALL.len()],
+}
+
+impl GpuMemoryUsage {
+    pub fn get(&self, category: GpuMemoryCategory) -> usize {
+        self.bytes[category as usize]
+    }
+
+    pub fn total(&self) -> usize {
+        self.bytes.iter().sum()
+    }
+}
+
+impl fmt::Display for GpuMemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} MB (", self.total() as f32 / 1000000.0)?;
+        for (i, category) in GpuMemoryCategory::ALL.iter().enumerate() {
+            let separator = if i > 0 { ", " } else { "" };
+            let megabytes = self.get(*category) as f32 / 1000000.0;
+            write!(f, "{separator}{} {megabytes:.1}", category.get_name())?;
+        }
+        write!(f, ")")
+    }
+}
+
+/* state shared by the resource manager and every handle */
+#[derive(Debug, Default)]
+struct SharedResources {
+    /* objects whose last handle was dropped, waiting for the end of the frame to be deleted */
+    released: Vec<GlObject>,
+    memory: GpuMemoryUsage,
+}
+
+struct HandleInner<T: GlObjectType> {
+    key: T::Key,
+    /* bytes uploaded to the object, see GlHandle::set_size */
+    size: Cell<Option<(GpuMemoryCategory, usize)>>,
+    shared: Rc<RefCell<SharedResources>>,
+}
+
+impl<T: GlObjectType> HandleInner<T> {
+    fn replace_size(&self, size: Option<(GpuMemoryCategory, usize)>) {
+        let mut shared = self.shared.borrow_mut();
+        if let Some((category, bytes)) = self.size.replace(size) {
+            shared.memory.bytes[category as usize] -= bytes;
+        }
+        if let Some((category, bytes)) = size {
+            shared.memory.bytes[category as usize] += bytes;
+        }
+    }
+}
+
+impl<T: GlObjectType> Drop for HandleInner<T> {
+    fn drop(&mut self) {
+        self.replace_size(None);
+        self.shared
+            .borrow_mut()
+            .released
+            .push(T::to_object(self.key));
+    }
+}
+
+/* shared ownership of a GL object, deleted at the end of the frame its last clone is dropped
+ * in. Draws queued earlier in that frame can still use it */
+pub struct GlHandle<T: GlObjectType>(Rc<HandleInner<T>>);
+
+pub type BufferHandle = GlHandle<BufferObject>;
+pub type VertexArrayHandle = GlHandle<VertexArrayObject>;
+pub type TextureHandle = GlHandle<TextureObject>;
+pub type ProgramHandle = GlHandle<ProgramObject>;
+pub type FramebufferHandle = GlHandle<FramebufferObject>;
+
+impl<T: GlObjectType> GlHandle<T> {
+    pub fn key(&self) -> T::Key {
+        self.0.key
+    }
+
+    /* records how many bytes were uploaded to the object, replacing the previous size. They are
+     * counted in GlContext::get_memory_usage until the object is released */
+    pub fn set_size(&self, category: GpuMemoryCategory, bytes: usize) {
+        self.0.replace_size(Some((category, bytes)));
+    }
+}
+
+impl<T: GlObjectType> Clone for GlHandle<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: GlObjectType> fmt::Debug for GlHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GlHandle({:?})", self.0.key)
+    }
+}
+
+/* number of objects of each type created through handles and not deleted yet */
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GlResourceCounts {
+    pub buffers: usize,
+    pub vertex_arrays: usize,
+    pub textures: usize,
+    pub programs: usize,
+    pub framebuffers: usize,
+}
+
+impl GlResourceCounts {
+    fn get_mut(&mut self, object: GlObject) -> &mut usize {
+        match object {
+            GlObject::Buffer(_) => &mut self.buffers,
+            GlObject::VertexArray(_) => &mut self.vertex_arrays,
+            GlObject::Texture(_) => &mut self.textures,
+            GlObject::Program(_) => &mut self.programs,
+            GlObject::Framebuffer(_) => &mut self.framebuffers,
+        }
+    }
+}
+
+impl fmt::Display for GlResourceCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} buffers, {} vertex arrays, {} textures, {} programs, {} framebuffers",
+            self.buffers, self.vertex_arrays, self.textures, self.programs, self.framebuffers
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GlResources {
+    shared: Rc<RefCell<SharedResources>>,
+    live: RefCell<GlResourceCounts>,
+}
+
+impl GlResources {
+    /* wraps a newly created object */
+    pub fn track<T: GlObjectType>(&self, key: T::Key) -> GlHandle<T> {
+        *self.live.borrow_mut().get_mut(T::to_object(key)) += 1;
+        GlHandle(Rc::new(HandleInner {
+            key,
+            size: Cell::new(None),
+            shared: self.shared.clone(),
+        }))
+    }
+
+    /* objects to delete now, counted as gone */
+    pub fn take_released(&self) -> Vec<GlObject> {
+        let released = std::mem::take(&mut self.shared.borrow_mut().released);
+        let mut live = self.live.borrow_mut();
+        for object in released.iter() {
+            *live.get_mut(*object) -= 1;
+        }
+        released
+    }
+
+    pub fn get_counts(&self) -> GlResourceCounts {
+        *self.live.borrow()
+    }
+
+    pub fn get_memory_usage(&self) -> GpuMemoryUsage {
+        self.shared.borrow().memory
+    }
+}
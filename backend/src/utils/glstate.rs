@@ -0,0 +1,228 @@
+use std::{cell::RefCell, collections::HashMap, fmt, ops::Deref};
+
+use glow::{Program, Texture, VertexArray};
+
+use super::{
+    BufferHandle, FramebufferHandle, GlDevice, GlObject, GlResourceCounts, GlResources,
+    GpuMemoryUsage, ProgramHandle, TextureHandle, VertexArrayHandle,
+};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum GlRenderFlags {
+    DepthTest = glow::DEPTH_TEST as isize,
+    CullFace = glow::CULL_FACE as isize,
+    Blend = glow::BLEND as isize,
+    PolygonOffsetFill = glow::POLYGON_OFFSET_FILL as isize,
+}
+
+/* last known value of the GL state the renderer changes the most, None until it's first set */
+#[derive(Debug, Default)]
+pub struct GlState {
+    program: Option<Option<Program>>,
+    vertex_array: Option<Option<VertexArray>>,
+    active_texture_unit: Option<u32>,
+    /* (unit, target) -> texture, units missing being unknown */
+    textures: HashMap<(u32, u32), Option<Texture>>,
+    flags: HashMap<GlRenderFlags, bool>,
+    /* (src rgb, dst rgb, src alpha, dst alpha) */
+    blend_func: Option<(u32, u32, u32, u32)>,
+    depth_func: Option<u32>,
+    stats: GlStateStats,
+}
+
+/* state changes since the last call to GlContext::take_state_stats */
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GlStateStats {
+    pub changes: usize,
+    pub skipped: usize,
+}
+
+impl GlState {
+    /* records the new value, returns whether GL has to be told */
+    fn update<T: PartialEq>(stats: &mut GlStateStats, cached: &mut Option<T>, value: T) -> bool {
+        if cached.as_ref() == Some(&value) {
+            stats.skipped += 1;
+            return false;
+        }
+        *cached = Some(value);
+        stats.changes += 1;
+        true
+    }
+
+    /* a deleted object is unbound by GL, its key may also be reused for a new one */
+    fn forget(&mut self, object: GlObject) {
+        match object {
+            GlObject::Buffer(_) | GlObject::Framebuffer(_) => {}
+            GlObject::VertexArray(key) => {
+                if self.vertex_array == Some(Some(key)) {
+                    self.vertex_array = None;
+                }
+            }
+            GlObject::Texture(key) => self.textures.retain(|_, bound| *bound != Some(key)),
+            GlObject::Program(key) => {
+                if self.program == Some(Some(key)) {
+                    self.program = None;
+                }
+            }
+        }
+    }
+}
+
+/* the GL device along with a cache of its state. The state setters below skip calls that
+ * wouldn't change anything, so bindings and flags must go through them for the cache to stay
+ * right. Buffers, vertex arrays, textures, programs and framebuffers are created as handles
+ * through it too. Everything else is called on the device it derefs to */
+pub struct GlContext {
+    device: Box<dyn GlDevice>,
+    state: RefCell<GlState>,
+    resources: GlResources,
+}
+
+impl fmt::Debug for GlContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlContext")
+            .field("state", &self.state)
+            .field("resources", &self.resources)
+            .finish()
+    }
+}
+
+impl Deref for GlContext {
+    type Target = dyn GlDevice + 'static;
+
+    fn deref(&self) -> &(dyn GlDevice + 'static) {
+        self.device.as_ref()
+    }
+}
+
+impl GlContext {
+    pub fn new(device: impl GlDevice + 'static) -> Self {
+        Self {
+            device: Box::new(device),
+            state: RefCell::new(GlState::default()),
+            resources: GlResources::default(),
+        }
+    }
+
+    pub fn new_buffer(&self) -> Result<BufferHandle, String> {
+        let key = unsafe { self.device.create_buffer()? };
+        Ok(self.resources.track(key))
+    }
+
+    pub fn new_vertex_array(&self) -> Result<VertexArrayHandle, String> {
+        let key = unsafe { self.device.create_vertex_array()? };
+        Ok(self.resources.track(key))
+    }
+
+    pub fn new_texture(&self) -> Result<TextureHandle, String> {
+        let key = unsafe { self.device.create_texture()? };
+        Ok(self.resources.track(key))
+    }
+
+    pub fn new_program(&self) -> Result<ProgramHandle, String> {
+        let key = unsafe { self.device.create_program()? };
+        Ok(self.resources.track(key))
+    }
+
+    pub fn new_framebuffer(&self) -> Result<FramebufferHandle, String> {
+        let key = unsafe { self.device.create_framebuffer()? };
+        Ok(self.resources.track(key))
+    }
+
+    /* deletes the objects released during the frame */
+    pub fn end_frame(&self) {
+        for object in self.resources.take_released() {
+            self.state.borrow_mut().forget(object);
+            unsafe {
+                match object {
+                    GlObject::Buffer(key) => self.device.delete_buffer(key),
+                    GlObject::VertexArray(key) => self.device.delete_vertex_array(key),
+                    GlObject::Texture(key) => self.device.delete_texture(key),
+                    GlObject::Program(key) => self.device.delete_program(key),
+                    GlObject::Framebuffer(key) => self.device.delete_framebuffer(key),
+                }
+            }
+        }
+    }
+
+    /* objects created through handles that are still alive */
+    pub fn get_resource_counts(&self) -> GlResourceCounts {
+        self.resources.get_counts()
+    }
+
+    /* bytes uploaded to the objects still alive, by category */
+    pub fn get_memory_usage(&self) -> GpuMemoryUsage {
+        self.resources.get_memory_usage()
+    }
+
+    pub fn take_state_stats(&self) -> GlStateStats {
+        std::mem::take(&mut self.state.borrow_mut().stats)
+    }
+
+    pub fn set_program(&self, program: Option<Program>) {
+        let state = &mut *self.state.borrow_mut();
+        if GlState::update(&mut state.stats, &mut state.program, program) {
+            unsafe { self.device.use_program(program) };
+        }
+    }
+
+    pub fn set_vertex_array(&self, vertex_array: Option<VertexArray>) {
+        let state = &mut *self.state.borrow_mut();
+        if GlState::update(&mut state.stats, &mut state.vertex_array, vertex_array) {
+            unsafe { self.device.bind_vertex_array(vertex_array) };
+        }
+    }
+
+    /* binds the texture to the given unit, unit 0 being the one to use for uploads */
+    pub fn set_texture(&self, unit: u32, target: u32, texture: Option<Texture>) {
+        let state = &mut *self.state.borrow_mut();
+        let mut cached = state.textures.get(&(unit, target)).copied();
+        if GlState::update(&mut state.stats, &mut cached, texture) {
+            state.textures.insert((unit, target), texture);
+            if GlState::update(&mut state.stats, &mut state.active_texture_unit, unit) {
+                unsafe { self.device.active_texture(glow::TEXTURE0 + unit) };
+            }
+            unsafe { self.device.bind_texture(target, texture) };
+        }
+    }
+
+    pub fn set_flag(&self, flag: GlRenderFlags, value: bool) {
+        let state = &mut *self.state.borrow_mut();
+        let mut cached = state.flags.get(&flag).copied();
+        if GlState::update(&mut state.stats, &mut cached, value) {
+            state.flags.insert(flag, value);
+            match value {
+                true => unsafe { self.device.enable(flag as _) },
+                false => unsafe { self.device.disable(flag as _) },
+            }
+        }
+    }
+
+    pub fn set_blend_func(&self, src: u32, dst: u32) {
+        self.set_blend_func_separate(src, dst, src, dst);
+    }
+
+    pub fn set_blend_func_separate(
+        &self,
+        src_rgb: u32,
+        dst_rgb: u32,
+        src_alpha: u32,
+        dst_alpha: u32,
+    ) {
+        let state = &mut *self.state.borrow_mut();
+        let func = (src_rgb, dst_rgb, src_alpha, dst_alpha);
+        if GlState::update(&mut state.stats, &mut state.blend_func, func) {
+            unsafe {
+                self.device
+                    .blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha)
+            };
+        }
+    }
+
+    pub fn set_depth_func(&self, func: u32) {
+        let state = &mut *self.state.borrow_mut();
+        if GlState::update(&mut state.stats, &mut state.depth_func, func) {
+            unsafe { self.device.depth_func(func) };
+        }
+    }
+}
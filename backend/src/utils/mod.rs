@@ -1,5 +1,17 @@
 mod basicmeshes;
+mod fetch;
+mod gldevice;
+mod glresources;
+mod glstate;
+#[cfg(not(target_arch = "wasm32"))]
+mod recordingdevice;
 mod utils;
 
 pub use self::basicmeshes::*;
+pub use self::fetch::*;
+pub use self::gldevice::*;
+pub use self::glresources::*;
+pub use self::glstate::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::recordingdevice::*;
 pub use self::utils::*;
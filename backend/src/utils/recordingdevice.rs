@@ -0,0 +1,983 @@
+use std::{
+    cell::{RefCell, RefMut},
+    collections::{HashMap, HashSet},
+    num::NonZeroU32,
+    rc::Rc,
+};
+
+use glow::{
+    ActiveAttribute, ActiveUniform, Buffer, Framebuffer, NativeBuffer, NativeFramebuffer,
+    NativeProgram, NativeShader, NativeTexture, NativeUniformLocation, NativeVertexArray, Program,
+    Shader, Texture, UniformLocation, VertexArray,
+};
+
+use crate::graphics::gl_type_from_glsl;
+
+use super::GlDevice;
+
+/* the types of objects RecordingDevice hands out */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RecordedObjectType {
+    Buffer,
+    VertexArray,
+    Texture,
+    Program,
+    Shader,
+    Framebuffer,
+}
+
+/* a draw call along with the state it was made in */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedDraw {
+    pub mode: u32,
+    pub count: i32,
+    pub instance_count: i32,
+    pub indexed: bool,
+    pub program: Option<Program>,
+    pub vertex_array: Option<VertexArray>,
+    pub framebuffer: Option<Framebuffer>,
+    /* (unit, target, texture), sorted */
+    pub textures: Vec<(u32, u32, Texture)>,
+}
+
+#[derive(Debug, Clone)]
+struct RecordedInput {
+    name: String,
+    gl_type: u32,
+    size: i32,
+}
+
+/* what a linked program exposes, read from the sources of its shaders */
+#[derive(Debug, Default)]
+struct RecordedProgram {
+    attached: Vec<NativeShader>,
+    attribute_bindings: HashMap<String, u32>,
+    attributes: Vec<(RecordedInput, u32)>,
+    /* uniforms outside of blocks, their index being their location */
+    uniforms: Vec<RecordedInput>,
+    block_members: Vec<RecordedInput>,
+    blocks: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct Recording {
+    commands: Vec<String>,
+    draws: Vec<RecordedDraw>,
+    errors: Vec<String>,
+    last_name: u32,
+    live: HashMap<NonZeroU32, RecordedObjectType>,
+    shaders: HashMap<NativeShader, (u32, String)>,
+    programs: HashMap<NativeProgram, RecordedProgram>,
+    program: Option<Program>,
+    vertex_array: Option<VertexArray>,
+    buffers: HashMap<u32, Buffer>,
+    active_unit: u32,
+    textures: HashMap<(u32, u32), Texture>,
+    framebuffer: Option<Framebuffer>,
+    enabled: HashSet<u32>,
+    viewport: [i32; 4],
+}
+
+impl Recording {
+    fn create(&mut self, object_type: RecordedObjectType) -> NonZeroU32 {
+        self.last_name += 1;
+        let name = NonZeroU32::new(self.last_name).unwrap();
+        self.live.insert(name, object_type);
+        name
+    }
+
+    /* records an error if the object isn't a live one of that type */
+    fn check(&mut self, call: &str, object_type: RecordedObjectType, name: NonZeroU32) -> bool {
+        if self.live.get(&name) == Some(&object_type) {
+            return true;
+        }
+        let error = format!("{call}: {object_type:?} {name} is not alive");
+        self.errors.push(error);
+        false
+    }
+
+    fn delete(&mut self, call: &str, object_type: RecordedObjectType, name: NonZeroU32) {
+        if self.check(call, object_type, name) {
+            self.live.remove(&name);
+        }
+    }
+
+    fn draw(&mut self, mode: u32, count: i32, instance_count: i32, indexed: bool) {
+        if self.program.is_none() {
+            self.errors.push("draw without a program".to_string());
+        }
+        let mut textures: Vec<_> = self
+            .textures
+            .iter()
+            .map(|(&(unit, target), &texture)| (unit, target, texture))
+            .collect();
+        textures.sort();
+        self.draws.push(RecordedDraw {
+            mode,
+            count,
+            instance_count,
+            indexed,
+            program: self.program,
+            vertex_array: self.vertex_array,
+            framebuffer: self.framebuffer,
+            textures,
+        });
+    }
+
+    fn check_uniform(&mut self, call: &str, location: Option<&UniformLocation>) {
+        let Some(location) = location else {
+            return;
+        };
+        let known = self
+            .program
+            .and_then(|program| self.programs.get(&program))
+            .is_some_and(|program| (location.0 as usize) < program.uniforms.len());
+        if !known {
+            let error = format!("{call}: no uniform at {location:?} in the bound program");
+            self.errors.push(error);
+        }
+    }
+
+    fn link(&mut self, program: Program) {
+        let Some(recorded) = self.programs.get(&program) else {
+            return;
+        };
+        let mut declarations = Declarations::default();
+        for shader in recorded.attached.iter() {
+            if let Some((shader_type, source)) = self.shaders.get(shader) {
+                declarations.scan(source, *shader_type == glow::VERTEX_SHADER);
+            }
+        }
+        let recorded = self.programs.get_mut(&program).unwrap();
+        // unbound attributes go to the first locations left
+        let mut used: HashSet<u32> = recorded.attribute_bindings.values().copied().collect();
+        recorded.attributes = declarations
+            .attributes
+            .into_iter()
+            .map(|input| {
+                let location = match recorded.attribute_bindings.get(&input.name) {
+                    Some(location) => *location,
+                    None => {
+                        let location = (0..).find(|l| !used.contains(l)).unwrap();
+                        used.insert(location);
+                        location
+                    }
+                };
+                (input, location)
+            })
+            .collect();
+        recorded.uniforms = declarations.uniforms;
+        recorded.block_members = declarations.block_members;
+        recorded.blocks = declarations.blocks;
+    }
+}
+
+/* uniforms, blocks and vertex inputs declared by GLSL sources, taking #ifdefs into account.
+ * Unlike a real driver, nothing unused is optimized out */
+#[derive(Debug, Default)]
+struct Declarations {
+    attributes: Vec<RecordedInput>,
+    uniforms: Vec<RecordedInput>,
+    block_members: Vec<RecordedInput>,
+    blocks: Vec<String>,
+}
+
+impl Declarations {
+    fn scan(&mut self, source: &str, is_vertex: bool) {
+        let mut defines = HashSet::new();
+        // (enclosing branches active, a branch of this #if was taken)
+        let mut conditions: Vec<(bool, bool)> = Vec::new();
+        let mut in_block = false;
+        for line in source.lines() {
+            let line = line.split("//").next().unwrap().trim();
+            let active = conditions
+                .last()
+                .is_none_or(|(parent, taken)| *parent && *taken);
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("#ifdef") | Some("#ifndef") | Some("#if") => {
+                    let defined = words.next().is_some_and(|name| defines.contains(name));
+                    let taken = match line.starts_with("#ifndef") {
+                        true => !defined,
+                        false => defined,
+                    };
+                    conditions.push((active, taken));
+                    continue;
+                }
+                Some("#else") | Some("#elif") => {
+                    if let Some((_, taken)) = conditions.last_mut() {
+                        *taken = !*taken;
+                    }
+                    continue;
+                }
+                Some("#endif") => {
+                    conditions.pop();
+                    continue;
+                }
+                Some("#define") if active => {
+                    defines.extend(words.next().map(str::to_string));
+                    continue;
+                }
+                _ if !active => continue,
+                _ => {}
+            }
+
+            if in_block {
+                in_block = !line.starts_with('}');
+                if let Some(input) = parse_declaration(line) {
+                    self.block_members.push(input);
+                }
+                continue;
+            }
+            let line = strip_layout(line);
+            if let Some(rest) = line.strip_prefix("uniform ") {
+                if rest.contains('{') || !rest.contains(';') {
+                    let name = rest.trim_end_matches('{').trim();
+                    self.blocks.push(name.to_string());
+                    in_block = true;
+                } else if let Some(input) = parse_declaration(rest) {
+                    if !self.uniforms.iter().any(|u| u.name == input.name) {
+                        self.uniforms.push(input);
+                    }
+                }
+            } else if let Some(rest) = line.strip_prefix("in ").filter(|_| is_vertex) {
+                self.attributes.extend(parse_declaration(rest));
+            }
+        }
+    }
+}
+
+/* "layout (location = 0) in vec3 position;" -> "in vec3 position;" */
+fn strip_layout(line: &str) -> &str {
+    match line.strip_prefix("layout") {
+        Some(rest) => rest
+            .split_once(')')
+            .map_or(line, |(_, rest)| rest.trim_start()),
+        None => line,
+    }
+}
+
+/* "highp vec3 lights[4];" -> vec3 of size 4 named lights */
+fn parse_declaration(line: &str) -> Option<RecordedInput> {
+    let mut words = line
+        .trim_end_matches(';')
+        .split_whitespace()
+        .filter(|word| !matches!(*word, "highp" | "mediump" | "lowp" | "flat" | "smooth"));
+    let gl_type = gl_type_from_glsl(words.next()?)?;
+    let declarator = words.next()?;
+    let (name, size) = match declarator.split_once('[') {
+        Some((name, size)) => (name, size.trim_end_matches(']').parse().ok()?),
+        None => (declarator, 1),
+    };
+    Some(RecordedInput {
+        name: name.to_string(),
+        gl_type,
+        size,
+    })
+}
+
+/* a device without a GPU, for native tests: logs every call, keeps track of the bound state,
+ * draws and live objects, and reports misuse like binding a deleted object. Shaders always
+ * compile, their uniforms and attributes being read from the source. Clones share the same
+ * recording, so a test can hand one to a GlContext and inspect the other */
+#[derive(Debug, Clone, Default)]
+pub struct RecordingDevice {
+    recording: Rc<RefCell<Recording>>,
+    extensions: HashSet<String>,
+}
+
+impl RecordingDevice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_extension(mut self, name: &str) -> Self {
+        self.extensions.insert(name.to_string());
+        self
+    }
+
+    /* every call since the last clear, like "bind_vertex_array(Some(NativeVertexArray(3)))" */
+    pub fn get_commands(&self) -> Vec<String> {
+        self.recording.borrow().commands.clone()
+    }
+
+    pub fn get_draws(&self) -> Vec<RecordedDraw> {
+        self.recording.borrow().draws.clone()
+    }
+
+    /* forgets the commands and draws recorded so far, keeping objects and state */
+    pub fn clear(&self) {
+        let mut recording = self.recording.borrow_mut();
+        recording.commands.clear();
+        recording.draws.clear();
+    }
+
+    /* misuse of the API, like using a deleted object or drawing without a program */
+    pub fn get_errors(&self) -> Vec<String> {
+        self.recording.borrow().errors.clone()
+    }
+
+    /* objects created and not deleted yet, sorted by type and name */
+    pub fn get_live_objects(&self) -> Vec<(RecordedObjectType, u32)> {
+        let recording = self.recording.borrow();
+        let mut live: Vec<_> = recording
+            .live
+            .iter()
+            .map(|(name, object_type)| (*object_type, name.get()))
+            .collect();
+        live.sort();
+        live
+    }
+
+    pub fn count_live(&self, object_type: RecordedObjectType) -> usize {
+        let recording = self.recording.borrow();
+        recording
+            .live
+            .values()
+            .filter(|t| **t == object_type)
+            .count()
+    }
+
+    pub fn get_bound_program(&self) -> Option<Program> {
+        self.recording.borrow().program
+    }
+
+    pub fn get_bound_vertex_array(&self) -> Option<VertexArray> {
+        self.recording.borrow().vertex_array
+    }
+
+    pub fn get_bound_buffer(&self, target: u32) -> Option<Buffer> {
+        self.recording.borrow().buffers.get(&target).copied()
+    }
+
+    pub fn get_bound_texture(&self, unit: u32, target: u32) -> Option<Texture> {
+        self.recording
+            .borrow()
+            .textures
+            .get(&(unit, target))
+            .copied()
+    }
+
+    pub fn get_bound_framebuffer(&self) -> Option<Framebuffer> {
+        self.recording.borrow().framebuffer
+    }
+
+    pub fn is_enabled(&self, capability: u32) -> bool {
+        self.recording.borrow().enabled.contains(&capability)
+    }
+
+    fn record(&self, command: String) -> RefMut<'_, Recording> {
+        let mut recording = self.recording.borrow_mut();
+        recording.commands.push(command);
+        recording
+    }
+}
+
+impl GlDevice for RecordingDevice {
+    fn supported_extensions(&self) -> &HashSet<String> {
+        &self.extensions
+    }
+
+    unsafe fn active_texture(&self, unit: u32) {
+        let mut recording = self.record(format!("active_texture({unit:#x})"));
+        recording.active_unit = unit - glow::TEXTURE0;
+    }
+
+    unsafe fn attach_shader(&self, program: Program, shader: Shader) {
+        let mut recording = self.record(format!("attach_shader({program:?}, {shader:?})"));
+        if recording.check("attach_shader", RecordedObjectType::Program, program.0)
+            && recording.check("attach_shader", RecordedObjectType::Shader, shader.0)
+        {
+            let recorded = recording.programs.entry(program).or_default();
+            recorded.attached.push(shader);
+        }
+    }
+
+    unsafe fn bind_attrib_location(&self, program: Program, index: u32, name: &str) {
+        let mut recording = self.record(format!(
+            "bind_attrib_location({program:?}, {index}, {name})"
+        ));
+        if recording.check(
+            "bind_attrib_location",
+            RecordedObjectType::Program,
+            program.0,
+        ) {
+            let recorded = recording.programs.entry(program).or_default();
+            recorded.attribute_bindings.insert(name.to_string(), index);
+        }
+    }
+
+    unsafe fn bind_buffer(&self, target: u32, buffer: Option<Buffer>) {
+        let mut recording = self.record(format!("bind_buffer({target:#x}, {buffer:?})"));
+        match buffer {
+            Some(buffer) => {
+                recording.check("bind_buffer", RecordedObjectType::Buffer, buffer.0);
+                recording.buffers.insert(target, buffer);
+            }
+            None => {
+                recording.buffers.remove(&target);
+            }
+        }
+    }
+
+    unsafe fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<Buffer>) {
+        let mut recording = self.record(format!(
+            "bind_buffer_base({target:#x}, {index}, {buffer:?})"
+        ));
+        if let Some(buffer) = buffer {
+            recording.check("bind_buffer_base", RecordedObjectType::Buffer, buffer.0);
+        }
+    }
+
+    unsafe fn bind_framebuffer(&self, target: u32, framebuffer: Option<Framebuffer>) {
+        let mut recording = self.record(format!("bind_framebuffer({target:#x}, {framebuffer:?})"));
+        if let Some(framebuffer) = framebuffer {
+            let object_type = RecordedObjectType::Framebuffer;
+            recording.check("bind_framebuffer", object_type, framebuffer.0);
+        }
+        recording.framebuffer = framebuffer;
+    }
+
+    unsafe fn bind_texture(&self, target: u32, texture: Option<Texture>) {
+        let mut recording = self.record(format!("bind_texture({target:#x}, {texture:?})"));
+        let unit = recording.active_unit;
+        match texture {
+            Some(texture) => {
+                recording.check("bind_texture", RecordedObjectType::Texture, texture.0);
+                recording.textures.insert((unit, target), texture);
+            }
+            None => {
+                recording.textures.remove(&(unit, target));
+            }
+        }
+    }
+
+    unsafe fn bind_vertex_array(&self, vertex_array: Option<VertexArray>) {
+        let mut recording = self.record(format!("bind_vertex_array({vertex_array:?})"));
+        if let Some(vertex_array) = vertex_array {
+            let object_type = RecordedObjectType::VertexArray;
+            recording.check("bind_vertex_array", object_type, vertex_array.0);
+        }
+        recording.vertex_array = vertex_array;
+    }
+
+    unsafe fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        self.record(format!(
+            "blend_equation_separate({mode_rgb:#x}, {mode_alpha:#x})"
+        ));
+    }
+
+    unsafe fn blend_func_separate(
+        &self,
+        src_rgb: u32,
+        dst_rgb: u32,
+        src_alpha: u32,
+        dst_alpha: u32,
+    ) {
+        self.record(format!(
+            "blend_func_separate({src_rgb:#x}, {dst_rgb:#x}, {src_alpha:#x}, {dst_alpha:#x})"
+        ));
+    }
+
+    unsafe fn buffer_data_size(&self, target: u32, size: i32, usage: u32) {
+        self.record(format!("buffer_data_size({target:#x}, {size}, {usage:#x})"));
+    }
+
+    unsafe fn buffer_data_u8_slice(&self, target: u32, data: &[u8], usage: u32) {
+        let mut recording = self.record(format!(
+            "buffer_data_u8_slice({target:#x}, {} bytes, {usage:#x})",
+            data.len()
+        ));
+        if !recording.buffers.contains_key(&target) {
+            let error = format!("buffer_data_u8_slice: no buffer bound to {target:#x}");
+            recording.errors.push(error);
+        }
+    }
+
+    unsafe fn buffer_sub_data_u8_slice(&self, target: u32, offset: i32, src_data: &[u8]) {
+        self.record(format!(
+            "buffer_sub_data_u8_slice({target:#x}, {offset}, {} bytes)",
+            src_data.len()
+        ));
+    }
+
+    unsafe fn check_framebuffer_status(&self, target: u32) -> u32 {
+        self.record(format!("check_framebuffer_status({target:#x})"));
+        glow::FRAMEBUFFER_COMPLETE
+    }
+
+    unsafe fn clear(&self, mask: u32) {
+        self.record(format!("clear({mask:#x})"));
+    }
+
+    unsafe fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.record(format!("clear_color({red}, {green}, {blue}, {alpha})"));
+    }
+
+    unsafe fn compile_shader(&self, shader: Shader) {
+        let mut recording = self.record(format!("compile_shader({shader:?})"));
+        recording.check("compile_shader", RecordedObjectType::Shader, shader.0);
+    }
+
+    unsafe fn create_buffer(&self) -> Result<Buffer, String> {
+        let mut recording = self.record("create_buffer()".to_string());
+        Ok(NativeBuffer(recording.create(RecordedObjectType::Buffer)))
+    }
+
+    unsafe fn create_framebuffer(&self) -> Result<Framebuffer, String> {
+        let mut recording = self.record("create_framebuffer()".to_string());
+        Ok(NativeFramebuffer(
+            recording.create(RecordedObjectType::Framebuffer),
+        ))
+    }
+
+    unsafe fn create_program(&self) -> Result<Program, String> {
+        let mut recording = self.record("create_program()".to_string());
+        let program = NativeProgram(recording.create(RecordedObjectType::Program));
+        recording
+            .programs
+            .insert(program, RecordedProgram::default());
+        Ok(program)
+    }
+
+    unsafe fn create_shader(&self, shader_type: u32) -> Result<Shader, String> {
+        let mut recording = self.record(format!("create_shader({shader_type:#x})"));
+        let shader = NativeShader(recording.create(RecordedObjectType::Shader));
+        recording
+            .shaders
+            .insert(shader, (shader_type, String::new()));
+        Ok(shader)
+    }
+
+    unsafe fn create_texture(&self) -> Result<Texture, String> {
+        let mut recording = self.record("create_texture()".to_string());
+        Ok(NativeTexture(recording.create(RecordedObjectType::Texture)))
+    }
+
+    unsafe fn create_vertex_array(&self) -> Result<VertexArray, String> {
+        let mut recording = self.record("create_vertex_array()".to_string());
+        Ok(NativeVertexArray(
+            recording.create(RecordedObjectType::VertexArray),
+        ))
+    }
+
+    unsafe fn delete_buffer(&self, buffer: Buffer) {
+        let mut recording = self.record(format!("delete_buffer({buffer:?})"));
+        recording.delete("delete_buffer", RecordedObjectType::Buffer, buffer.0);
+        recording.buffers.retain(|_, bound| *bound != buffer);
+    }
+
+    unsafe fn delete_framebuffer(&self, framebuffer: Framebuffer) {
+        let mut recording = self.record(format!("delete_framebuffer({framebuffer:?})"));
+        let object_type = RecordedObjectType::Framebuffer;
+        recording.delete("delete_framebuffer", object_type, framebuffer.0);
+        if recording.framebuffer == Some(framebuffer) {
+            recording.framebuffer = None;
+        }
+    }
+
+    unsafe fn delete_program(&self, program: Program) {
+        let mut recording = self.record(format!("delete_program({program:?})"));
+        recording.delete("delete_program", RecordedObjectType::Program, program.0);
+    }
+
+    unsafe fn delete_shader(&self, shader: Shader) {
+        let mut recording = self.record(format!("delete_shader({shader:?})"));
+        recording.delete("delete_shader", RecordedObjectType::Shader, shader.0);
+    }
+
+    unsafe fn delete_texture(&self, texture: Texture) {
+        let mut recording = self.record(format!("delete_texture({texture:?})"));
+        recording.delete("delete_texture", RecordedObjectType::Texture, texture.0);
+        recording.textures.retain(|_, bound| *bound != texture);
+    }
+
+    unsafe fn delete_vertex_array(&self, vertex_array: VertexArray) {
+        let mut recording = self.record(format!("delete_vertex_array({vertex_array:?})"));
+        let object_type = RecordedObjectType::VertexArray;
+        recording.delete("delete_vertex_array", object_type, vertex_array.0);
+        if recording.vertex_array == Some(vertex_array) {
+            recording.vertex_array = None;
+        }
+    }
+
+    unsafe fn depth_func(&self, func: u32) {
+        self.record(format!("depth_func({func:#x})"));
+    }
+
+    unsafe fn depth_mask(&self, value: bool) {
+        self.record(format!("depth_mask({value})"));
+    }
+
+    unsafe fn disable(&self, parameter: u32) {
+        let mut recording = self.record(format!("disable({parameter:#x})"));
+        recording.enabled.remove(&parameter);
+    }
+
+    unsafe fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        let mut recording = self.record(format!("draw_arrays({mode:#x}, {first}, {count})"));
+        recording.draw(mode, count, 1, false);
+    }
+
+    unsafe fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32) {
+        let mut recording = self.record(format!(
+            "draw_arrays_instanced({mode:#x}, {first}, {count}, {instances})"
+        ));
+        recording.draw(mode, count, instances, false);
+    }
+
+    unsafe fn draw_buffers(&self, buffers: &[u32]) {
+        self.record(format!("draw_buffers({buffers:?})"));
+    }
+
+    unsafe fn draw_elements(&self, mode: u32, count: i32, element_type: u32, offset: i32) {
+        let mut recording = self.record(format!(
+            "draw_elements({mode:#x}, {count}, {element_type:#x}, {offset})"
+        ));
+        recording.draw(mode, count, 1, true);
+    }
+
+    unsafe fn draw_elements_instanced(
+        &self,
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+        instances: i32,
+    ) {
+        let mut recording = self.record(format!(
+            "draw_elements_instanced({mode:#x}, {count}, {element_type:#x}, {offset}, {instances})"
+        ));
+        recording.draw(mode, count, instances, true);
+    }
+
+    unsafe fn enable(&self, parameter: u32) {
+        let mut recording = self.record(format!("enable({parameter:#x})"));
+        recording.enabled.insert(parameter);
+    }
+
+    unsafe fn enable_vertex_attrib_array(&self, index: u32) {
+        let mut recording = self.record(format!("enable_vertex_attrib_array({index})"));
+        if recording.vertex_array.is_none() {
+            let error = "enable_vertex_attrib_array: no vertex array bound".to_string();
+            recording.errors.push(error);
+        }
+    }
+
+    unsafe fn framebuffer_texture_2d(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<Texture>,
+        level: i32,
+    ) {
+        let mut recording = self.record(format!(
+            "framebuffer_texture_2d({target:#x}, {attachment:#x}, {texture_target:#x}, \
+             {texture:?}, {level})"
+        ));
+        if let Some(texture) = texture {
+            let call = "framebuffer_texture_2d";
+            recording.check(call, RecordedObjectType::Texture, texture.0);
+        }
+    }
+
+    unsafe fn generate_mipmap(&self, target: u32) {
+        self.record(format!("generate_mipmap({target:#x})"));
+    }
+
+    unsafe fn get_active_attribute(&self, program: Program, index: u32) -> Option<ActiveAttribute> {
+        let recording = self.record(format!("get_active_attribute({program:?}, {index})"));
+        let (input, _) = recording
+            .programs
+            .get(&program)?
+            .attributes
+            .get(index as usize)?;
+        Some(ActiveAttribute {
+            size: input.size,
+            atype: input.gl_type,
+            name: input.name.clone(),
+        })
+    }
+
+    unsafe fn get_active_attributes(&self, program: Program) -> u32 {
+        let recording = self.record(format!("get_active_attributes({program:?})"));
+        let recorded = recording.programs.get(&program);
+        recorded.map_or(0, |recorded| recorded.attributes.len() as u32)
+    }
+
+    unsafe fn get_active_uniform(&self, program: Program, index: u32) -> Option<ActiveUniform> {
+        let recording = self.record(format!("get_active_uniform({program:?}, {index})"));
+        let recorded = recording.programs.get(&program)?;
+        let input = recorded
+            .uniforms
+            .iter()
+            .chain(recorded.block_members.iter())
+            .nth(index as usize)?;
+        // like drivers do, arrays are reported by their first element
+        let name = match input.size > 1 {
+            true => format!("{}[0]", input.name),
+            false => input.name.clone(),
+        };
+        Some(ActiveUniform {
+            size: input.size,
+            utype: input.gl_type,
+            name,
+        })
+    }
+
+    unsafe fn get_active_uniforms(&self, program: Program) -> u32 {
+        let recording = self.record(format!("get_active_uniforms({program:?})"));
+        let recorded = recording.programs.get(&program);
+        recorded.map_or(0, |recorded| {
+            (recorded.uniforms.len() + recorded.block_members.len()) as u32
+        })
+    }
+
+    unsafe fn get_attrib_location(&self, program: Program, name: &str) -> Option<u32> {
+        let recording = self.record(format!("get_attrib_location({program:?}, {name})"));
+        let recorded = recording.programs.get(&program)?;
+        recorded
+            .attributes
+            .iter()
+            .find(|(input, _)| input.name == name)
+            .map(|(_, location)| *location)
+    }
+
+    unsafe fn get_parameter_i32_slice(&self, parameter: u32, out: &mut [i32]) {
+        let recording = self.record(format!("get_parameter_i32_slice({parameter:#x})"));
+        match parameter {
+            glow::VIEWPORT => out.copy_from_slice(&recording.viewport[..out.len()]),
+            _ => out.fill(0),
+        }
+    }
+
+    unsafe fn get_program_info_log(&self, program: Program) -> String {
+        self.record(format!("get_program_info_log({program:?})"));
+        String::new()
+    }
+
+    unsafe fn get_program_link_status(&self, program: Program) -> bool {
+        self.record(format!("get_program_link_status({program:?})"));
+        true
+    }
+
+    unsafe fn get_shader_compile_status(&self, shader: Shader) -> bool {
+        self.record(format!("get_shader_compile_status({shader:?})"));
+        true
+    }
+
+    unsafe fn get_shader_info_log(&self, shader: Shader) -> String {
+        self.record(format!("get_shader_info_log({shader:?})"));
+        String::new()
+    }
+
+    unsafe fn get_uniform_block_index(&self, program: Program, name: &str) -> Option<u32> {
+        let recording = self.record(format!("get_uniform_block_index({program:?}, {name})"));
+        let recorded = recording.programs.get(&program)?;
+        let index = recorded.blocks.iter().position(|block| block == name)?;
+        Some(index as u32)
+    }
+
+    unsafe fn get_uniform_location(&self, program: Program, name: &str) -> Option<UniformLocation> {
+        let recording = self.record(format!("get_uniform_location({program:?}, {name})"));
+        let recorded = recording.programs.get(&program)?;
+        let name = name.trim_end_matches("[0]");
+        let index = recorded
+            .uniforms
+            .iter()
+            .position(|input| input.name == name)?;
+        Some(NativeUniformLocation(index as u32))
+    }
+
+    unsafe fn link_program(&self, program: Program) {
+        let mut recording = self.record(format!("link_program({program:?})"));
+        if recording.check("link_program", RecordedObjectType::Program, program.0) {
+            recording.link(program);
+        }
+    }
+
+    unsafe fn polygon_offset(&self, factor: f32, units: f32) {
+        self.record(format!("polygon_offset({factor}, {units})"));
+    }
+
+    unsafe fn read_buffer(&self, src: u32) {
+        self.record(format!("read_buffer({src:#x})"));
+    }
+
+    unsafe fn shader_source(&self, shader: Shader, source: &str) {
+        let mut recording = self.record(format!("shader_source({shader:?})"));
+        if let Some((_, shader_source)) = recording.shaders.get_mut(&shader) {
+            *shader_source = source.to_string();
+        }
+    }
+
+    unsafe fn tex_image_2d(
+        &self,
+        target: u32,
+        level: i32,
+        _internal_format: i32,
+        width: i32,
+        height: i32,
+        _border: i32,
+        _format: u32,
+        _ty: u32,
+        _pixels: Option<&[u8]>,
+    ) {
+        self.record(format!(
+            "tex_image_2d({target:#x}, {level}, {width}x{height})"
+        ));
+    }
+
+    unsafe fn tex_image_3d(
+        &self,
+        target: u32,
+        level: i32,
+        _internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        _border: i32,
+        _format: u32,
+        _ty: u32,
+        _pixels: Option<&[u8]>,
+    ) {
+        self.record(format!(
+            "tex_image_3d({target:#x}, {level}, {width}x{height}x{depth})"
+        ));
+    }
+
+    unsafe fn tex_parameter_i32(&self, target: u32, parameter: u32, value: i32) {
+        self.record(format!(
+            "tex_parameter_i32({target:#x}, {parameter:#x}, {value:#x})"
+        ));
+    }
+
+    unsafe fn tex_storage_2d(
+        &self,
+        target: u32,
+        levels: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+    ) {
+        self.record(format!(
+            "tex_storage_2d({target:#x}, {levels}, {internal_format:#x}, {width}x{height})"
+        ));
+    }
+
+    unsafe fn uniform_1_f32(&self, location: Option<&UniformLocation>, x: f32) {
+        let mut recording = self.record(format!("uniform_1_f32({location:?}, {x})"));
+        recording.check_uniform("uniform_1_f32", location);
+    }
+
+    unsafe fn uniform_1_i32(&self, location: Option<&UniformLocation>, x: i32) {
+        let mut recording = self.record(format!("uniform_1_i32({location:?}, {x})"));
+        recording.check_uniform("uniform_1_i32", location);
+    }
+
+    unsafe fn uniform_2_f32(&self, location: Option<&UniformLocation>, x: f32, y: f32) {
+        let mut recording = self.record(format!("uniform_2_f32({location:?}, {x}, {y})"));
+        recording.check_uniform("uniform_2_f32", location);
+    }
+
+    unsafe fn uniform_3_f32(&self, location: Option<&UniformLocation>, x: f32, y: f32, z: f32) {
+        let mut recording = self.record(format!("uniform_3_f32({location:?}, {x}, {y}, {z})"));
+        recording.check_uniform("uniform_3_f32", location);
+    }
+
+    unsafe fn uniform_4_f32(
+        &self,
+        location: Option<&UniformLocation>,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32,
+    ) {
+        let mut recording = self.record(format!("uniform_4_f32({location:?}, {x}, {y}, {z}, {w})"));
+        recording.check_uniform("uniform_4_f32", location);
+    }
+
+    unsafe fn uniform_block_binding(&self, program: Program, index: u32, binding: u32) {
+        self.record(format!(
+            "uniform_block_binding({program:?}, {index}, {binding})"
+        ));
+    }
+
+    unsafe fn uniform_matrix_3_f32_slice(
+        &self,
+        location: Option<&UniformLocation>,
+        transpose: bool,
+        v: &[f32],
+    ) {
+        let mut recording = self.record(format!(
+            "uniform_matrix_3_f32_slice({location:?}, {transpose}, {v:?})"
+        ));
+        recording.check_uniform("uniform_matrix_3_f32_slice", location);
+    }
+
+    unsafe fn uniform_matrix_4_f32_slice(
+        &self,
+        location: Option<&UniformLocation>,
+        transpose: bool,
+        v: &[f32],
+    ) {
+        let mut recording = self.record(format!(
+            "uniform_matrix_4_f32_slice({location:?}, {transpose}, {v:?})"
+        ));
+        recording.check_uniform("uniform_matrix_4_f32_slice", location);
+    }
+
+    unsafe fn use_program(&self, program: Option<Program>) {
+        let mut recording = self.record(format!("use_program({program:?})"));
+        if let Some(program) = program {
+            recording.check("use_program", RecordedObjectType::Program, program.0);
+        }
+        recording.program = program;
+    }
+
+    unsafe fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        self.record(format!("vertex_attrib_divisor({index}, {divisor})"));
+    }
+
+    unsafe fn vertex_attrib_pointer_f32(
+        &self,
+        index: u32,
+        size: i32,
+        data_type: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    ) {
+        let mut recording = self.record(format!(
+            "vertex_attrib_pointer_f32({index}, {size}, {data_type:#x}, {normalized}, {stride}, \
+             {offset})"
+        ));
+        if !recording.buffers.contains_key(&glow::ARRAY_BUFFER) {
+            let error = "vertex_attrib_pointer_f32: no array buffer bound".to_string();
+            recording.errors.push(error);
+        }
+    }
+
+    unsafe fn vertex_attrib_pointer_i32(
+        &self,
+        index: u32,
+        size: i32,
+        data_type: u32,
+        stride: i32,
+        offset: i32,
+    ) {
+        let mut recording = self.record(format!(
+            "vertex_attrib_pointer_i32({index}, {size}, {data_type:#x}, {stride}, {offset})"
+        ));
+        if !recording.buffers.contains_key(&glow::ARRAY_BUFFER) {
+            let error = "vertex_attrib_pointer_i32: no array buffer bound".to_string();
+            recording.errors.push(error);
+        }
+    }
+
+    unsafe fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        let mut recording = self.record(format!("viewport({x}, {y}, {width}, {height})"));
+        recording.viewport = [x, y, width, height];
+    }
+}
@@ -1,5 +1,3 @@
-use glow::HasContext;
-
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{
@@ -50,10 +48,17 @@ pub fn get_web_sys_context() -> Result<WebGl2RenderingContext, JsValue> {
     Ok(context)
 }
 
+#[cfg(target_arch = "wasm32")]
 pub fn get_webgl2_context() -> Result<glow::Context, JsValue> {
     Ok(glow::Context::from_webgl2_context(get_web_sys_context()?))
 }
 
+/* native builds draw through another device, see GlDevice */
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_webgl2_context() -> Result<glow::Context, JsValue> {
+    Err("WebGL2 is only available on the web".into())
+}
+
 #[wasm_bindgen]
 pub async fn run(repo: String) -> Result<JsValue, JsValue> {
     let mut opts = RequestInit::new();
@@ -82,44 +87,16 @@ pub async fn run(repo: String) -> Result<JsValue, JsValue> {
     Ok(json)
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum GlRenderFlags {
-    DepthTest = glow::DEPTH_TEST as isize,
-    CullFace = glow::CULL_FACE as isize,
-    Blend = glow::BLEND as isize,
-}
-
-pub struct GlState {
-    pub depth_test: bool,
-    pub cull_face: bool,
-    pub blend: bool,
-}
-
-impl GlState {
-    // pub fn save(gl: &glow::Context) -> Self {
-    //     unsafe {
-    //         Self {
-    //             depth_test: gl.is_enabled(GlRenderFlags::DepthTest as _),
-    //             cull_face: gl.is_enabled(GlRenderFlags::CullFace as _),
-    //             blend: gl.is_enabled(GlRenderFlags::Blend as _),
-    //         }
-    //     }
-    // }
-    // pub fn restore(&self, gl: &glow::Context) {
-    //     GlState::set_flag(gl, GlRenderFlags::DepthTest, self.depth_test);
-    //     GlState::set_flag(gl, GlRenderFlags::CullFace, self.cull_face);
-    //     GlState::set_flag(gl, GlRenderFlags::Blend, self.blend);
-    // }
-
-    pub fn set_flag(gl: &glow::Context, param: GlRenderFlags, value: bool) {
-        if value {
-            unsafe { gl.enable(param as _) };
-        } else {
-            unsafe { gl.disable(param as _) };
-        }
-    }
+#[cfg(target_arch = "wasm32")]
+pub fn performance_now() -> f64 {
+    get_performance().unwrap().now()
 }
 
+/* milliseconds since the first call, as performance.now() counts from the page load */
+#[cfg(not(target_arch = "wasm32"))]
 pub fn performance_now() -> f64 {
-    get_performance().unwrap().now()
+    use std::{sync::OnceLock, time::Instant};
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
 }
@@ -1,15 +1,6 @@
-mod chunk;
-mod chunkstreaming;
-mod position;
-mod testworldgenerator;
 mod world;
-mod worldgenerator;
 mod worldrender;
 
-pub use chunk::*;
-pub use chunkstreaming::*;
-pub use position::*;
-pub use testworldgenerator::*;
+pub use engine_core::world::*;
 pub use world::*;
-pub use worldgenerator::*;
 pub use worldrender::*;
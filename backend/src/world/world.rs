@@ -1,12 +1,13 @@
-use std::{collections::HashMap, mem::size_of, rc::Rc};
+use std::collections::HashMap;
 
 use glam::{ivec3, IVec3, Vec3};
-use glow::HasContext;
 use log::info;
 
 const MAX_LOAD_CHUNK_DISTANCE: i32 = 15;
 const MAX_LOAD_CHUNK_DISTANCE_SQUARED: i32 = MAX_LOAD_CHUNK_DISTANCE * MAX_LOAD_CHUNK_DISTANCE;
-const MAX_MESH_TO_KEEP: usize = 1024;
+/* GPU memory for chunk meshes. The farthest meshes out of the load distance are dropped to make
+ * room for new ones, when that isn't enough no more meshes are loaded */
+pub const DEFAULT_CHUNK_MEMORY_BUDGET: usize = 128 * 1024 * 1024;
 
 // const CHUNK_LOADING_WEIGHT: f32 = 1.0;
 // const CHUNK_GENERATION_WEIGHT: f32 = 0.5;
@@ -14,11 +15,15 @@ const MAX_LOADS_PER_FRAME: usize = 32;
 
 use crate::{
     core::Time,
-    graphics::{Camera, TextureType},
-    world::{WorldGenerator, MAX_CHUNK_Y, MIN_CHUNK_Y},
+    graphics::{Camera, ShadowMap},
+    utils::GlContext,
+    world::{WorldGenerator, CHUNK_SIZE, MAX_CHUNK_Y, MIN_CHUNK_Y},
 };
 
-use super::{BlockPos, ChunkPos, ChunkStreamer, ChunkVao, WorldRenderData};
+use super::{
+    BlockAppearance, BlockPos, BlockTextureLayers, BlockType, ChunkMesh, ChunkPos, ChunkStreamer,
+    LightStorage, WorldRenderData,
+};
 
 type OffsetPriority = Vec<IVec3>;
 
@@ -48,13 +53,19 @@ pub struct World<G>
 where
     G: WorldGenerator,
 {
-    chunks: HashMap<ChunkPos, Option<ChunkVao>>,
+    chunks: HashMap<ChunkPos, Option<ChunkMesh>>,
     loaded_vertices: usize,
+    loaded_bytes: usize,
+    chunk_memory_budget: usize,
     // loaded_meshes: Vec<LoadedChunkMesh>,
     streamer: ChunkStreamer<G>,
     last_computed_chunk_pos: Option<ChunkPos>,
     offset_priority: OffsetPriority,
     chunks_to_load: Vec<ChunkPos>,
+    /* no mesh fits in the budget, loading waits until the player moves or meshes are dropped */
+    budget_full: bool,
+    /* layers written in the meshes, they match the bound block texture array */
+    texture_layers: BlockTextureLayers,
     render_data: WorldRenderData,
 }
 
@@ -69,40 +80,91 @@ where
         Self {
             chunks: HashMap::new(),
             loaded_vertices: 0,
+            loaded_bytes: 0,
+            chunk_memory_budget: DEFAULT_CHUNK_MEMORY_BUDGET,
             streamer: ChunkStreamer::new(generator),
             offset_priority,
             last_computed_chunk_pos: None,
-            chunks_to_load: Vec::with_capacity(MAX_MESH_TO_KEEP * 2),
+            chunks_to_load: Vec::new(),
+            budget_full: false,
+            texture_layers: BlockTextureLayers::default(),
             render_data: WorldRenderData::new(),
         }
     }
 
     pub fn get_info(&self) -> String {
         let vertex_count = self.loaded_vertices;
-        let memory_used = (vertex_count * size_of::<f32>()) as f32 / 1000000.0;
-        let loaded_meshes = self.chunks.values().map(|c| c.is_some()).count();
+        let memory_used = self.loaded_bytes as f32 / 1000000.0;
+        let budget = self.chunk_memory_budget as f32 / 1000000.0;
+        let loaded_meshes = self.chunks.values().filter(|c| c.is_some()).count();
+        let budget_full = match self.budget_full {
+            true => format!(" (full, {} chunks waiting)", self.chunks_to_load.len()),
+            false => String::new(),
+        };
         format!(
-            "World: Loaded {}/{} chunks\nStreaming: {}\n{vertex_count} vertices - {memory_used:.3} MB",
+            "World: Loaded {}/{} chunks\nStreaming: {}\n{vertex_count} vertices - {memory_used:.3}/{budget:.0} MB{budget_full}",
             loaded_meshes,
             self.chunks.len(),
             self.streamer.get_info(),
         )
     }
 
+    pub fn get_chunk_memory_budget(&self) -> usize {
+        self.chunk_memory_budget
+    }
+
+    /* GPU memory taken by the loaded chunk meshes, never above the budget */
+    pub fn get_loaded_bytes(&self) -> usize {
+        self.loaded_bytes
+    }
+
+    /* bytes of chunk meshes to keep, applied from the next update */
+    pub fn set_chunk_memory_budget(&mut self, bytes: usize) {
+        if bytes != self.chunk_memory_budget {
+            self.chunk_memory_budget = bytes;
+            self.last_computed_chunk_pos = None;
+        }
+    }
+
+    /* loaded chunks that have something to draw */
+    pub fn iter_meshed_chunks(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.chunks
+            .iter()
+            .filter(|(_, mesh)| mesh.is_some())
+            .map(|(pos, _)| *pos)
+    }
+
+    /* distance in blocks up to which chunks around the player get loaded */
+    pub fn get_load_radius(&self) -> f32 {
+        ((MAX_LOAD_CHUNK_DISTANCE - 1) * CHUNK_SIZE as i32) as f32
+    }
+
+    /* draws blocks with the texture array of the appearance, can be called again to switch
+     * packs. Meshes are rebuilt if the layers moved */
     pub fn setup_graphics(
         &mut self,
-        gl: &glow::Context,
-        texture: Rc<(TextureType, glow::WebTextureKey)>,
+        gl: &GlContext,
+        appearance: &BlockAppearance,
     ) -> Result<(), String> {
-        self.render_data.setup_graphics(gl, texture)
+        self.render_data.setup_graphics(gl, appearance)?;
+        if appearance.layers != self.texture_layers {
+            self.texture_layers = appearance.layers;
+            self.drop_all_meshes();
+        }
+        Ok(())
     }
 
-    pub fn update(
-        &mut self,
-        gl: &glow::Context,
-        _time: &Time,
-        player_pos: Vec3,
-    ) -> Result<(), String> {
+    /* every chunk gets meshed again from the next update */
+    fn drop_all_meshes(&mut self) {
+        self.chunks.clear();
+        self.chunks_to_load.clear();
+        self.loaded_vertices = 0;
+        self.loaded_bytes = 0;
+        self.budget_full = false;
+        self.last_computed_chunk_pos = None;
+    }
+
+    pub fn update(&mut self, gl: &GlContext, _time: &Time, player_pos: Vec3) -> Result<(), String> {
         let player_block_pos: BlockPos = player_pos.as_ivec3().into();
         let player_chunk_pos: ChunkPos = player_block_pos.into();
 
@@ -110,14 +172,17 @@ where
         if self.streamer.tick_streaming(player_chunk_pos) > 0 {
             geom_changed = true;
         }
+        if self.remesh_relit_chunks() > 0 {
+            geom_changed = true;
+        }
 
         if geom_changed || self.last_computed_chunk_pos != Some(player_chunk_pos) {
             self.last_computed_chunk_pos = Some(player_chunk_pos);
-            self.on_chunk_changed(player_chunk_pos, gl);
+            self.on_chunk_changed(player_chunk_pos);
             geom_changed = true;
         }
 
-        if self.load_some_chunks(gl) > 0 {
+        if self.load_some_chunks(gl, player_chunk_pos) > 0 {
             geom_changed = true;
         }
 
@@ -128,8 +193,8 @@ where
         Ok(())
     }
 
-    fn load_some_chunks(&mut self, gl: &glow::Context) -> usize {
-        if self.chunks_to_load.len() == 0 {
+    fn load_some_chunks(&mut self, gl: &GlContext, player_chunk_pos: ChunkPos) -> usize {
+        if self.chunks_to_load.is_empty() || self.budget_full {
             return 0;
         }
         let chunks_to_load: Vec<ChunkPos> = self
@@ -138,67 +203,119 @@ where
             .take(MAX_LOADS_PER_FRAME)
             .copied()
             .collect();
-        let chunks_count_to_load = chunks_to_load.len();
         info!(
             "World Graphics: gl loading {} chunks this frame",
-            chunks_count_to_load
+            chunks_to_load.len()
         );
 
-        if chunks_count_to_load > 0 {
-            for chunk_pos in chunks_to_load {
-                if !self.chunks.contains_key(&chunk_pos) {
-                    let chunk = match self.streamer.get_chunk(chunk_pos) {
-                        None => None,
-                        Some(chunk) => {
-                            let mesh = ChunkVao::load(gl, chunk).expect("can't load mesh");
-                            self.loaded_vertices += mesh.vertex_count;
-                            Some(mesh)
-                        }
-                    };
-                    self.chunks.insert(chunk_pos, chunk);
-                }
+        let mut loaded_count = 0;
+        for chunk_pos in chunks_to_load {
+            if self.chunks.contains_key(&chunk_pos) {
+                continue;
             }
-            self.chunks_to_load.retain(|c| !self.chunks.contains_key(c));
+            let vertex_data = self.streamer.get_chunk(chunk_pos).map(|chunk| {
+                let origin = chunk_pos.get_center_block_pos().as_vec();
+                chunk.to_vertex_data(&self.texture_layers, |offset| {
+                    self.streamer.get_light(origin + offset)
+                })
+            });
+            let mesh = match vertex_data {
+                None => None,
+                Some(vertex_data) => {
+                    if !self.make_room(player_chunk_pos, vertex_data.byte_size()) {
+                        info!("World: chunk memory budget full");
+                        self.budget_full = true;
+                        break;
+                    }
+                    let mesh = ChunkMesh::load(gl, vertex_data).expect("can't load mesh");
+                    self.loaded_vertices += mesh.vertex_count();
+                    self.loaded_bytes += mesh.byte_size();
+                    Some(mesh)
+                }
+            };
+            self.chunks.insert(chunk_pos, mesh);
+            loaded_count += 1;
         }
+        self.chunks_to_load.retain(|c| !self.chunks.contains_key(c));
 
-        chunks_count_to_load
+        loaded_count
     }
 
-    pub fn on_chunk_changed(&mut self, new_chunk_pos: ChunkPos, gl: &glow::Context) {
-        info!("World: recompute for chunk pos: {new_chunk_pos:?}");
+    /* drops the farthest meshes out of the load distance until `bytes` more fit in the budget,
+     * false if the meshes within the load distance take too much already */
+    fn make_room(&mut self, player_chunk_pos: ChunkPos, bytes: usize) -> bool {
+        if self.loaded_bytes + bytes <= self.chunk_memory_budget {
+            return true;
+        }
+        let mut chunks_we_can_unload: Vec<ChunkPos> = self
+            .chunks
+            .iter()
+            .filter(|(_, mesh)| mesh.is_some())
+            .map(|(chunk_pos, _)| *chunk_pos)
+            .filter(|chunk_pos| {
+                player_chunk_pos.distance_squared(*chunk_pos) > MAX_LOAD_CHUNK_DISTANCE_SQUARED
+            })
+            .collect();
+        chunks_we_can_unload.sort_by(|a, b| {
+            a.distance_squared(player_chunk_pos)
+                .cmp(&b.distance_squared(player_chunk_pos))
+        });
+        let mut unloaded = 0;
+        while self.loaded_bytes + bytes > self.chunk_memory_budget {
+            let Some(chunk_pos) = chunks_we_can_unload.pop() else {
+                break;
+            };
+            // the GL objects are released once the last frame drawing it is done
+            let mesh = self
+                .chunks
+                .remove(&chunk_pos)
+                .expect("added inexistent chunk")
+                .expect("added empty chunk");
+            self.loaded_vertices -= mesh.vertex_count();
+            self.loaded_bytes -= mesh.byte_size();
+            unloaded += 1;
+        }
+        if unloaded > 0 {
+            info!("World: unloading {unloaded} chunks");
+        }
+        self.loaded_bytes + bytes <= self.chunk_memory_budget
+    }
 
-        // delete some chunks
-        {
-            let mut chunks_we_can_unload = Vec::with_capacity(MAX_MESH_TO_KEEP * 2);
-            for (&chunk_pos, c) in self.chunks.iter() {
-                if let Some(_) = &c {
-                    if new_chunk_pos.distance_squared(chunk_pos) > MAX_LOAD_CHUNK_DISTANCE_SQUARED {
-                        chunks_we_can_unload.push(chunk_pos);
-                    }
-                }
-            }
-            chunks_we_can_unload.sort_by(|a, b| {
-                a.distance_squared(new_chunk_pos)
-                    .cmp(&b.distance_squared(new_chunk_pos))
-            });
-            if chunks_we_can_unload.len() > MAX_MESH_TO_KEEP {
-                info!(
-                    "World: unloading {} chunks",
-                    chunks_we_can_unload.len() - MAX_MESH_TO_KEEP
-                );
-                for chunk_pos in chunks_we_can_unload.drain(MAX_MESH_TO_KEEP..) {
-                    let vao = self
-                        .chunks
-                        .remove(&chunk_pos)
-                        .expect("added inexistent chunk")
-                        .expect("added empty chunk");
-                    unsafe {
-                        gl.delete_vertex_array(vao.vertex_array);
-                    }
-                    self.loaded_vertices -= vao.vertex_count;
+    /* None if the block isn't loaded */
+    pub fn get_block(&self, pos: BlockPos) -> Option<BlockType> {
+        self.streamer.get_block(pos.as_vec())
+    }
+
+    /* changes a block, the meshes around it are rebuilt on the next update */
+    pub fn set_block(&mut self, pos: BlockPos, block: BlockType) -> bool {
+        self.streamer.set_block(pos, block)
+    }
+
+    /* drops meshes whose blocks or light changed and queues them first for loading */
+    fn remesh_relit_chunks(&mut self) -> usize {
+        let relit_chunks = self.streamer.take_relit_chunks();
+        let mut count = 0;
+        for chunk_pos in relit_chunks {
+            if let Some(mesh) = self.chunks.remove(&chunk_pos) {
+                if let Some(mesh) = mesh {
+                    self.loaded_vertices -= mesh.vertex_count();
+                    self.loaded_bytes -= mesh.byte_size();
                 }
+                self.chunks_to_load.insert(0, chunk_pos);
+                count += 1;
             }
         }
+        if count > 0 {
+            self.budget_full = false;
+        }
+        count
+    }
+
+    pub fn on_chunk_changed(&mut self, new_chunk_pos: ChunkPos) {
+        info!("World: recompute for chunk pos: {new_chunk_pos:?}");
+
+        // the budget may have changed, or meshes got out of reach and can make room
+        self.budget_full = !self.make_room(new_chunk_pos, 0);
 
         // add new chunks
         {
@@ -218,7 +335,11 @@ where
         }
     }
 
-    pub fn render(&self, gl: &glow::Context, camera: &Camera) {
+    pub fn render(&mut self, gl: &GlContext, camera: &Camera) {
         self.render_data.render(gl, camera);
     }
+
+    pub fn render_shadows(&self, gl: &GlContext, shadow_map: &ShadowMap) {
+        self.render_data.render_shadows(gl, shadow_map);
+    }
 }
@@ -1,67 +1,169 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, mem::size_of, rc::Rc};
 
-use glam::IVec3;
-use glow::{HasContext, WebVertexArrayKey};
+use glam::{IVec3, Vec3};
 use log::info;
 
 use crate::{
     graphics::Camera,
-    graphics::TextureType,
-    graphics::{ShaderDef, ShaderProgram, UniformTypes},
+    graphics::ShadowMap,
+    graphics::TextureDef,
+    graphics::{
+        enable_vertex_layout, Material, ShaderDef, ShaderDefine, ShaderVariants, UniformTypes,
+        UniformValue, VertexAttrType, VertexAttribute, VertexDataType, FRAME_DATA_BLOCK,
+    },
     shader_def,
-    world::{Chunk, CHUNK_SIZE},
+    utils::{BufferHandle, GlContext, GlRenderFlags, GpuMemoryCategory, VertexArrayHandle},
+    world::{ChunkVertexData, CHUNK_SIZE},
 };
 
-use super::{BlockPos, ChunkPos};
+use super::{sort_faces_back_to_front, BlockPos, BlockTextureLayers, ChunkPos};
+
+/* packed vertex data, read at location 0 by both chunk programs so they share the VAOs */
+const CHUNK_DATA_ATTRIBUTE: (VertexAttrType, &str) =
+    (VertexAttrType::Custom(VertexDataType::I32), "data");
+const CHUNK_LAYOUT: [VertexAttribute; 1] = [VertexAttribute::integer(
+    CHUNK_DATA_ATTRIBUTE.0,
+    1,
+    VertexDataType::I32,
+)];
 
 const MAX_MESH_TO_KEEP: usize = 1024;
+/* distance (in blocks) the camera must move before translucent faces get sorted again */
+const TRANSLUCENT_RESORT_DISTANCE: f32 = 1.0;
+const CUTOUT_DEFINE: ShaderDefine = ("ALPHA_THRESHOLD", "0.5");
+const TRANSLUCENT_OPACITY: f32 = 0.6;
 
 #[derive(Debug)]
 struct GraphicContext {
-    program: Rc<ShaderProgram>,
-    texture: Rc<(TextureType, glow::WebTextureKey)>,
+    /* opaque faces, without alpha test */
+    material: Material,
+    cutout_material: Material,
+    /* same program as material, blended with TRANSLUCENT_OPACITY */
+    translucent_material: Material,
+    shadow_material: Material,
+    /* alpha tested like cutout_material, so leaves don't cast square shadows */
+    cutout_shadow_material: Material,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct ChunkVao {
-    pub vertex_array: WebVertexArrayKey,
+    pub vertex_array: VertexArrayHandle,
+    pub vertex_buffer: BufferHandle,
     pub vertex_count: usize,
 }
 
+#[derive(Debug)]
+pub struct TranslucentFaces {
+    data: Vec<i32>,
+    sorted_from: Option<Vec3>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChunkMesh {
+    pub opaque: Option<ChunkVao>,
+    pub cutout: Option<ChunkVao>,
+    pub translucent: Option<(ChunkVao, Rc<RefCell<TranslucentFaces>>)>,
+}
+
+/* what blocks look like, from a resource pack */
+#[derive(Debug, Clone)]
+pub struct BlockAppearance {
+    pub texture: Rc<TextureDef>,
+    pub layers: BlockTextureLayers,
+    /* sources of chunk.vert and chunk.frag, None keeps the built-in one */
+    pub shaders: [Option<String>; 2],
+}
+
 #[derive(Debug)]
 pub struct WorldRenderData {
     graphics: Option<GraphicContext>,
-    chunks_to_draw: Vec<(ChunkPos, ChunkVao)>,
+    chunks_to_draw: Vec<(ChunkPos, ChunkMesh)>,
 }
 
 impl ChunkVao {
-    pub fn load(gl: &glow::Context, chunk: &Chunk) -> Result<Self, String> {
-        let vertex_data = chunk.to_vertex_data();
+    pub fn load(gl: &GlContext, vertex_data: &[i32], usage: u32) -> Result<Self, String> {
         unsafe {
-            let vao = gl.create_vertex_array()?;
-            gl.bind_vertex_array(Some(vao));
-            let vbo = gl.create_buffer()?;
-            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
-            gl.buffer_data_u8_slice(
-                glow::ARRAY_BUFFER,
-                vertex_data.align_to::<u8>().1,
-                glow::STATIC_DRAW,
-            );
-
-            let location = 0;
-            let size = 1;
-            gl.vertex_attrib_pointer_i32(location, size, glow::INT, 0, 0);
-            gl.enable_vertex_attrib_array(location);
-
-            gl.bind_vertex_array(None);
+            let vao = gl.new_vertex_array()?;
+            gl.set_vertex_array(Some(vao.key()));
+            let vbo = gl.new_buffer()?;
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo.key()));
+            let bytes = vertex_data.align_to::<u8>().1;
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, bytes, usage);
+            vbo.set_size(GpuMemoryCategory::ChunkGeometry, bytes.len());
+
+            // both chunk programs read the data at location 0, see chunk_data.glsl
+            enable_vertex_layout(gl, &CHUNK_LAYOUT, 0, |_| Some(0));
+
+            gl.set_vertex_array(None);
             gl.bind_buffer(glow::ARRAY_BUFFER, None);
 
             Ok(Self {
                 vertex_array: vao,
+                vertex_buffer: vbo,
                 vertex_count: vertex_data.len(),
             })
         }
     }
+
+    fn draw(&self, gl: &GlContext) {
+        unsafe {
+            gl.set_vertex_array(Some(self.vertex_array.key()));
+            gl.draw_arrays(glow::TRIANGLES, 0, self.vertex_count as _);
+        }
+    }
+}
+
+impl ChunkMesh {
+    pub fn load(gl: &GlContext, vertex_data: ChunkVertexData) -> Result<Self, String> {
+        let load_layer = |data: &[i32], usage| match data.is_empty() {
+            true => Ok(None),
+            false => ChunkVao::load(gl, data, usage).map(Some),
+        };
+        let translucent = load_layer(&vertex_data.translucent, glow::DYNAMIC_DRAW)?.map(|vao| {
+            let faces = TranslucentFaces {
+                data: vertex_data.translucent,
+                sorted_from: None,
+            };
+            (vao, Rc::new(RefCell::new(faces)))
+        });
+        Ok(Self {
+            opaque: load_layer(&vertex_data.opaque, glow::STATIC_DRAW)?,
+            cutout: load_layer(&vertex_data.cutout, glow::STATIC_DRAW)?,
+            translucent,
+        })
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.iter_vaos().map(|vao| vao.vertex_count).sum()
+    }
+
+    /* bytes uploaded for the chunk, one i32 per vertex */
+    pub fn byte_size(&self) -> usize {
+        self.vertex_count() * size_of::<i32>()
+    }
+
+    fn iter_vaos(&self) -> impl Iterator<Item = &ChunkVao> {
+        self.opaque
+            .iter()
+            .chain(self.cutout.iter())
+            .chain(self.translucent.iter().map(|(vao, _)| vao))
+    }
+}
+
+impl TranslucentFaces {
+    /* sorts faces back to front if the eye moved enough since last time, returns whether it did */
+    fn sort_if_needed(&mut self, eye: Vec3) -> bool {
+        if let Some(sorted_from) = self.sorted_from {
+            if sorted_from.distance_squared(eye)
+                < TRANSLUCENT_RESORT_DISTANCE * TRANSLUCENT_RESORT_DISTANCE
+            {
+                return false;
+            }
+        }
+        sort_faces_back_to_front(&mut self.data, eye);
+        self.sorted_from = Some(eye);
+        true
+    }
 }
 
 impl WorldRenderData {
@@ -74,68 +176,194 @@ impl WorldRenderData {
 
     pub fn compile(
         &mut self,
-        gl: &glow::Context,
+        gl: &GlContext,
         player_chunk_pos: ChunkPos,
-        loaded_chunks: &HashMap<ChunkPos, Option<ChunkVao>>,
+        loaded_chunks: &HashMap<ChunkPos, Option<ChunkMesh>>,
     ) {
         self.chunks_to_draw.clear();
         for data in loaded_chunks
             .iter()
-            .filter(|(_, c)| c.is_some())
-            .map(|(pos, c)| (*pos, c.unwrap()))
+            .filter_map(|(pos, c)| c.as_ref().map(|c| (*pos, c.clone())))
         {
             self.chunks_to_draw.push(data);
         }
     }
 
+    /* the previous graphics are kept if the new ones don't compile */
     pub fn setup_graphics(
         &mut self,
-        gl: &glow::Context,
-        texture: Rc<(TextureType, glow::WebTextureKey)>,
+        gl: &GlContext,
+        appearance: &BlockAppearance,
     ) -> Result<(), String> {
-        let program = compile_shader(gl)?;
-        self.graphics = Some(GraphicContext { program, texture });
+        let texture = appearance.texture.clone();
+        let mut def = chunk_shader_def();
+        if appearance.shaders.iter().any(Option::is_some) {
+            let (vertex, fragment) = def.get_sources();
+            let [new_vertex, new_fragment] = &appearance.shaders;
+            let vertex = new_vertex.clone().unwrap_or(vertex.to_string());
+            let fragment = new_fragment.clone().unwrap_or(fragment.to_string());
+            def = def.with_sources(vertex, fragment);
+        }
+        let mut variants = ShaderVariants::new(def);
+        let opaque = UniformValue::Float(1.0);
+        let material = Material::new(&variants.get(gl, vec![])?)
+            .with_texture("u_texture", texture.clone())?
+            .with_shadow_map()?
+            .with_uniform("opacity", opaque)?;
+        let translucent_material = material
+            .clone()
+            .with_uniform("opacity", UniformValue::Float(TRANSLUCENT_OPACITY))?;
+        let cutout_defines = vec![CUTOUT_DEFINE];
+        let cutout_material = Material::new(&variants.get(gl, cutout_defines.clone())?)
+            .with_texture("u_texture", texture.clone())?
+            .with_shadow_map()?
+            .with_uniform("opacity", opaque)?;
+        let mut shadow_variants = ShaderVariants::new(chunk_shadow_shader_def());
+        let shadow_material = Material::new(&shadow_variants.get(gl, vec![])?);
+        let cutout_shadow_material = Material::new(&shadow_variants.get(gl, cutout_defines)?)
+            .with_texture("u_texture", texture)?;
+        self.graphics = Some(GraphicContext {
+            material,
+            cutout_material,
+            translucent_material,
+            shadow_material,
+            cutout_shadow_material,
+        });
         Ok(())
     }
 
-    pub fn render(&self, gl: &glow::Context, camera: &Camera) {
+    /* opaque and cutout faces cast shadows, translucent ones let the light through */
+    pub fn render_shadows(&self, gl: &GlContext, shadow_map: &ShadowMap) {
+        if let Some(graphics) = &self.graphics {
+            let chunk_radius = CHUNK_SIZE as f32 * 3.0_f32.sqrt() / 2.0;
+            let casting_chunks: Vec<_> = self
+                .chunks_to_draw
+                .iter()
+                .filter(|(chunk_pos, _)| {
+                    let chunk_center = chunk_pos.get_center_block_pos().as_vec3()
+                        + Vec3::splat(CHUNK_SIZE as f32 / 2.0);
+                    shadow_map.may_cast_shadow(chunk_center, chunk_radius)
+                })
+                .collect();
+            let world_pos_position = use_material(gl, &graphics.shadow_material);
+            for (chunk_pos, mesh) in casting_chunks.iter() {
+                if let Some(vao) = &mesh.opaque {
+                    set_world_position(gl, world_pos_position.as_ref(), *chunk_pos);
+                    vao.draw(gl);
+                }
+            }
+
+            let world_pos_position = use_material(gl, &graphics.cutout_shadow_material);
+            for (chunk_pos, mesh) in casting_chunks.iter() {
+                if let Some(vao) = &mesh.cutout {
+                    set_world_position(gl, world_pos_position.as_ref(), *chunk_pos);
+                    vao.draw(gl);
+                }
+            }
+        }
+    }
+
+    pub fn render(&mut self, gl: &GlContext, camera: &Camera) {
         if let Some(graphics) = &self.graphics {
             unsafe {
-                let program = &graphics.program;
-                program.gl_use(gl);
-                gl.bind_texture(glow::TEXTURE_2D_ARRAY, Some(graphics.texture.1));
-                program.set_matrix(gl, UniformTypes::ViewMatrix, &camera.look_at);
-                program.set_matrix(gl, UniformTypes::ProjMatrix, &camera.projection);
-                let world_pos_position = program.get_uniform_location(UniformTypes::WorldPosition);
-                gl.enable(glow::CULL_FACE);
-                gl.enable(glow::DEPTH_TEST);
-                gl.disable(glow::BLEND);
-                for (chunk_pos, vao) in self.chunks_to_draw.iter() {
-                    let block_pos = chunk_pos.get_center_block_pos();
-                    let world_pos = block_pos.as_vec().as_vec3();
-                    gl.uniform_3_f32(world_pos_position, world_pos.x, world_pos.y, world_pos.z);
-
-                    gl.bind_vertex_array(Some(vao.vertex_array));
-                    gl.draw_arrays(glow::TRIANGLES, 0, vao.vertex_count as _);
+                gl.set_flag(GlRenderFlags::CullFace, true);
+                gl.set_flag(GlRenderFlags::DepthTest, true);
+                gl.set_flag(GlRenderFlags::Blend, false);
+
+                // opaque pass
+                let world_pos_position = use_material(gl, &graphics.material);
+                for (chunk_pos, mesh) in self.chunks_to_draw.iter() {
+                    if let Some(vao) = &mesh.opaque {
+                        set_world_position(gl, world_pos_position.as_ref(), *chunk_pos);
+                        vao.draw(gl);
+                    }
+                }
+
+                // cutout pass: alpha tested, no blending needed
+                let world_pos_position = use_material(gl, &graphics.cutout_material);
+                for (chunk_pos, mesh) in self.chunks_to_draw.iter() {
+                    if let Some(vao) = &mesh.cutout {
+                        set_world_position(gl, world_pos_position.as_ref(), *chunk_pos);
+                        vao.draw(gl);
+                    }
                 }
+
+                // translucent pass: back to front, blended, without writing depth
+                let eye = camera.get_position();
+                let mut translucent_chunks: Vec<_> = self
+                    .chunks_to_draw
+                    .iter()
+                    .filter_map(|(pos, mesh)| mesh.translucent.as_ref().map(|t| (*pos, t)))
+                    .map(|(pos, t)| {
+                        (
+                            pos.get_center_block_pos().as_vec3().distance_squared(eye),
+                            pos,
+                            t,
+                        )
+                    })
+                    .collect();
+                translucent_chunks.sort_by(|(a, _, _), (b, _, _)| b.total_cmp(a));
+
+                let world_pos_position = use_material(gl, &graphics.translucent_material);
+                gl.set_flag(GlRenderFlags::Blend, true);
+                gl.set_blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+                gl.depth_mask(false);
+                for (_, chunk_pos, (vao, faces)) in translucent_chunks {
+                    let chunk_eye = eye - chunk_pos.get_center_block_pos().as_vec3();
+                    let mut faces = faces.borrow_mut();
+                    if faces.sort_if_needed(chunk_eye) {
+                        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vao.vertex_buffer.key()));
+                        gl.buffer_sub_data_u8_slice(
+                            glow::ARRAY_BUFFER,
+                            0,
+                            faces.data.align_to::<u8>().1,
+                        );
+                        gl.bind_buffer(glow::ARRAY_BUFFER, None);
+                    }
+                    set_world_position(gl, world_pos_position.as_ref(), chunk_pos);
+                    vao.draw(gl);
+                }
+                gl.depth_mask(true);
+                gl.set_flag(GlRenderFlags::Blend, false);
             }
         }
     }
 }
 
-fn compile_shader(gl: &glow::Context) -> Result<Rc<ShaderProgram>, String> {
-    unsafe {
-        let program = shader_def!(
-            "chunk.vert",
-            "chunk.frag",
-            vec!(),
-            vec!(
-                (UniformTypes::WorldPosition, "world_pos"),
-                (UniformTypes::ViewMatrix, "view"),
-                (UniformTypes::ProjMatrix, "projection"),
-            )
-        )
-        .compile(gl)?;
-        Ok(Rc::new(program))
-    }
+/* applies the material of a pass, returns where the position of each chunk goes */
+fn use_material(gl: &GlContext, material: &Material) -> Option<glow::UniformLocation> {
+    material.apply(gl);
+    material
+        .get_program()
+        .get_uniform_location(UniformTypes::WorldPosition)
+        .cloned()
+}
+
+fn set_world_position(
+    gl: &GlContext,
+    location: Option<&glow::UniformLocation>,
+    chunk_pos: ChunkPos,
+) {
+    let world_pos = chunk_pos.get_center_block_pos().as_vec3();
+    unsafe { gl.uniform_3_f32(location, world_pos.x, world_pos.y, world_pos.z) };
+}
+
+fn chunk_shader_def() -> ShaderDef {
+    shader_def!(
+        "chunk.vert",
+        "chunk.frag",
+        vec!((UniformTypes::WorldPosition, "world_pos"))
+    )
+    .with_attributes(vec![CHUNK_DATA_ATTRIBUTE])
+    .with_uniform_blocks(vec![FRAME_DATA_BLOCK])
+}
+
+fn chunk_shadow_shader_def() -> ShaderDef {
+    shader_def!(
+        "chunk_shadow.vert",
+        "chunk_shadow.frag",
+        vec!((UniformTypes::WorldPosition, "world_pos"))
+    )
+    .with_attributes(vec![CHUNK_DATA_ATTRIBUTE])
+    .with_uniform_blocks(vec![FRAME_DATA_BLOCK])
 }
@@ -0,0 +1,47 @@
+use backend::{
+    core::Game,
+    utils::{GlContext, RecordedObjectType, RecordingDevice},
+};
+
+const WIDTH: i32 = 1280;
+const HEIGHT: i32 = 720;
+/* fixed steps keep runs reproducible */
+const FRAME_TIME_MS: f64 = 1000.0 / 60.0;
+
+/* runs the simulation and chunk streaming without a window, drawing into a RecordingDevice.
+ * Fails if the game reports an error or uses the GL API wrongly */
+pub fn run(frames: u64) -> Result<(), String> {
+    let device = RecordingDevice::new();
+    let gl = GlContext::new(device.clone());
+    unsafe { gl.viewport(0, 0, WIDTH, HEIGHT) };
+
+    let mut game = Game::new()?;
+    unsafe { game.load(&gl)? };
+
+    let mut draws = 0;
+    for frame in 0..frames {
+        game.tick(&gl, frame as f64 * FRAME_TIME_MS)
+            .map_err(|e| format!("Frame {frame}: {e}"))?;
+        gl.end_frame();
+        draws = device.get_draws().len();
+
+        let errors = device.get_errors();
+        if !errors.is_empty() {
+            return Err(format!("Frame {frame}: GL errors:\n{}", errors.join("\n")));
+        }
+        /* only the last frame is kept, the recording would otherwise grow every frame */
+        device.clear();
+    }
+
+    println!("{frames} frames, {draws} draws in the last one");
+    println!(
+        "Live GL objects: {} buffers, {} vertex arrays, {} textures, {} programs, {} framebuffers",
+        device.count_live(RecordedObjectType::Buffer),
+        device.count_live(RecordedObjectType::VertexArray),
+        device.count_live(RecordedObjectType::Texture),
+        device.count_live(RecordedObjectType::Program),
+        device.count_live(RecordedObjectType::Framebuffer),
+    );
+    println!("{}", game.get_world_info());
+    Ok(())
+}
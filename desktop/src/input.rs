@@ -0,0 +1,196 @@
+use backend::core::{InputEventType, KeyEvent, Modifiers, MouseEvent, WheelEvent};
+use glam::Vec2;
+use winit::{
+    event::{ElementState, KeyEvent as WinitKeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{Key, KeyCode, ModifiersState, NamedKey, PhysicalKey},
+};
+
+/* pixels scrolled per wheel line, about what browsers report */
+const PIXELS_PER_LINE: f64 = 100.0;
+
+/* turns window events into the game's input events, which follow the DOM conventions.
+ * winit only reports changes, so the cursor, held buttons and modifiers are tracked here */
+#[derive(Debug, Default)]
+pub struct InputTranslator {
+    position: Vec2,
+    buttons: u16,
+    modifiers: Modifiers,
+}
+
+impl InputTranslator {
+    pub fn translate(&mut self, event: &WindowEvent) -> Option<InputEventType> {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = to_modifiers(modifiers.state());
+                None
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.position = Vec2::new(position.x as _, position.y as _);
+                Some(InputEventType::MouseMove(self.mouse_event(0)))
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let (button, mask) = match button {
+                    MouseButton::Left => (0, 1),
+                    MouseButton::Middle => (1, 4),
+                    MouseButton::Right => (2, 2),
+                    _ => return None,
+                };
+                match state {
+                    ElementState::Pressed => {
+                        self.buttons |= mask;
+                        Some(InputEventType::MouseDown(self.mouse_event(button)))
+                    }
+                    ElementState::Released => {
+                        self.buttons &= !mask;
+                        Some(InputEventType::MouseUp(self.mouse_event(button)))
+                    }
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                /* winit counts up when scrolling away from the user, the DOM counts down */
+                let delta_y = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => -*y as f64 * PIXELS_PER_LINE,
+                    MouseScrollDelta::PixelDelta(position) => -position.y,
+                };
+                Some(InputEventType::MouseWheel(WheelEvent { delta_y }))
+            }
+            WindowEvent::KeyboardInput { event, .. } => {
+                let key_event = self.key_event(event)?;
+                match event.state {
+                    ElementState::Pressed => Some(InputEventType::KeyDown(key_event)),
+                    ElementState::Released => Some(InputEventType::KeyUp(key_event)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn mouse_event(&self, button: i16) -> MouseEvent {
+        MouseEvent {
+            position: self.position,
+            button,
+            buttons: self.buttons,
+            modifiers: self.modifiers,
+        }
+    }
+
+    fn key_event(&self, event: &WinitKeyEvent) -> Option<KeyEvent> {
+        let code = match event.physical_key {
+            PhysicalKey::Code(code) => dom_code(code),
+            PhysicalKey::Unidentified(_) => return None,
+        };
+        let key = match &event.logical_key {
+            Key::Named(NamedKey::Space) => " ".to_string(),
+            Key::Named(NamedKey::Super) => "Meta".to_string(),
+            Key::Named(named) => format!("{named:?}"),
+            Key::Character(text) => text.to_string(),
+            _ => "Unidentified".to_string(),
+        };
+        Some(KeyEvent {
+            key,
+            code: code.to_string(),
+            repeat: event.repeat,
+            modifiers: self.modifiers,
+        })
+    }
+}
+
+fn to_modifiers(state: ModifiersState) -> Modifiers {
+    Modifiers {
+        shift: state.shift_key(),
+        ctrl: state.control_key(),
+        alt: state.alt_key(),
+        meta: state.super_key(),
+    }
+}
+
+/* the KeyboardEvent.code of the keys the game and the GUI use, spelled out because winit's
+ * names differ for some keys, like SuperLeft for MetaLeft */
+fn dom_code(code: KeyCode) -> &'static str {
+    match code {
+        KeyCode::KeyA => "KeyA",
+        KeyCode::KeyB => "KeyB",
+        KeyCode::KeyC => "KeyC",
+        KeyCode::KeyD => "KeyD",
+        KeyCode::KeyE => "KeyE",
+        KeyCode::KeyF => "KeyF",
+        KeyCode::KeyG => "KeyG",
+        KeyCode::KeyH => "KeyH",
+        KeyCode::KeyI => "KeyI",
+        KeyCode::KeyJ => "KeyJ",
+        KeyCode::KeyK => "KeyK",
+        KeyCode::KeyL => "KeyL",
+        KeyCode::KeyM => "KeyM",
+        KeyCode::KeyN => "KeyN",
+        KeyCode::KeyO => "KeyO",
+        KeyCode::KeyP => "KeyP",
+        KeyCode::KeyQ => "KeyQ",
+        KeyCode::KeyR => "KeyR",
+        KeyCode::KeyS => "KeyS",
+        KeyCode::KeyT => "KeyT",
+        KeyCode::KeyU => "KeyU",
+        KeyCode::KeyV => "KeyV",
+        KeyCode::KeyW => "KeyW",
+        KeyCode::KeyX => "KeyX",
+        KeyCode::KeyY => "KeyY",
+        KeyCode::KeyZ => "KeyZ",
+        KeyCode::Digit0 => "Digit0",
+        KeyCode::Digit1 => "Digit1",
+        KeyCode::Digit2 => "Digit2",
+        KeyCode::Digit3 => "Digit3",
+        KeyCode::Digit4 => "Digit4",
+        KeyCode::Digit5 => "Digit5",
+        KeyCode::Digit6 => "Digit6",
+        KeyCode::Digit7 => "Digit7",
+        KeyCode::Digit8 => "Digit8",
+        KeyCode::Digit9 => "Digit9",
+        KeyCode::F1 => "F1",
+        KeyCode::F2 => "F2",
+        KeyCode::F3 => "F3",
+        KeyCode::F4 => "F4",
+        KeyCode::F5 => "F5",
+        KeyCode::F6 => "F6",
+        KeyCode::F7 => "F7",
+        KeyCode::F8 => "F8",
+        KeyCode::F9 => "F9",
+        KeyCode::F10 => "F10",
+        KeyCode::F11 => "F11",
+        KeyCode::F12 => "F12",
+        KeyCode::Escape => "Escape",
+        KeyCode::Tab => "Tab",
+        KeyCode::Enter => "Enter",
+        KeyCode::Space => "Space",
+        KeyCode::Backspace => "Backspace",
+        KeyCode::Delete => "Delete",
+        KeyCode::Insert => "Insert",
+        KeyCode::Home => "Home",
+        KeyCode::End => "End",
+        KeyCode::PageUp => "PageUp",
+        KeyCode::PageDown => "PageDown",
+        KeyCode::ArrowUp => "ArrowUp",
+        KeyCode::ArrowDown => "ArrowDown",
+        KeyCode::ArrowLeft => "ArrowLeft",
+        KeyCode::ArrowRight => "ArrowRight",
+        KeyCode::ShiftLeft => "ShiftLeft",
+        KeyCode::ShiftRight => "ShiftRight",
+        KeyCode::ControlLeft => "ControlLeft",
+        KeyCode::ControlRight => "ControlRight",
+        KeyCode::AltLeft => "AltLeft",
+        KeyCode::AltRight => "AltRight",
+        KeyCode::CapsLock => "CapsLock",
+        KeyCode::Minus => "Minus",
+        KeyCode::Equal => "Equal",
+        KeyCode::BracketLeft => "BracketLeft",
+        KeyCode::BracketRight => "BracketRight",
+        KeyCode::Backslash => "Backslash",
+        KeyCode::Semicolon => "Semicolon",
+        KeyCode::Quote => "Quote",
+        KeyCode::Backquote => "Backquote",
+        KeyCode::Comma => "Comma",
+        KeyCode::Period => "Period",
+        KeyCode::Slash => "Slash",
+        KeyCode::SuperLeft => "MetaLeft",
+        KeyCode::SuperRight => "MetaRight",
+        _ => "Unidentified",
+    }
+}
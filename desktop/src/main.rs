@@ -0,0 +1,66 @@
+use std::{env, path::PathBuf, process::ExitCode};
+
+mod headless;
+mod input;
+mod window;
+
+const USAGE: &str = "usage: desktop [--headless --frames N] [--assets DIR]";
+
+#[derive(Debug)]
+struct Args {
+    headless: bool,
+    frames: Option<u64>,
+    /* textures are looked up relative to this directory, like the web build serves `public` */
+    assets: PathBuf,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        headless: false,
+        frames: None,
+        assets: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../public"),
+    };
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--headless" => args.headless = true,
+            "--frames" => {
+                let frames = iter.next().ok_or("--frames needs a count")?;
+                let frames = frames
+                    .parse()
+                    .map_err(|e| format!("Invalid frame count {frames}: {e}"))?;
+                args.frames = Some(frames);
+            }
+            "--assets" => args.assets = iter.next().ok_or("--assets needs a directory")?.into(),
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    if args.headless && args.frames.is_none() {
+        return Err("--headless needs --frames".into());
+    }
+    Ok(args)
+}
+
+fn run(args: Args) -> Result<(), String> {
+    env::set_current_dir(&args.assets)
+        .map_err(|e| format!("Can't use {:?} as asset directory: {e}", args.assets))?;
+    match args.headless {
+        true => headless::run(args.frames.unwrap_or_default()),
+        false => window::run(args.frames),
+    }
+}
+
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let result = parse_args()
+        .map_err(|e| format!("{e}\n{USAGE}"))
+        .and_then(run);
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
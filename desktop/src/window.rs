@@ -0,0 +1,116 @@
+use std::{num::NonZeroU32, time::Instant};
+
+use backend::{core::Game, utils::GlContext};
+use glutin::{
+    config::ConfigTemplateBuilder,
+    context::{ContextApi, ContextAttributesBuilder, NotCurrentGlContext, Version},
+    display::{GetGlDisplay, GlDisplay},
+    surface::{GlSurface, SwapInterval},
+};
+use glutin_winit::{DisplayBuilder, GlWindow};
+use log::error;
+use raw_window_handle::HasRawWindowHandle;
+use winit::{
+    dpi::LogicalSize,
+    event::{Event, WindowEvent},
+    event_loop::EventLoop,
+    window::WindowBuilder,
+};
+
+use crate::input::InputTranslator;
+
+const TITLE: &str = "rust-wasm-gl";
+const WIDTH: u32 = 1280;
+const HEIGHT: u32 = 720;
+
+/* opens a window with a GL ES 3 context, the same API as WebGL2, and runs the game in it.
+ * Stops after `frames` frames if given, which helps comparing profiles */
+pub fn run(frames: Option<u64>) -> Result<(), String> {
+    let event_loop = EventLoop::new().map_err(|e| format!("Can't create event loop: {e}"))?;
+    let window_builder = WindowBuilder::new()
+        .with_title(TITLE)
+        .with_inner_size(LogicalSize::new(WIDTH, HEIGHT));
+    let template = ConfigTemplateBuilder::new()
+        .with_alpha_size(8)
+        .with_depth_size(24);
+    let (window, gl_config) = DisplayBuilder::new()
+        .with_window_builder(Some(window_builder))
+        .build(&event_loop, template, |mut configs| configs.next().unwrap())
+        .map_err(|e| format!("Can't create window: {e}"))?;
+    let window = window.ok_or("Can't create window")?;
+
+    let gl_display = gl_config.display();
+    let context_attributes = ContextAttributesBuilder::new()
+        .with_context_api(ContextApi::Gles(Some(Version::new(3, 0))))
+        .build(Some(window.raw_window_handle()));
+    let surface_attributes = window.build_surface_attributes(Default::default());
+    let (surface, context) = unsafe {
+        let surface = gl_display
+            .create_window_surface(&gl_config, &surface_attributes)
+            .map_err(|e| format!("Can't create window surface: {e}"))?;
+        let context = gl_display
+            .create_context(&gl_config, &context_attributes)
+            .map_err(|e| format!("Can't create GL ES 3 context: {e}"))?
+            .make_current(&surface)
+            .map_err(|e| format!("Can't make context current: {e}"))?;
+        (surface, context)
+    };
+    if let Err(e) = surface.set_swap_interval(&context, SwapInterval::Wait(NonZeroU32::MIN)) {
+        error!("Can't enable vsync: {e}");
+    }
+
+    let gl = GlContext::new(unsafe {
+        glow::Context::from_loader_function_cstr(|name| gl_display.get_proc_address(name))
+    });
+    let size = window.inner_size();
+    unsafe { gl.viewport(0, 0, size.width as _, size.height as _) };
+
+    let mut game = Game::new()?;
+    unsafe { game.load(&gl)? };
+
+    let mut inputs = InputTranslator::default();
+    let start = Instant::now();
+    let mut frame = 0;
+    let mut result = Ok(());
+    event_loop
+        .run(|event, target| match event {
+            Event::WindowEvent { event, .. } => {
+                if let Some(input) = inputs.translate(&event) {
+                    game.push_input(input);
+                }
+                match event {
+                    WindowEvent::CloseRequested => target.exit(),
+                    WindowEvent::Resized(size) => {
+                        let (Some(width), Some(height)) =
+                            (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+                        else {
+                            return;
+                        };
+                        surface.resize(&context, width, height);
+                        unsafe { gl.viewport(0, 0, size.width as _, size.height as _) };
+                    }
+                    WindowEvent::RedrawRequested => {
+                        let time = start.elapsed().as_secs_f64() * 1000.0;
+                        if let Err(e) = game.tick(&gl, time) {
+                            result = Err(e);
+                            target.exit();
+                            return;
+                        }
+                        gl.end_frame();
+                        if let Err(e) = surface.swap_buffers(&context) {
+                            error!("Can't swap buffers: {e}");
+                        }
+                        frame += 1;
+                        if frames.is_some_and(|frames| frame >= frames) {
+                            target.exit();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::AboutToWait => window.request_redraw(),
+            _ => {}
+        })
+        .map_err(|e| format!("Event loop error: {e}"))?;
+    result
+}
@@ -0,0 +1,6 @@
+/* game logic with no dependency on the platform or on GL, shared by the web build and native
+ * tests */
+
+pub mod math;
+pub mod time;
+pub mod world;
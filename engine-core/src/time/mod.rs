@@ -0,0 +1,5 @@
+mod time;
+mod timeofday;
+
+pub use time::Time;
+pub use timeofday::*;
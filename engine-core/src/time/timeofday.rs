@@ -0,0 +1,63 @@
+use std::f32::consts::{PI, TAU};
+
+use glam::{vec3, Vec3};
+
+use super::Time;
+
+pub const HOURS_PER_DAY: f32 = 24.0;
+const DEFAULT_DAY_LENGTH_MS: f64 = 10.0 * 60.0 * 1000.0;
+const DEFAULT_HOURS: f32 = 10.0;
+/* tilt of the sun path so that it never goes exactly overhead */
+const SUN_PATH_TILT: f32 = 0.3;
+
+#[derive(Debug, Clone)]
+pub struct TimeOfDay {
+    hours: f32,
+    day_length_ms: f64,
+    pub frozen: bool,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            hours: DEFAULT_HOURS,
+            day_length_ms: DEFAULT_DAY_LENGTH_MS,
+            frozen: false,
+        }
+    }
+}
+
+impl TimeOfDay {
+    pub fn update(&mut self, time: &Time) {
+        if self.frozen {
+            return;
+        }
+        let elapsed_hours = time.delta_time() / self.day_length_ms * HOURS_PER_DAY as f64;
+        self.set_hours(self.hours + elapsed_hours as f32);
+    }
+
+    pub fn get_hours(&self) -> f32 {
+        self.hours
+    }
+
+    pub fn set_hours(&mut self, hours: f32) {
+        self.hours = hours.rem_euclid(HOURS_PER_DAY);
+    }
+
+    /* direction pointing towards the sun, rising in +X at 6:00 and setting in -X at 18:00 */
+    pub fn get_sun_position(&self) -> Vec3 {
+        let angle = self.hours / HOURS_PER_DAY * TAU - PI / 2.0;
+        vec3(angle.cos(), angle.sin(), SUN_PATH_TILT).normalize()
+    }
+
+    /* the moon is always opposite the sun */
+    pub fn get_moon_position(&self) -> Vec3 {
+        -self.get_sun_position()
+    }
+
+    /* 0.0 at night, 1.0 during the day, smooth in between around sunrise and sunset */
+    pub fn get_daylight(&self) -> f32 {
+        let t = (self.get_sun_position().y * 4.0 + 0.5).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
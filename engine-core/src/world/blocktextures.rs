@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+/* chunk vertices keep 6 bits for the texture layer, see generate_mesh */
+pub const MAX_BLOCK_TEXTURE_LAYERS: u32 = 64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BlockSideTexture {
+    Unknown = 0,
+    GrassSide,
+    Cobblestone,
+    RedStone,
+    TreeBark,
+    Sand,
+    Dirt,
+    Pickaxe,
+    TreeCenter,
+    GrassTop,
+    Coal,
+    Lava,
+    Diamond,
+    Iron,
+    Gold,
+    Dirt2,
+    Glass,
+    Water,
+}
+
+pub const BLOCK_SIDE_TEXTURE_COUNT: usize = 18;
+
+impl BlockSideTexture {
+    /* in the order of the layers of the default pack: blocks_atlas.png, then the images added
+     * on top of it */
+    pub const ALL: [BlockSideTexture; BLOCK_SIDE_TEXTURE_COUNT] = [
+        BlockSideTexture::Unknown,
+        BlockSideTexture::GrassSide,
+        BlockSideTexture::Cobblestone,
+        BlockSideTexture::RedStone,
+        BlockSideTexture::TreeBark,
+        BlockSideTexture::Sand,
+        BlockSideTexture::Dirt,
+        BlockSideTexture::Pickaxe,
+        BlockSideTexture::TreeCenter,
+        BlockSideTexture::GrassTop,
+        BlockSideTexture::Coal,
+        BlockSideTexture::Lava,
+        BlockSideTexture::Diamond,
+        BlockSideTexture::Iron,
+        BlockSideTexture::Gold,
+        BlockSideTexture::Dirt2,
+        BlockSideTexture::Glass,
+        BlockSideTexture::Water,
+    ];
+
+    /* the name of the image the texture is looked up by, like its file name */
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            BlockSideTexture::Unknown => "unknown",
+            BlockSideTexture::GrassSide => "grass_block_side",
+            BlockSideTexture::Cobblestone => "cobblestone",
+            BlockSideTexture::RedStone => "redstone_ore",
+            BlockSideTexture::TreeBark => "tree_bark",
+            BlockSideTexture::Sand => "sand",
+            BlockSideTexture::Dirt => "dirt",
+            BlockSideTexture::Pickaxe => "pickaxe",
+            BlockSideTexture::TreeCenter => "tree_center",
+            BlockSideTexture::GrassTop => "grass_block_top",
+            BlockSideTexture::Coal => "coal_ore",
+            BlockSideTexture::Lava => "lava",
+            BlockSideTexture::Diamond => "diamond_ore",
+            BlockSideTexture::Iron => "iron_ore",
+            BlockSideTexture::Gold => "gold_ore",
+            BlockSideTexture::Dirt2 => "coarse_dirt",
+            BlockSideTexture::Glass => "glass",
+            BlockSideTexture::Water => "water",
+        }
+    }
+}
+
+/* the layer of the block texture array each BlockSideTexture is drawn with */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BlockTextureLayers([u8; BLOCK_SIDE_TEXTURE_COUNT]);
+
+/* the layers of the default pack */
+impl Default for BlockTextureLayers {
+    fn default() -> Self {
+        Self(BlockSideTexture::ALL.map(|texture| texture as u8))
+    }
+}
+
+impl BlockTextureLayers {
+    /* looks every texture up by name, the missing ones are drawn with the "unknown" layer,
+     * or the first one if there is none */
+    pub fn from_names(layers: &HashMap<String, u32>) -> Result<Self, String> {
+        if let Some((name, layer)) = layers
+            .iter()
+            .find(|(_, layer)| **layer >= MAX_BLOCK_TEXTURE_LAYERS)
+        {
+            return Err(format!(
+                "Block texture {name} is in layer {layer}, chunks can only use {} layers",
+                MAX_BLOCK_TEXTURE_LAYERS
+            ));
+        }
+        let unknown = layers
+            .get(BlockSideTexture::Unknown.get_name())
+            .copied()
+            .unwrap_or(0);
+        Ok(Self(BlockSideTexture::ALL.map(|texture| {
+            layers.get(texture.get_name()).copied().unwrap_or(unknown) as u8
+        })))
+    }
+
+    pub fn get_layer(&self, texture: BlockSideTexture) -> u8 {
+        self.0[texture as usize]
+    }
+}
@@ -0,0 +1,430 @@
+use fastrand::Rng;
+use glam::{vec3, IVec3, U16Vec3, Vec3};
+
+use super::{BlockSideTexture, BlockTextureLayers, Side};
+
+use super::{ChunkLight, LightLevel, BLOCKS_PER_CHUNK, CHUNK_SIZE, MAX_LIGHT};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BlockType {
+    Empty = 0,
+    Grass,
+    Dirt,
+    Stone,
+    Sand,
+    Lava,
+    Diamond,
+    Coal,
+    Gold,
+    Water,
+    Glass,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BlockRenderLayer {
+    Opaque,
+    Cutout,
+    Translucent,
+}
+
+impl BlockType {
+    pub fn render_layer(&self) -> BlockRenderLayer {
+        match self {
+            BlockType::Water => BlockRenderLayer::Translucent,
+            BlockType::Glass => BlockRenderLayer::Cutout,
+            _ => BlockRenderLayer::Opaque,
+        }
+    }
+
+    pub fn is_transparent_to_light(&self) -> bool {
+        *self == BlockType::Empty || self.render_layer() != BlockRenderLayer::Opaque
+    }
+
+    pub fn get_light_emission(&self) -> u8 {
+        match self {
+            BlockType::Lava => MAX_LIGHT,
+            _ => 0,
+        }
+    }
+
+    /* whether a face of this block is visible through the given neighbour */
+    fn is_face_visible_through(&self, neighbour: BlockType) -> bool {
+        match neighbour {
+            BlockType::Empty => true,
+            n if n == *self => false,
+            n => n.render_layer() != BlockRenderLayer::Opaque,
+        }
+    }
+}
+
+impl Into<BlockType> for u8 {
+    fn into(self) -> BlockType {
+        match self {
+            0 => BlockType::Empty,
+            1 => BlockType::Grass,
+            2 => BlockType::Dirt,
+            3 => BlockType::Stone,
+            4 => BlockType::Sand,
+            5 => BlockType::Lava,
+            6 => BlockType::Diamond,
+            7 => BlockType::Coal,
+            8 => BlockType::Gold,
+            9 => BlockType::Water,
+            10 => BlockType::Glass,
+            _ => BlockType::Empty,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ChunkVertexData {
+    pub opaque: Vec<i32>,
+    pub cutout: Vec<i32>,
+    pub translucent: Vec<i32>,
+}
+
+impl ChunkVertexData {
+    /* bytes the mesh takes once uploaded, one i32 per vertex */
+    pub fn byte_size(&self) -> usize {
+        let vertex_count = self.opaque.len() + self.cutout.len() + self.translucent.len();
+        vertex_count * std::mem::size_of::<i32>()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub blocks: [BlockType; BLOCKS_PER_CHUNK],
+    pub light: ChunkLight,
+    is_emtpy: bool,
+}
+
+const OPTIMIZATION_LEVEL: usize = 1;
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Chunk {
+            blocks: [BlockType::Empty; BLOCKS_PER_CHUNK],
+            light: Default::default(),
+            is_emtpy: true,
+        }
+    }
+}
+
+impl Chunk {
+    pub fn new(is_emtpy: bool) -> Chunk {
+        Chunk {
+            is_emtpy,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.is_emtpy
+    }
+
+    pub fn random(rng: &mut Rng) -> Chunk {
+        let mut res = Self::new(false);
+        for i in 0..BLOCKS_PER_CHUNK {
+            res.blocks[i] = Into::<BlockType>::into(rng.u8(..9));
+        }
+        res
+    }
+
+    pub fn empty() -> Chunk {
+        Default::default()
+    }
+
+    pub fn plain(block: BlockType) -> Chunk {
+        let mut res = Self::new(block == BlockType::Empty);
+        for i in 0..BLOCKS_PER_CHUNK {
+            res.blocks[i] = block;
+        }
+        res
+    }
+
+    // pub fn at(&self, offset: U16Vec3) -> &BlockType {
+    //     &self.blocks[chunk_index_from_offset(&offset)]
+    // }
+
+    pub fn set(&mut self, offset: U16Vec3, block: BlockType) {
+        self.blocks[chunk_index_from_offset(&offset)] = block;
+        if block != BlockType::Empty {
+            self.is_emtpy = false;
+        }
+    }
+
+    // fn at_mut(&mut self, offset: U16Vec3) -> &mut BlockType {
+    //     &mut self.blocks[chunk_index_from_offset(&offset)]
+    // }
+
+    // pub fn dirt_with_grass_on_top(chunk_pos: IVec3) -> Chunk {
+    //     let mut res = Self::plain(chunk_pos, BlockType::Dirt);
+    //     for x in 0..CHUNK_SIZE {
+    //         for z in 0..CHUNK_SIZE {
+    //             *res.at_mut(U16Vec3::new(x as _, CHUNK_SIZE as u16 - 1, z as _)) = BlockType::Grass;
+    //         }
+    //     }
+    //     res
+    // }
+
+    pub fn get_block(&self, offset: U16Vec3) -> BlockType {
+        self.blocks[chunk_index_from_offset(&offset)]
+    }
+
+    pub fn get_light(&self, offset: U16Vec3) -> LightLevel {
+        self.light.get(chunk_index_from_offset(&offset))
+    }
+
+    pub fn set_light(&mut self, offset: U16Vec3, light: LightLevel) {
+        self.light.set(chunk_index_from_offset(&offset), light);
+    }
+
+    /* outside_light gives the light of blocks next to the chunk, from their offset to this chunk */
+    pub fn to_vertex_data<F>(
+        &self,
+        layers: &BlockTextureLayers,
+        outside_light: F,
+    ) -> ChunkVertexData
+    where
+        F: Fn(IVec3) -> LightLevel,
+    {
+        if self.is_empty() {
+            return Default::default();
+        }
+        let mut opaque_sides: Vec<ChunkSideData> = Vec::new();
+        let mut cutout_sides: Vec<ChunkSideData> = Vec::new();
+        let mut translucent_sides: Vec<ChunkSideData> = Vec::new();
+        for x in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    let offset = U16Vec3::new(x as _, y as _, z as _);
+                    let block = self.get_block(offset);
+                    let (top, side, bottom) = match block {
+                        BlockType::Empty => continue,
+                        t => t.into(),
+                    };
+                    let sides = match block.render_layer() {
+                        BlockRenderLayer::Opaque => &mut opaque_sides,
+                        BlockRenderLayer::Cutout => &mut cutout_sides,
+                        BlockRenderLayer::Translucent => &mut translucent_sides,
+                    };
+
+                    for (side_type, direction) in SIDE_DIRECTIONS {
+                        let neighbour = offset.as_ivec3() + direction;
+                        let is_inside = neighbour.cmpge(IVec3::ZERO).all()
+                            && neighbour.cmplt(IVec3::splat(CHUNK_SIZE as i32)).all();
+                        // todo: don't generate hidden sides across chunks
+                        if OPTIMIZATION_LEVEL >= 1
+                            && is_inside
+                            && !block
+                                .is_face_visible_through(self.get_block(neighbour.as_u16vec3()))
+                        {
+                            continue;
+                        }
+                        // faces are lit by the block they are facing
+                        let light = match is_inside {
+                            true => self.get_light(neighbour.as_u16vec3()),
+                            false => outside_light(neighbour),
+                        };
+                        let texture = match side_type {
+                            Side::Top => top,
+                            Side::Bottom => bottom,
+                            _ => side,
+                        };
+                        sides.push((side_type, texture, offset, light));
+                    }
+                }
+            }
+        }
+        ChunkVertexData {
+            opaque: generate_mesh(opaque_sides, layers),
+            cutout: generate_mesh(cutout_sides, layers),
+            translucent: generate_mesh(translucent_sides, layers),
+        }
+    }
+}
+
+const SIDE_DIRECTIONS: [(Side, IVec3); 6] = [
+    (Side::Top, IVec3::Y),
+    (Side::Bottom, IVec3::NEG_Y),
+    (Side::Right, IVec3::X),
+    (Side::Left, IVec3::NEG_X),
+    (Side::Front, IVec3::Z),
+    (Side::Back, IVec3::NEG_Z),
+];
+
+// #[inline(always)]
+// fn offset_from_chunk_index(i: usize) -> U16Vec3 {
+//     U16Vec3::new(
+//         (i % CHUNK_SIZE) as _,
+//         (i / CHUNK_SIZE) as _,
+//         (i / (CHUNK_SIZE * CHUNK_SIZE)) as _,
+//     )
+// }
+
+#[inline(always)]
+fn chunk_index_from_offset(offset: &U16Vec3) -> usize {
+    offset.x as usize + offset.y as usize * CHUNK_SIZE + offset.z as usize * CHUNK_SIZE * CHUNK_SIZE
+}
+
+type BlockSideTextures = (BlockSideTexture, BlockSideTexture, BlockSideTexture);
+
+impl Into<BlockSideTextures> for BlockType {
+    fn into(self) -> BlockSideTextures {
+        match self {
+            BlockType::Grass => (
+                BlockSideTexture::GrassTop,
+                BlockSideTexture::GrassSide,
+                BlockSideTexture::Dirt,
+            ),
+            BlockType::Dirt => three_of(BlockSideTexture::Dirt),
+            BlockType::Stone => three_of(BlockSideTexture::Cobblestone),
+            BlockType::Sand => three_of(BlockSideTexture::Sand),
+            BlockType::Lava => three_of(BlockSideTexture::Lava),
+            BlockType::Diamond => three_of(BlockSideTexture::Diamond),
+            BlockType::Coal => three_of(BlockSideTexture::Coal),
+            BlockType::Gold => three_of(BlockSideTexture::Gold),
+            BlockType::Water => three_of(BlockSideTexture::Water),
+            BlockType::Glass => three_of(BlockSideTexture::Glass),
+            BlockType::Empty => three_of(BlockSideTexture::Unknown),
+            // _ => three_of(BlockSideTexture::Pickaxe),
+        }
+    }
+}
+
+fn three_of(t: BlockSideTexture) -> BlockSideTextures {
+    (t, t, t)
+}
+
+pub type ChunkSideData = (Side, BlockSideTexture, U16Vec3, LightLevel);
+
+pub const VERTICES_PER_SIDE: usize = 6;
+
+/* packs each vertex in an i32, read back in chunk.vert:
+ * bits 0-14: x, y, z (5 bits each), 15-17: face, 18-23: texture layer,
+ * 24-27: skylight, 28-31: block light */
+pub fn generate_mesh<I>(sides: I, layers: &BlockTextureLayers) -> Vec<i32>
+where
+    I: IntoIterator<Item = ChunkSideData>,
+    I::IntoIter: ExactSizeIterator,
+{
+    let iterator = sides.into_iter();
+    let size = iterator.len();
+    let mut data = Vec::<i32>::with_capacity(size * VERTICES_PER_SIDE);
+    for (side, texture, offset, light) in iterator {
+        for vert in SIDE_VERTICES[side as usize].get_quad_triangles() {
+            let norm = side as i32;
+            let pos = vert + offset;
+            let mut result: i32 = 0;
+            result |= (pos.x & 31) as i32;
+            result |= (pos.y as i32 & 31) << 5;
+            result |= (pos.z as i32 & 31) << 10;
+            result |= (norm & 7) << 15;
+            result |= (layers.get_layer(texture) as i32 & 63) << 18;
+            result |= (light.sky() as i32 & 15) << 24;
+            result |= (light.block() as i32 & 15) << 28;
+            data.push(result);
+        }
+    }
+    data
+}
+
+/* sorts faces made by generate_mesh from the furthest to the closest to eye (in chunk space) */
+pub fn sort_faces_back_to_front(data: &mut [i32], eye: Vec3) {
+    let mut faces: Vec<(f32, [i32; VERTICES_PER_SIDE])> = data
+        .chunks_exact(VERTICES_PER_SIDE)
+        .map(|face| {
+            let face: [i32; VERTICES_PER_SIDE] = face.try_into().unwrap();
+            (face_center(&face).distance_squared(eye), face)
+        })
+        .collect();
+    faces.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    for (dest, (_, face)) in data.chunks_exact_mut(VERTICES_PER_SIDE).zip(faces) {
+        dest.copy_from_slice(&face);
+    }
+}
+
+fn face_center(face: &[i32]) -> Vec3 {
+    let sum = face.iter().fold(Vec3::ZERO, |acc, data| {
+        acc + vec3(
+            (data & 31) as f32,
+            ((data >> 5) & 31) as f32,
+            ((data >> 10) & 31) as f32,
+        )
+    });
+    sum / face.len() as f32
+}
+
+//   C        D
+// A        B
+
+//   G        H
+// E        F
+
+const A: U16Vec3 = U16Vec3 { x: 0, y: 1, z: 1 };
+const B: U16Vec3 = U16Vec3 { x: 1, y: 1, z: 1 };
+const C: U16Vec3 = U16Vec3 { x: 0, y: 1, z: 0 };
+const D: U16Vec3 = U16Vec3 { x: 1, y: 1, z: 0 };
+const E: U16Vec3 = U16Vec3 { x: 0, y: 0, z: 1 };
+const F: U16Vec3 = U16Vec3 { x: 1, y: 0, z: 1 };
+const G: U16Vec3 = U16Vec3 { x: 0, y: 0, z: 0 };
+const H: U16Vec3 = U16Vec3 { x: 1, y: 0, z: 0 };
+
+pub struct SideVertices {
+    pub a: U16Vec3,
+    pub b: U16Vec3,
+    pub c: U16Vec3,
+    pub d: U16Vec3,
+}
+
+// enum Corner {
+//     TopLeft,
+//     TopRight,
+//     BotLeft,
+//     BotRight,
+// }
+
+impl SideVertices {
+    fn get_quad_triangles(&self) -> [U16Vec3; VERTICES_PER_SIDE] {
+        [self.a, self.b, self.c, self.a, self.c, self.d]
+    }
+}
+
+const SIDE_VERTICES: [SideVertices; 6] = [
+    SideVertices {
+        a: C,
+        b: A,
+        c: B,
+        d: D,
+    },
+    SideVertices {
+        a: E,
+        b: G,
+        c: H,
+        d: F,
+    },
+    SideVertices {
+        a: A,
+        b: E,
+        c: F,
+        d: B,
+    },
+    SideVertices {
+        a: D,
+        b: H,
+        c: G,
+        d: C,
+    },
+    SideVertices {
+        a: B,
+        b: F,
+        c: H,
+        d: D,
+    },
+    SideVertices {
+        a: C,
+        b: G,
+        c: E,
+        d: A,
+    },
+];
@@ -0,0 +1,578 @@
+use std::collections::{HashSet, VecDeque};
+
+use glam::{ivec2, ivec3, IVec2, IVec3, U16Vec3};
+use itertools::Itertools;
+use log::{info, warn};
+
+use crate::{
+    math::AABB,
+    world::{ChunkPos, PageChunkOffset, MAX_CHUNK_Y},
+};
+
+use super::{
+    is_in_world,
+    position::{PagePos, CHUNK_PAGE_SIZE, MIN_CHUNK_PAGE_OFFSET, NUM_CHUNKS_PER_PAGE},
+    propagate_light, update_light, BlockPos, BlockType, Chunk, ChunkLight, LightChannel,
+    LightLevel, LightStorage, WorldGenerator, BLOCKS_PER_CHUNK, CHUNK_SIZE, MAX_BLOCK_Y,
+    MIN_BLOCK_Y, MIN_CHUNK_Y,
+};
+
+const PAGE_LOAD_PER_FRAME: usize = 2;
+const MAX_NUM_PAGES: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct ChunkPage {
+    chunks: Vec<Chunk>,
+    position: PagePos,
+    content_bounds: AABB<IVec3>,
+}
+
+impl Default for ChunkPage {
+    fn default() -> Self {
+        Self {
+            chunks: vec![Chunk::default(); NUM_CHUNKS_PER_PAGE],
+            position: Default::default(),
+            content_bounds: Default::default(),
+        }
+    }
+}
+
+impl ChunkPage {
+    fn get_chunk(&self, chunk_pos: ChunkPos) -> Option<&Chunk> {
+        let (page_pos, offset) = chunk_pos.get_page_pos_with_offset();
+        debug_assert!(page_pos == self.position);
+        let index: usize = offset.as_page_index().into();
+        self.chunks.get(index)
+    }
+
+    fn get_chunk_mut(&mut self, chunk_pos: ChunkPos) -> Option<&mut Chunk> {
+        let (page_pos, offset) = chunk_pos.get_page_pos_with_offset();
+        debug_assert!(page_pos == self.position);
+        let index: usize = offset.as_page_index().into();
+        self.chunks.get_mut(index)
+    }
+
+    /* chunk position of the lowest corner of the page */
+    fn get_min_chunk_pos(&self) -> IVec3 {
+        self.position.get_center_chunk_pos().as_vec() + MIN_CHUNK_PAGE_OFFSET
+    }
+
+    /* lights columns open to the sky from the top, returns the lowest skylit y of each column */
+    fn fill_sky_light(&mut self) -> Vec<i32> {
+        let min_chunk = self.get_min_chunk_pos();
+        let page_width = CHUNK_PAGE_SIZE.x * CHUNK_SIZE as i32;
+        let page_depth = CHUNK_PAGE_SIZE.z * CHUNK_SIZE as i32;
+        let mut column_bottoms = vec![MIN_BLOCK_Y; (page_width * page_depth) as usize];
+        for chunk_x in 0..CHUNK_PAGE_SIZE.x {
+            for chunk_z in 0..CHUNK_PAGE_SIZE.z {
+                let mut lit = [true; CHUNK_SIZE * CHUNK_SIZE];
+                for chunk_y in (MIN_CHUNK_Y..MAX_CHUNK_Y).rev() {
+                    let chunk_pos: ChunkPos =
+                        ivec3(min_chunk.x + chunk_x, chunk_y, min_chunk.z + chunk_z).into();
+                    let chunk = self.get_chunk_mut(chunk_pos).expect("chunk not in page");
+                    if chunk.is_empty() && lit.iter().all(|l| *l) {
+                        chunk.light = ChunkLight::Uniform(LightLevel::FULL_SKY);
+                        continue;
+                    }
+                    chunk.light = Default::default();
+                    for y in (0..CHUNK_SIZE).rev() {
+                        for (i, column_lit) in lit.iter_mut().enumerate() {
+                            if !*column_lit {
+                                continue;
+                            }
+                            let (x, z) = (i % CHUNK_SIZE, i / CHUNK_SIZE);
+                            let offset = U16Vec3::new(x as _, y as _, z as _);
+                            if chunk.get_block(offset).is_transparent_to_light() {
+                                chunk.set_light(offset, LightLevel::FULL_SKY);
+                            } else {
+                                *column_lit = false;
+                                let x = chunk_x * CHUNK_SIZE as i32 + x as i32;
+                                let z = chunk_z * CHUNK_SIZE as i32 + z as i32;
+                                column_bottoms[(x + z * page_width) as usize] =
+                                    chunk_y * CHUNK_SIZE as i32 + y as i32 + 1;
+                            }
+                        }
+                    }
+                    if lit.iter().all(|l| !*l) {
+                        break;
+                    }
+                }
+            }
+        }
+        column_bottoms
+    }
+
+    /* skylit blocks next to a darker column, from where skylight has to spread sideways */
+    fn get_sky_light_sources(&self, column_bottoms: &[i32]) -> VecDeque<IVec3> {
+        let min_block = self.get_min_chunk_pos() * CHUNK_SIZE as i32;
+        let page_width = CHUNK_PAGE_SIZE.x * CHUNK_SIZE as i32;
+        let page_depth = CHUNK_PAGE_SIZE.z * CHUNK_SIZE as i32;
+        let bottom_at = |x: i32, z: i32| match x >= 0 && x < page_width && z >= 0 && z < page_depth
+        {
+            true => column_bottoms[(x + z * page_width) as usize],
+            false => MIN_BLOCK_Y,
+        };
+        let mut sources = VecDeque::new();
+        for x in 0..page_width {
+            for z in 0..page_depth {
+                let bottom = bottom_at(x, z);
+                let neighbours_bottom = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                    .iter()
+                    .map(|(dx, dz)| bottom_at(x + dx, z + dz))
+                    .max()
+                    .unwrap();
+                for y in bottom..neighbours_bottom {
+                    sources.push_back(ivec3(min_block.x + x, y, min_block.z + z));
+                }
+            }
+        }
+        sources
+    }
+
+    /* sets the light of emitting blocks, returns their positions */
+    fn fill_block_light(&mut self) -> VecDeque<IVec3> {
+        let mut sources = VecDeque::new();
+        let position = self.position;
+        for chunk_pos in position.iter_chunk_pos().collect::<Vec<_>>() {
+            if !position.has_chunk_pos(chunk_pos) {
+                continue;
+            }
+            let chunk = self.get_chunk_mut(chunk_pos).expect("chunk not in page");
+            if chunk.is_empty() {
+                continue;
+            }
+            let origin = chunk_pos.get_center_block_pos().as_vec();
+            for i in 0..BLOCKS_PER_CHUNK {
+                let emission = chunk.blocks[i].get_light_emission();
+                if emission > 0 {
+                    let offset = U16Vec3::new(
+                        (i % CHUNK_SIZE) as _,
+                        (i / CHUNK_SIZE % CHUNK_SIZE) as _,
+                        (i / (CHUNK_SIZE * CHUNK_SIZE)) as _,
+                    );
+                    let light = chunk.get_light(offset);
+                    chunk.set_light(offset, light.with(LightChannel::Block, emission));
+                    sources.push_back(origin + offset.as_ivec3());
+                }
+            }
+        }
+        sources
+    }
+
+    // fn get_pos_at_index(index: usize) -> ChunkPos {
+    //     ivec3(
+    //         index as i32 % CHUNK_PAGE_SIZE.x,
+    //         index as i32 / CHUNK_PAGE_SIZE.x % CHUNK_PAGE_SIZE.y + MIN_CHUNK_Y,
+    //         index as i32 / CHUNK_PAGE_SIZE.x / CHUNK_PAGE_SIZE.y,
+    //     )
+    //     .into()
+    // }
+
+    // fn fill_from<G>(&mut self, generator: &mut G, page_index: PagePos)
+    // where
+    //     G: WorldGenerator,
+    // {
+    //     info!("Streaming: Filling chunk page {page_index:?}");
+    //     let page_chunk_pos = page_index.0 * CHUNK_PAGE_SIZE as i32;
+    //     self.position = page_index;
+    //     for x in 0..CHUNK_PAGE_SIZE {
+    //         for y in 0..CHUNK_PAGE_SIZE {
+    //             for z in 0..CHUNK_PAGE_SIZE {
+    //                 let chunk_index = self.get_chunk_index(ivec3(x as i32, y as i32, z as i32));
+    //                 let world_chunk_pos = ivec3(
+    //                     page_chunk_pos.x + x as i32,
+    //                     y as i32,
+    //                     page_chunk_pos.y + z as i32,
+    //                 );
+    //                 let chunk = generator.generate(&world_chunk_pos);
+
+    //                 if !chunk.is_empty() {
+    //                     self.content_bounds.add(world_chunk_pos);
+    //                 }
+
+    //                 self.chunks[chunk_index] = chunk;
+    //             }
+    //         }
+    //     }
+    // }
+
+    fn fill_from<G>(&mut self, generator: &mut G, page_pos: PagePos)
+    where
+        G: WorldGenerator,
+    {
+        self.position = page_pos;
+        info!("Streaming: Filling chunk page {page_pos:?}");
+        // let page_chunk_world_offset = Into::<ChunkPos>::into(page_pos).as_vec();
+        for i in 0..NUM_CHUNKS_PER_PAGE {
+            let chunk_offset = PageChunkOffset::from_page_index(i.into());
+            let chunk_world_pos: ChunkPos = page_pos.get_chunk_pos_at(chunk_offset);
+            let chunk = generator.generate(chunk_world_pos);
+            if !chunk.is_empty() {
+                self.content_bounds.add(chunk_world_pos.as_vec());
+            }
+            self.chunks[i] = chunk;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ChunkStreamer<G>
+where
+    G: WorldGenerator,
+{
+    loaded_chunk_pages: Vec<ChunkPage>,
+    chunk_page_pool: Vec<ChunkPage>,
+    generator: G,
+    content_bounds: AABB<IVec3>,
+    last_computed_page_pos: Option<PagePos>,
+    pages_to_load: Vec<PagePos>,
+    relit_chunks: HashSet<ChunkPos>,
+    /* blocks whose light changed during the current propagation */
+    relit_bounds: Option<AABB<IVec3>>,
+}
+
+const PAGE_OFFSETS_PRIORITY: [IVec2; 9] = [
+    ivec2(0, 0),
+    ivec2(0, -1),
+    ivec2(0, 1),
+    ivec2(1, 0),
+    ivec2(-1, 0),
+    ivec2(1, -1),
+    ivec2(1, 1),
+    ivec2(-1, 1),
+    ivec2(-1, -1),
+];
+
+impl<G> ChunkStreamer<G>
+where
+    G: WorldGenerator,
+{
+    pub fn new(generator: G) -> Self {
+        Self {
+            loaded_chunk_pages: Vec::with_capacity(MAX_NUM_PAGES),
+            chunk_page_pool: vec![ChunkPage::default(); MAX_NUM_PAGES],
+            generator,
+            content_bounds: Default::default(),
+            last_computed_page_pos: None,
+            pages_to_load: Vec::with_capacity(MAX_NUM_PAGES),
+            relit_chunks: HashSet::new(),
+            relit_bounds: None,
+        }
+    }
+
+    pub fn get_info(&self) -> String {
+        format!("{} page(s) loaded", self.loaded_chunk_pages.len())
+    }
+
+    pub fn is_chunked_streamed(&self, chunk_pos: ChunkPos) -> bool {
+        self.get_page_for_chunk(chunk_pos).is_some()
+    }
+
+    pub fn get_chunk(&self, chunk_pos: ChunkPos) -> Option<&Chunk> {
+        if let Some(page) = self.get_page_for_chunk(chunk_pos) {
+            page.get_chunk(chunk_pos)
+        } else {
+            None
+        }
+    }
+
+    /* changes a loaded block and updates the light around it, returns false if not loaded */
+    pub fn set_block(&mut self, pos: BlockPos, block: BlockType) -> bool {
+        let chunk_pos: ChunkPos = pos.into();
+        let offset = pos.as_vec() - chunk_pos.get_center_block_pos().as_vec();
+        match self.get_page(chunk_pos.into()) {
+            None => return false,
+            Some(page) => page
+                .get_chunk_mut(chunk_pos)
+                .expect("chunk not in page")
+                .set(offset.as_u16vec3(), block),
+        }
+        self.add_relit(pos.as_vec());
+        update_light(self, pos.as_vec(), block);
+        self.mark_relit_chunks();
+        true
+    }
+
+    /* chunks whose blocks or light changed since the last call, their meshes need an update */
+    pub fn take_relit_chunks(&mut self) -> Vec<ChunkPos> {
+        self.relit_chunks.drain().collect()
+    }
+
+    pub fn tick_streaming(&mut self, player_chunk_pos: ChunkPos) -> i32 {
+        let player_page_index: PagePos = player_chunk_pos.into();
+        if self.last_computed_page_pos != Some(player_page_index) {
+            self.last_computed_page_pos = Some(player_page_index);
+            self.on_new_page_index(player_page_index, player_chunk_pos);
+        }
+        let mut new_pages_loaded = 0;
+        if self.pages_to_load.len() > 0 {
+            if self.chunk_page_pool.len() < self.pages_to_load.len() {
+                info!("Streaming: Pool size too small - trying to free some pages");
+                self.free_some_pages(PAGE_LOAD_PER_FRAME, player_chunk_pos);
+            }
+            for _ in 0..PAGE_LOAD_PER_FRAME {
+                if let Some(page) = self.pages_to_load.pop() {
+                    self.load_page(page);
+                    new_pages_loaded += 1;
+                }
+            }
+        }
+        new_pages_loaded
+    }
+
+    fn on_new_page_index(&mut self, page_index: PagePos, player_chunk_pos: ChunkPos) {
+        let page_offsets: &[IVec2] = &PAGE_OFFSETS_PRIORITY;
+        // let page_offsets: &[IVec2] = &[ivec2(0, 0)];
+        let new_pages_to_load: Vec<PagePos> = page_offsets
+            .iter()
+            .map(|offset| {
+                ivec2(
+                    page_index.as_vec().x + offset.x,
+                    page_index.as_vec().y + offset.y,
+                )
+                .into()
+            })
+            .filter(|index| self.get_page_ref(*index).is_none())
+            .collect();
+        self.pages_to_load.extend(new_pages_to_load);
+        // sort with best last so that we can pop
+        self.pages_to_load.sort_by(|a, b| {
+            b.get_center_chunk_pos()
+                .distance_squared(player_chunk_pos)
+                .cmp(&a.get_center_chunk_pos().distance_squared(player_chunk_pos))
+        });
+    }
+
+    fn free_some_pages(&mut self, num_pages: usize, player_chunk_pos: ChunkPos) {
+        // Iterate over the loaded_chunk_pages vector in reverse to remove items while iterating
+        let player_chunk_pos_vec = player_chunk_pos.as_vec();
+        let indices: Vec<usize> = self
+            .loaded_chunk_pages
+            .iter()
+            .map(|page| {
+                page.position
+                    .get_center_chunk_pos()
+                    .get_center_block_pos()
+                    .as_vec()
+                    .distance_squared(player_chunk_pos_vec)
+            })
+            .enumerate()
+            .sorted_by_key(|(_, pos_a)| *pos_a)
+            .take(num_pages)
+            .map(|(index, _)| index)
+            .sorted_by_key(|i| -(*i as i32))
+            .collect();
+        if indices.len() < num_pages {
+            warn!("Not enough pages to free");
+        } else {
+            debug_assert!(
+                indices.len() < 2 || indices[0] > indices[1],
+                "Pages should be in descending order"
+            );
+            for i in indices {
+                info!("Freeing page {:?}", self.loaded_chunk_pages[i].position);
+                let removed_page = self.loaded_chunk_pages.remove(i);
+                // TODO: free stuff in uloaded page
+                self.chunk_page_pool.push(removed_page);
+            }
+            self.update_bounds();
+        }
+    }
+
+    fn get_page_for_chunk(&self, chunk_pos: ChunkPos) -> Option<&ChunkPage> {
+        let page_index: PagePos = chunk_pos.into();
+        self.get_page_ref(page_index)
+    }
+
+    fn get_page_ref(&self, page_index: PagePos) -> Option<&ChunkPage> {
+        self.loaded_chunk_pages
+            .iter()
+            .find(|p| p.position == page_index)
+    }
+
+    fn get_page(&mut self, page_index: PagePos) -> Option<&mut ChunkPage> {
+        self.loaded_chunk_pages
+            .iter_mut()
+            .find(|p| p.position == page_index)
+    }
+
+    fn load_page(&mut self, page_index: PagePos)
+    where
+        G: WorldGenerator,
+    {
+        let mut page = self.get_pool_page();
+        page.fill_from(&mut self.generator, page_index);
+        self.loaded_chunk_pages.push(page);
+        self.light_page(page_index);
+
+        self.update_bounds();
+    }
+
+    fn light_page(&mut self, page_pos: PagePos) {
+        let page = self.get_page(page_pos).expect("page not loaded");
+        let column_bottoms = page.fill_sky_light();
+        let mut sky_sources = page.get_sky_light_sources(&column_bottoms);
+        let mut block_sources = page.fill_block_light();
+        let (border_sky_sources, border_block_sources) = self.get_border_light_sources(page_pos);
+        sky_sources.extend(border_sky_sources);
+        block_sources.extend(border_block_sources);
+        propagate_light(self, LightChannel::Sky, sky_sources);
+        propagate_light(self, LightChannel::Block, block_sources);
+        self.mark_relit_chunks();
+    }
+
+    /* blocks on either side of the page borders that should spread light to the other side */
+    fn get_border_light_sources(
+        &mut self,
+        page_pos: PagePos,
+    ) -> (VecDeque<IVec3>, VecDeque<IVec3>) {
+        let mut sky_sources = VecDeque::new();
+        let mut block_sources = VecDeque::new();
+        let page_width = CHUNK_PAGE_SIZE.x * CHUNK_SIZE as i32;
+        let min_chunk = page_pos.get_center_chunk_pos().as_vec() + MIN_CHUNK_PAGE_OFFSET;
+        let min_block = min_chunk * CHUNK_SIZE as i32;
+        let sides = [
+            (IVec3::X, IVec3::Z, ivec3(page_width - 1, 0, 0)),
+            (IVec3::NEG_X, IVec3::Z, IVec3::ZERO),
+            (IVec3::Z, IVec3::X, ivec3(0, 0, page_width - 1)),
+            (IVec3::NEG_Z, IVec3::X, IVec3::ZERO),
+        ];
+        for (side, tangent, start) in sides {
+            let neighbour_page: PagePos = (page_pos.as_vec() + ivec2(side.x, side.z)).into();
+            if self.get_page_ref(neighbour_page).is_none() {
+                continue;
+            }
+            for chunk_t in 0..CHUNK_PAGE_SIZE.x {
+                let segment_start = min_block + start + tangent * chunk_t * CHUNK_SIZE as i32;
+                for chunk_y in MIN_CHUNK_Y..MAX_CHUNK_Y {
+                    let segment_start = segment_start.with_y(chunk_y * CHUNK_SIZE as i32);
+                    let inside_chunk: ChunkPos = Into::<BlockPos>::into(segment_start).into();
+                    let outside_chunk: ChunkPos =
+                        Into::<BlockPos>::into(segment_start + side).into();
+                    // faces of the neighbour page facing this one have to be drawn again
+                    self.relit_chunks.insert(outside_chunk);
+                    // evenly lit chunks, like the sky or solid ground, have nothing to spread
+                    let inside_uniform = self.get_uniform_light(inside_chunk);
+                    if inside_uniform.is_some()
+                        && inside_uniform == self.get_uniform_light(outside_chunk)
+                    {
+                        continue;
+                    }
+                    for t in 0..CHUNK_SIZE as i32 {
+                        for y in 0..CHUNK_SIZE as i32 {
+                            let inside = segment_start + tangent * t + IVec3::Y * y;
+                            let outside = inside + side;
+                            let (inside_light, outside_light) =
+                                (self.get_light(inside), self.get_light(outside));
+                            for (channel, sources) in [
+                                (LightChannel::Sky, &mut sky_sources),
+                                (LightChannel::Block, &mut block_sources),
+                            ] {
+                                let (inside_level, outside_level) =
+                                    (inside_light.get(channel), outside_light.get(channel));
+                                if inside_level > outside_level + 1 {
+                                    sources.push_back(inside);
+                                } else if outside_level > inside_level + 1 {
+                                    sources.push_back(outside);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        (sky_sources, block_sources)
+    }
+
+    fn get_chunk_with_offset(&self, pos: IVec3) -> Option<(&Chunk, U16Vec3)> {
+        if !is_in_world(pos) {
+            return None;
+        }
+        let chunk_pos: ChunkPos = Into::<BlockPos>::into(pos).into();
+        let offset = pos - chunk_pos.get_center_block_pos().as_vec();
+        self.get_chunk(chunk_pos)
+            .map(|chunk| (chunk, offset.as_u16vec3()))
+    }
+
+    /* the light of a chunk, if every block of it has the same */
+    fn get_uniform_light(&self, chunk_pos: ChunkPos) -> Option<LightLevel> {
+        match self.get_chunk(chunk_pos)?.light {
+            ChunkLight::Uniform(light) => Some(light),
+            ChunkLight::PerBlock(_) => None,
+        }
+    }
+
+    fn add_relit(&mut self, pos: IVec3) {
+        match &mut self.relit_bounds {
+            Some(bounds) => bounds.add(pos),
+            None => self.relit_bounds = Some(AABB { min: pos, max: pos }),
+        }
+    }
+
+    /* marks the chunks around the blocks changed since the last call, a change can alter faces
+     * of the neighbouring chunks too */
+    fn mark_relit_chunks(&mut self) {
+        let Some(bounds) = self.relit_bounds.take() else {
+            return;
+        };
+        let chunk_at = |pos: IVec3| -> IVec3 {
+            let pos = pos.with_y(pos.y.clamp(MIN_BLOCK_Y, MAX_BLOCK_Y - 1));
+            let block_pos: BlockPos = pos.into();
+            Into::<ChunkPos>::into(block_pos).as_vec()
+        };
+        let (min, max) = (
+            chunk_at(bounds.min - IVec3::ONE),
+            chunk_at(bounds.max + IVec3::ONE),
+        );
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    self.relit_chunks.insert(ivec3(x, y, z).into());
+                }
+            }
+        }
+    }
+
+    fn update_bounds(&mut self) {
+        self.content_bounds = Default::default();
+        for page in &self.loaded_chunk_pages {
+            self.content_bounds.add(page.content_bounds.min);
+            self.content_bounds.add(page.content_bounds.max);
+        }
+        info!("Streaming: new content bounds: {:?}", self.content_bounds);
+    }
+
+    fn get_pool_page(&mut self) -> ChunkPage {
+        self.chunk_page_pool.pop().expect("Pool is empty")
+    }
+}
+
+impl<G> LightStorage for ChunkStreamer<G>
+where
+    G: WorldGenerator,
+{
+    fn get_block(&self, pos: IVec3) -> Option<BlockType> {
+        self.get_chunk_with_offset(pos)
+            .map(|(chunk, offset)| chunk.get_block(offset))
+    }
+
+    fn get_light(&self, pos: IVec3) -> LightLevel {
+        self.get_chunk_with_offset(pos)
+            .map(|(chunk, offset)| chunk.get_light(offset))
+            .unwrap_or_default()
+    }
+
+    fn set_light(&mut self, pos: IVec3, light: LightLevel) {
+        if !is_in_world(pos) {
+            return;
+        }
+        let chunk_pos: ChunkPos = Into::<BlockPos>::into(pos).into();
+        let offset = pos - chunk_pos.get_center_block_pos().as_vec();
+        if let Some(page) = self.get_page(chunk_pos.into()) {
+            page.get_chunk_mut(chunk_pos)
+                .expect("chunk not in page")
+                .set_light(offset.as_u16vec3(), light);
+            self.add_relit(pos);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::VecDeque;
+
+use glam::IVec3;
+
+use super::{BlockType, BLOCKS_PER_CHUNK, MAX_BLOCK_Y, MIN_BLOCK_Y};
+
+pub const MAX_LIGHT: u8 = 15;
+
+const NEIGHBOURS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightChannel {
+    Sky,
+    Block,
+}
+
+/* skylight in the high nibble, block light in the low one */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LightLevel(u8);
+
+impl LightLevel {
+    pub const DARK: LightLevel = LightLevel(0);
+    pub const FULL_SKY: LightLevel = LightLevel(MAX_LIGHT << 4);
+
+    pub fn new(sky: u8, block: u8) -> Self {
+        debug_assert!(sky <= MAX_LIGHT && block <= MAX_LIGHT);
+        Self(sky << 4 | block)
+    }
+
+    pub fn sky(&self) -> u8 {
+        self.0 >> 4
+    }
+
+    pub fn block(&self) -> u8 {
+        self.0 & MAX_LIGHT
+    }
+
+    pub fn get(&self, channel: LightChannel) -> u8 {
+        match channel {
+            LightChannel::Sky => self.sky(),
+            LightChannel::Block => self.block(),
+        }
+    }
+
+    pub fn with(&self, channel: LightChannel, level: u8) -> Self {
+        match channel {
+            LightChannel::Sky => Self::new(level, self.block()),
+            LightChannel::Block => Self::new(self.sky(), level),
+        }
+    }
+}
+
+/* most chunks are fully skylit or fully dark, light is only stored per block when needed */
+#[derive(Debug, Clone)]
+pub enum ChunkLight {
+    Uniform(LightLevel),
+    PerBlock(Box<[LightLevel; BLOCKS_PER_CHUNK]>),
+}
+
+impl Default for ChunkLight {
+    fn default() -> Self {
+        ChunkLight::Uniform(LightLevel::DARK)
+    }
+}
+
+impl ChunkLight {
+    pub fn get(&self, index: usize) -> LightLevel {
+        match self {
+            ChunkLight::Uniform(light) => *light,
+            ChunkLight::PerBlock(lights) => lights[index],
+        }
+    }
+
+    pub fn set(&mut self, index: usize, light: LightLevel) {
+        match self {
+            ChunkLight::Uniform(current) if *current == light => {}
+            ChunkLight::Uniform(current) => {
+                let mut lights = Box::new([*current; BLOCKS_PER_CHUNK]);
+                lights[index] = light;
+                *self = ChunkLight::PerBlock(lights);
+            }
+            ChunkLight::PerBlock(lights) => lights[index] = light,
+        }
+    }
+}
+
+/* anything holding blocks and their light, addressed by world block positions */
+pub trait LightStorage {
+    /* None if the block isn't loaded */
+    fn get_block(&self, pos: IVec3) -> Option<BlockType>;
+    fn get_light(&self, pos: IVec3) -> LightLevel;
+    fn set_light(&mut self, pos: IVec3, light: LightLevel);
+}
+
+pub fn is_in_world(pos: IVec3) -> bool {
+    pos.y >= MIN_BLOCK_Y && pos.y < MAX_BLOCK_Y
+}
+
+/* full skylight travels down without getting dimmer, any other light loses a level per block */
+fn propagated_level(channel: LightChannel, level: u8, direction: IVec3) -> u8 {
+    if channel == LightChannel::Sky && level == MAX_LIGHT && direction == IVec3::NEG_Y {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(1)
+    }
+}
+
+fn lets_light_through<S: LightStorage>(storage: &S, pos: IVec3) -> bool {
+    is_in_world(pos)
+        && storage
+            .get_block(pos)
+            .is_some_and(|block| block.is_transparent_to_light())
+}
+
+/* flood fills light from the queued positions */
+pub fn propagate_light<S: LightStorage>(
+    storage: &mut S,
+    channel: LightChannel,
+    mut queue: VecDeque<IVec3>,
+) {
+    while let Some(pos) = queue.pop_front() {
+        let level = storage.get_light(pos).get(channel);
+        if level <= 1 {
+            continue;
+        }
+        for direction in NEIGHBOURS {
+            let neighbour = pos + direction;
+            if !lets_light_through(storage, neighbour) {
+                continue;
+            }
+            let new_level = propagated_level(channel, level, direction);
+            let light = storage.get_light(neighbour);
+            if light.get(channel) < new_level {
+                storage.set_light(neighbour, light.with(channel, new_level));
+                queue.push_back(neighbour);
+            }
+        }
+    }
+}
+
+/* darkens everything lit from pos, returns the positions that need to be propagated again */
+pub fn remove_light<S: LightStorage>(
+    storage: &mut S,
+    channel: LightChannel,
+    pos: IVec3,
+) -> VecDeque<IVec3> {
+    let mut relight = VecDeque::new();
+    let light = storage.get_light(pos);
+    storage.set_light(pos, light.with(channel, 0));
+    let mut queue = VecDeque::from([(pos, light.get(channel))]);
+    while let Some((pos, level)) = queue.pop_front() {
+        for direction in NEIGHBOURS {
+            let neighbour = pos + direction;
+            if !is_in_world(neighbour) || storage.get_block(neighbour).is_none() {
+                continue;
+            }
+            let neighbour_light = storage.get_light(neighbour);
+            let neighbour_level = neighbour_light.get(channel);
+            if neighbour_level == 0 {
+                continue;
+            }
+            // full skylight below a removed source is as bright as the source itself
+            let undimmed = level > 0 && propagated_level(channel, level, direction) == level;
+            if neighbour_level < level || undimmed {
+                storage.set_light(neighbour, neighbour_light.with(channel, 0));
+                queue.push_back((neighbour, neighbour_level));
+            } else {
+                relight.push_back(neighbour);
+            }
+        }
+    }
+    relight
+}
+
+/* incrementally relights around pos after its block changed */
+pub fn update_light<S: LightStorage>(storage: &mut S, pos: IVec3, block: BlockType) {
+    for channel in [LightChannel::Sky, LightChannel::Block] {
+        let mut relight = remove_light(storage, channel, pos);
+        let emission = block.get_light_emission();
+        if channel == LightChannel::Block && emission > 0 {
+            let light = storage.get_light(pos);
+            storage.set_light(pos, light.with(channel, emission));
+            relight.push_back(pos);
+        }
+        if block.is_transparent_to_light() {
+            // light around can now flow through
+            relight.extend(NEIGHBOURS.iter().map(|direction| pos + *direction));
+        }
+        propagate_light(storage, channel, relight);
+    }
+}
@@ -0,0 +1,17 @@
+mod blocktextures;
+mod chunk;
+mod chunkstreaming;
+mod light;
+mod position;
+mod side;
+mod testworldgenerator;
+mod worldgenerator;
+
+pub use blocktextures::*;
+pub use chunk::*;
+pub use chunkstreaming::*;
+pub use light::*;
+pub use position::*;
+pub use side::*;
+pub use testworldgenerator::*;
+pub use worldgenerator::*;
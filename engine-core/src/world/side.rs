@@ -0,0 +1,28 @@
+use glam::Vec3;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Side {
+    Top = 0,
+    Bottom,
+    Front,
+    Back,
+    Right,
+    Left,
+}
+
+pub const SIDE_NORMS: [Vec3; 6] = [
+    Vec3::Y,
+    Vec3::NEG_Y,
+    Vec3::Z,
+    Vec3::NEG_Z,
+    Vec3::X,
+    Vec3::NEG_X,
+];
+
+type Norm = Vec3;
+
+impl Into<Norm> for Side {
+    fn into(self) -> Norm {
+        SIDE_NORMS[self as usize]
+    }
+}
@@ -1,6 +1,5 @@
 use fastrand::Rng;
-use glam::{IVec3, U16Vec3};
-use log::info;
+use glam::U16Vec3;
 
 use super::{BlockType, Chunk, ChunkPos, WorldGenerator, CHUNK_SIZE};
 
@@ -12,6 +11,7 @@ pub struct TestGenerator {
 fn dirt_with_grass_on_top(rng: &mut Rng) -> Chunk {
     let min_y = 14;
     let max_y = 16;
+    let water_y = min_y as u16;
     let mut res = Chunk::new(false);
     for x in 0..CHUNK_SIZE {
         for z in 0..CHUNK_SIZE {
@@ -24,6 +24,9 @@ fn dirt_with_grass_on_top(rng: &mut Rng) -> Chunk {
                 };
                 res.set(U16Vec3::new(x as _, y, z as _), block);
             }
+            if max_y <= water_y {
+                res.set(U16Vec3::new(x as _, water_y, z as _), BlockType::Water);
+            }
         }
     }
     res